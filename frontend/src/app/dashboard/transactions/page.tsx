@@ -26,14 +26,23 @@ const TransactionsPage = () => {
   async function getTransaction() {
     try {
       setIsLoading(true);
-      const response = await fetch(
-        `${process.env.NEXT_PUBLIC_API_BASE_URL}/all_groups`
-      );
-      if (!response.ok) {
-        throw new Error("Failed to fetch transaction");
-      }
-      const data = await response.json();
-      setTransaction(data);
+      // follow next_cursor until the last page so no group is dropped
+      const groups: GroupTransactionData[] = [];
+      let cursor: string | null = null;
+      do {
+        const params = new URLSearchParams({ limit: "500" });
+        if (cursor) params.set("cursor", cursor);
+        const response = await fetch(
+          `${process.env.NEXT_PUBLIC_API_BASE_URL}/v1/groups?${params}`
+        );
+        if (!response.ok) {
+          throw new Error("Failed to fetch transaction");
+        }
+        const data = await response.json();
+        groups.push(...data.items);
+        cursor = data.next_cursor;
+      } while (cursor);
+      setTransaction(groups);
     } catch (error) {
       console.error("Error fetching transactions:", error);
     } finally {
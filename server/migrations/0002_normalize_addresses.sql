@@ -0,0 +1,88 @@
+-- normalize every stored address / tx hash to 0x + 64 lowercase hex digits,
+-- the form produced by `util::address::Address`. Values that aren't felt hex
+-- strings are left untouched.
+CREATE OR REPLACE FUNCTION normalize_felt_hex(value TEXT)
+RETURNS TEXT AS $$
+    SELECT CASE
+        WHEN value ~* '^0x[0-9a-f]{1,64}$' THEN '0x' || lpad(lower(substr(value, 3)), 64, '0')
+        ELSE value
+    END
+$$ LANGUAGE sql IMMUTABLE;
+
+-- let key rewrites on groups / payments flow into the referencing rows
+ALTER TABLE group_tx_hashes
+    DROP CONSTRAINT fk_group_tx_hash,
+    ADD CONSTRAINT fk_group_tx_hash
+        FOREIGN KEY (group_address)
+        REFERENCES groups (group_address)
+        ON DELETE CASCADE ON UPDATE CASCADE;
+
+ALTER TABLE payments
+    DROP CONSTRAINT fk_group_payment,
+    ADD CONSTRAINT fk_group_payment
+        FOREIGN KEY (group_address)
+        REFERENCES groups (group_address)
+        ON DELETE CASCADE ON UPDATE CASCADE;
+
+ALTER TABLE group_token_history
+    DROP CONSTRAINT fk_group_balance,
+    ADD CONSTRAINT fk_group_balance
+        FOREIGN KEY (group_address)
+        REFERENCES groups (group_address)
+        ON DELETE CASCADE ON UPDATE CASCADE;
+
+ALTER TABLE group_members
+    DROP CONSTRAINT fk_group_member,
+    ADD CONSTRAINT fk_group_member
+        FOREIGN KEY (group_address)
+        REFERENCES groups (group_address)
+        ON DELETE CASCADE ON UPDATE CASCADE;
+
+ALTER TABLE distributions_history
+    DROP CONSTRAINT fk_payment_distribution,
+    ADD CONSTRAINT fk_payment_distribution
+        FOREIGN KEY (tx_hash)
+        REFERENCES payments (tx_hash)
+        ON DELETE SET NULL ON UPDATE CASCADE,
+    DROP CONSTRAINT fk_group_distribution,
+    ADD CONSTRAINT fk_group_distribution
+        FOREIGN KEY (group_address)
+        REFERENCES groups (group_address)
+        ON DELETE SET NULL ON UPDATE CASCADE;
+
+-- keys first; the cascades rewrite group_address / tx_hash in child tables
+UPDATE groups SET
+    group_address = normalize_felt_hex(group_address),
+    created_by = normalize_felt_hex(created_by)
+WHERE group_address <> normalize_felt_hex(group_address)
+   OR created_by <> normalize_felt_hex(created_by);
+
+UPDATE payments SET
+    tx_hash = normalize_felt_hex(tx_hash),
+    token_address = normalize_felt_hex(token_address)
+WHERE tx_hash <> normalize_felt_hex(tx_hash)
+   OR token_address <> normalize_felt_hex(token_address);
+
+UPDATE group_tx_hashes SET
+    from_address = normalize_felt_hex(from_address),
+    tx_hash = normalize_felt_hex(tx_hash),
+    token_address = normalize_felt_hex(token_address)
+WHERE from_address <> normalize_felt_hex(from_address)
+   OR tx_hash <> normalize_felt_hex(tx_hash)
+   OR token_address <> normalize_felt_hex(token_address);
+
+UPDATE group_token_history SET
+    token_address = normalize_felt_hex(token_address)
+WHERE token_address <> normalize_felt_hex(token_address);
+
+UPDATE group_members SET
+    member_address = normalize_felt_hex(member_address)
+WHERE member_address <> normalize_felt_hex(member_address);
+
+UPDATE distributions_history SET
+    member_address = normalize_felt_hex(member_address),
+    token_address = normalize_felt_hex(token_address)
+WHERE member_address <> normalize_felt_hex(member_address)
+   OR token_address <> normalize_felt_hex(token_address);
+
+DROP FUNCTION normalize_felt_hex(TEXT);
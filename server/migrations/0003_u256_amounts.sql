@@ -0,0 +1,16 @@
+-- u256 tops out at 78 decimal digits; NUMERIC(70,0) could not hold the full range
+ALTER TABLE group_tx_hashes
+    ALTER COLUMN token_amount TYPE NUMERIC(78,0),
+    ADD CONSTRAINT chk_group_tx_hashes_token_amount CHECK (token_amount >= 0);
+
+ALTER TABLE payments
+    ALTER COLUMN amount TYPE NUMERIC(78,0),
+    ADD CONSTRAINT chk_payments_amount CHECK (amount >= 0);
+
+ALTER TABLE group_token_history
+    ALTER COLUMN amount TYPE NUMERIC(78,0),
+    ADD CONSTRAINT chk_group_token_history_amount CHECK (amount >= 0);
+
+ALTER TABLE distributions_history
+    ALTER COLUMN token_amount TYPE NUMERIC(78,0),
+    ADD CONSTRAINT chk_distributions_history_token_amount CHECK (token_amount >= 0);
@@ -0,0 +1,13 @@
+-- composite (sort column, key) indexes backing keyset pagination on the list
+-- endpoints; they supersede the single-column timestamp indexes
+DROP INDEX IF EXISTS idx_groupsx_created_at;
+CREATE INDEX idx_groups_created_at_key ON groups (created_at, group_address);
+CREATE INDEX idx_groups_name_key ON groups (group_name, group_address);
+
+DROP INDEX IF EXISTS idx_group_payments_paid_at;
+CREATE INDEX idx_payments_paid_at_key ON payments (paid_at, tx_hash);
+CREATE INDEX idx_payments_group_paid_at_key ON payments (group_address, paid_at, tx_hash);
+
+CREATE INDEX idx_distributions_history_sent_at_key ON distributions_history (sent_at, id);
+CREATE INDEX idx_distributions_history_member_sent_at_key
+    ON distributions_history (member_address, sent_at, id);
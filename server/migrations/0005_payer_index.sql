@@ -0,0 +1,2 @@
+-- payer history looks transfers up by sender
+CREATE INDEX idx_group_tx_hashes_from ON group_tx_hashes (from_address, group_address);
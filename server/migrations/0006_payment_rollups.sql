@@ -0,0 +1,52 @@
+-- rollups of `payments`, maintained in the same transaction as each insert
+-- (see `libs::rollup`) and rebuildable with `server rebuild-rollups`.
+-- Volumes are sums of u256 amounts, so they are unbounded NUMERIC.
+
+-- per group, per token, per UTC day
+CREATE TABLE group_token_daily_rollups (
+    group_address VARCHAR(66) NOT NULL,
+    token_address VARCHAR(66) NOT NULL,
+    day DATE NOT NULL,
+    volume NUMERIC NOT NULL DEFAULT 0 CHECK (volume >= 0),
+    payment_count BIGINT NOT NULL DEFAULT 0,
+
+    PRIMARY KEY (group_address, token_address, day),
+
+    CONSTRAINT fk_group_daily_rollup
+        FOREIGN KEY (group_address)
+        REFERENCES groups (group_address)
+        ON DELETE CASCADE ON UPDATE CASCADE
+);
+
+CREATE INDEX idx_group_token_daily_rollups_day ON group_token_daily_rollups (day);
+
+-- global, per token
+CREATE TABLE token_rollups (
+    token_address VARCHAR(66) PRIMARY KEY,
+    volume NUMERIC NOT NULL DEFAULT 0 CHECK (volume >= 0),
+    payment_count BIGINT NOT NULL DEFAULT 0,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- global counters; a single row
+CREATE TABLE payment_rollup_totals (
+    id BOOLEAN PRIMARY KEY DEFAULT TRUE CHECK (id),
+    group_count BIGINT NOT NULL DEFAULT 0,
+    payment_count BIGINT NOT NULL DEFAULT 0,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- backfill from existing payments
+INSERT INTO group_token_daily_rollups (group_address, token_address, day, volume, payment_count)
+SELECT group_address, token_address, (paid_at AT TIME ZONE 'UTC')::date, SUM(amount), COUNT(*)
+FROM payments
+GROUP BY 1, 2, 3;
+
+INSERT INTO token_rollups (token_address, volume, payment_count)
+SELECT token_address, SUM(amount), COUNT(*)
+FROM payments
+GROUP BY token_address;
+
+INSERT INTO payment_rollup_totals (group_count, payment_count)
+SELECT COUNT(DISTINCT group_address), COUNT(*)
+FROM payments;
@@ -0,0 +1,13 @@
+-- fiat price of one whole token, as reported by a price source at `priced_at`;
+-- amounts are valued with the latest price at or before their time
+CREATE TABLE token_prices (
+    token_address VARCHAR(66) NOT NULL,
+    currency VARCHAR(3) NOT NULL,
+    priced_at TIMESTAMPTZ NOT NULL,
+    price NUMERIC NOT NULL CHECK (price >= 0),
+    decimals SMALLINT NOT NULL,
+    source VARCHAR(32) NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    PRIMARY KEY (token_address, currency, priced_at)
+);
@@ -0,0 +1,34 @@
+-- payments whose distributions have been checked against the group's shares
+CREATE TABLE payment_audits (
+    tx_hash VARCHAR(66) PRIMARY KEY,
+    finding_count INTEGER NOT NULL,
+    audited_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT fk_payment_audit
+        FOREIGN KEY (tx_hash)
+        REFERENCES payments (tx_hash)
+        ON DELETE CASCADE
+);
+
+-- one row per discrepancy; replaced whenever the payment is re-audited
+CREATE TABLE distribution_audit_findings (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    tx_hash VARCHAR(66) NOT NULL,
+    group_address VARCHAR(66) NOT NULL,
+    token_address VARCHAR(66) NOT NULL,
+    member_address VARCHAR(66),
+    kind VARCHAR(32) NOT NULL,
+    severity VARCHAR(16) NOT NULL,
+    expected NUMERIC(78,0),
+    actual NUMERIC(78,0),
+    detected_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT fk_finding_payment
+        FOREIGN KEY (tx_hash)
+        REFERENCES payments (tx_hash)
+        ON DELETE CASCADE
+);
+
+CREATE INDEX idx_audit_findings_tx ON distribution_audit_findings (tx_hash);
+CREATE INDEX idx_audit_findings_group ON distribution_audit_findings (group_address, detected_at);
+CREATE INDEX idx_audit_findings_detected ON distribution_audit_findings (detected_at, id);
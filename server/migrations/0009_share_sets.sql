@@ -0,0 +1,58 @@
+-- versioned member shares; a version applies from valid_from until valid_to,
+-- the current one has no valid_to
+CREATE TABLE group_share_sets (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    group_address VARCHAR(66) NOT NULL,
+    version INTEGER NOT NULL,
+    valid_from TIMESTAMPTZ NOT NULL,
+    valid_to TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT fk_group_share_set
+        FOREIGN KEY (group_address)
+        REFERENCES groups (group_address)
+        ON DELETE CASCADE ON UPDATE CASCADE,
+
+    CONSTRAINT unique_group_share_set_version UNIQUE (group_address, version),
+    CONSTRAINT share_set_validity CHECK (valid_to IS NULL OR valid_to > valid_from)
+);
+
+CREATE UNIQUE INDEX idx_group_share_sets_current ON group_share_sets (group_address) WHERE valid_to IS NULL;
+CREATE INDEX idx_group_share_sets_validity ON group_share_sets (group_address, valid_from);
+
+CREATE TABLE group_share_set_members (
+    share_set_id UUID NOT NULL,
+    member_address VARCHAR(66) NOT NULL,
+    member_percentage NUMERIC(5,2) NOT NULL,
+
+    PRIMARY KEY (share_set_id, member_address),
+
+    CONSTRAINT fk_share_set_member
+        FOREIGN KEY (share_set_id)
+        REFERENCES group_share_sets (id)
+        ON DELETE CASCADE
+);
+
+-- the share set a payment was split by
+ALTER TABLE payments ADD COLUMN share_set_id UUID
+    REFERENCES group_share_sets (id) ON DELETE SET NULL;
+
+-- backfill: the members known so far become version 1, valid since the group
+-- (or its first member) was created
+INSERT INTO group_share_sets (group_address, version, valid_from)
+SELECT g.group_address, 1, LEAST(g.created_at, MIN(gm.added_at))
+FROM groups g
+JOIN group_members gm ON gm.group_address = g.group_address
+WHERE gm.is_active
+GROUP BY g.group_address, g.created_at;
+
+INSERT INTO group_share_set_members (share_set_id, member_address, member_percentage)
+SELECT s.id, gm.member_address, gm.member_percentage
+FROM group_share_sets s
+JOIN group_members gm ON gm.group_address = s.group_address
+WHERE gm.is_active;
+
+UPDATE payments p
+SET share_set_id = s.id
+FROM group_share_sets s
+WHERE s.group_address = p.group_address AND s.valid_from <= p.paid_at;
@@ -0,0 +1,57 @@
+-- mirror of the contract's request_group_update flow: a proposal is pending
+-- until GroupUpdated applies it, or superseded by a newer request
+CREATE TABLE group_update_proposals (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    group_address VARCHAR(66) NOT NULL,
+    request_tx_hash VARCHAR(66) NOT NULL UNIQUE,
+    requester VARCHAR(66) NOT NULL,
+    new_name VARCHAR(100) NOT NULL,
+    status VARCHAR(16) NOT NULL DEFAULT 'pending',
+    approval_count INTEGER NOT NULL DEFAULT 0,
+    total_members INTEGER NOT NULL,
+    requested_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    resolved_at TIMESTAMPTZ,
+    update_tx_hash VARCHAR(66) UNIQUE,
+    share_set_id UUID REFERENCES group_share_sets (id) ON DELETE SET NULL,
+
+    CONSTRAINT fk_group_update_proposal
+        FOREIGN KEY (group_address)
+        REFERENCES groups (group_address)
+        ON DELETE CASCADE ON UPDATE CASCADE,
+
+    CONSTRAINT group_update_proposal_status
+        CHECK (status IN ('pending', 'completed', 'superseded'))
+);
+
+-- the contract keeps a single pending request per group
+CREATE UNIQUE INDEX idx_group_update_proposals_pending
+    ON group_update_proposals (group_address) WHERE status = 'pending';
+CREATE INDEX idx_group_update_proposals_group
+    ON group_update_proposals (group_address, requested_at DESC);
+
+CREATE TABLE group_update_proposal_members (
+    proposal_id UUID NOT NULL,
+    member_address VARCHAR(66) NOT NULL,
+    member_percentage NUMERIC(5,2) NOT NULL,
+
+    PRIMARY KEY (proposal_id, member_address),
+
+    CONSTRAINT fk_proposal_member
+        FOREIGN KEY (proposal_id)
+        REFERENCES group_update_proposals (id)
+        ON DELETE CASCADE
+);
+
+CREATE TABLE group_update_approvals (
+    proposal_id UUID NOT NULL,
+    approver VARCHAR(66) NOT NULL,
+    tx_hash VARCHAR(66) NOT NULL UNIQUE,
+    approved_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    PRIMARY KEY (proposal_id, approver),
+
+    CONSTRAINT fk_proposal_approval
+        FOREIGN KEY (proposal_id)
+        REFERENCES group_update_proposals (id)
+        ON DELETE CASCADE
+);
@@ -0,0 +1,45 @@
+-- payer-supplied note on a transfer, used to match it to an invoice
+ALTER TABLE group_tx_hashes ADD COLUMN memo VARCHAR(140);
+
+-- bills payable into a group. `overdue` is not stored: an open or partially
+-- paid invoice past due_at reads as overdue
+CREATE TABLE invoices (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    public_id VARCHAR(16) NOT NULL UNIQUE,
+    group_address VARCHAR(66) NOT NULL,
+    token_address VARCHAR(66) NOT NULL,
+    amount NUMERIC(78,0) NOT NULL,
+    paid_amount NUMERIC(78,0) NOT NULL DEFAULT 0,
+    due_at TIMESTAMPTZ NOT NULL,
+    reference VARCHAR(140) NOT NULL,
+    payer_address VARCHAR(66),
+    status VARCHAR(16) NOT NULL DEFAULT 'open',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    paid_at TIMESTAMPTZ,
+    cancelled_at TIMESTAMPTZ,
+
+    CONSTRAINT fk_group_invoice
+        FOREIGN KEY (group_address)
+        REFERENCES groups (group_address)
+        ON DELETE CASCADE ON UPDATE CASCADE,
+
+    CONSTRAINT chk_invoices_amount CHECK (amount > 0 AND paid_amount >= 0),
+    CONSTRAINT invoice_status CHECK (status IN ('open', 'partially_paid', 'paid', 'cancelled'))
+);
+
+CREATE INDEX idx_invoices_group_due ON invoices (group_address, due_at);
+CREATE INDEX idx_invoices_unpaid ON invoices (group_address, token_address)
+    WHERE status IN ('open', 'partially_paid');
+
+-- transfers applied to an invoice; a transfer pays at most one invoice
+CREATE TABLE invoice_payments (
+    invoice_id UUID NOT NULL REFERENCES invoices (id) ON DELETE CASCADE,
+    tx_hash VARCHAR(66) NOT NULL UNIQUE REFERENCES group_tx_hashes (tx_hash) ON DELETE CASCADE,
+    amount NUMERIC(78,0) NOT NULL,
+    matched_by VARCHAR(16) NOT NULL,
+    matched_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    PRIMARY KEY (invoice_id, tx_hash),
+
+    CONSTRAINT invoice_payment_match CHECK (matched_by IN ('memo', 'payer', 'amount'))
+);
@@ -0,0 +1,90 @@
+-- inflows a group expects on a schedule, e.g. rent from a tenant
+CREATE TABLE recurring_expectations (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    group_address VARCHAR(66) NOT NULL,
+    payer_address VARCHAR(66) NOT NULL,
+    token_address VARCHAR(66) NOT NULL,
+    min_amount NUMERIC(78,0) NOT NULL,
+    max_amount NUMERIC(78,0) NOT NULL,
+    frequency VARCHAR(8) NOT NULL,
+    interval_count INT NOT NULL DEFAULT 1,
+    starts_on DATE NOT NULL,
+    ends_on DATE,
+    grace_days INT NOT NULL DEFAULT 3,
+    description VARCHAR(140),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    cancelled_at TIMESTAMPTZ,
+
+    CONSTRAINT fk_group_recurring_expectation
+        FOREIGN KEY (group_address)
+        REFERENCES groups (group_address)
+        ON DELETE CASCADE ON UPDATE CASCADE,
+
+    CONSTRAINT chk_recurring_amounts CHECK (min_amount > 0 AND max_amount >= min_amount),
+    CONSTRAINT recurring_frequency CHECK (frequency IN ('daily', 'weekly', 'monthly')),
+    CONSTRAINT chk_recurring_interval CHECK (interval_count BETWEEN 1 AND 365),
+    CONSTRAINT chk_recurring_grace CHECK (grace_days >= 0),
+    CONSTRAINT chk_recurring_ends CHECK (ends_on IS NULL OR ends_on >= starts_on)
+);
+
+CREATE INDEX idx_recurring_expectations_group ON recurring_expectations (group_address);
+
+-- due date of occurrence `seq` (from 0). Always counted from starts_on, so a
+-- schedule starting on the 31st falls on each month's last day without drifting
+CREATE FUNCTION recurring_due_on(starts_on DATE, frequency VARCHAR, interval_count INT, seq INT)
+RETURNS DATE LANGUAGE SQL IMMUTABLE AS $$
+    SELECT (starts_on + seq * interval_count * CASE frequency
+        WHEN 'daily' THEN interval '1 day'
+        WHEN 'weekly' THEN interval '1 week'
+        ELSE interval '1 month'
+    END)::date
+$$;
+
+-- occurrence numbers that can fall on or before `until`; months count as 28
+-- days, so this overshoots and callers filter on the due date
+CREATE FUNCTION recurring_seq_bound(starts_on DATE, frequency VARCHAR, interval_count INT, until DATE)
+RETURNS INT LANGUAGE SQL IMMUTABLE AS $$
+    SELECT (until - starts_on) / (interval_count * CASE frequency
+        WHEN 'daily' THEN 1
+        WHEN 'weekly' THEN 7
+        ELSE 28
+    END)
+$$;
+
+-- one row per due date, created once its matching window opens
+CREATE TABLE recurring_occurrences (
+    expectation_id UUID NOT NULL REFERENCES recurring_expectations (id) ON DELETE CASCADE,
+    seq INT NOT NULL,
+    due_on DATE NOT NULL,
+    status VARCHAR(16) NOT NULL DEFAULT 'pending',
+    tx_hash VARCHAR(66) UNIQUE REFERENCES group_tx_hashes (tx_hash) ON DELETE SET NULL,
+    amount NUMERIC(78,0),
+    received_at TIMESTAMPTZ,
+    missed_at TIMESTAMPTZ,
+
+    PRIMARY KEY (expectation_id, seq),
+
+    CONSTRAINT recurring_occurrence_status
+        CHECK (status IN ('pending', 'received', 'late', 'missed'))
+);
+
+CREATE INDEX idx_recurring_occurrences_open ON recurring_occurrences (expectation_id)
+    WHERE status IN ('pending', 'missed');
+
+-- alerts for a group's creator, read by the frontend
+CREATE TABLE notifications (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    recipient VARCHAR(66) NOT NULL,
+    group_address VARCHAR(66) NOT NULL,
+    kind VARCHAR(32) NOT NULL,
+    message TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    read_at TIMESTAMPTZ,
+
+    CONSTRAINT fk_group_notification
+        FOREIGN KEY (group_address)
+        REFERENCES groups (group_address)
+        ON DELETE CASCADE ON UPDATE CASCADE
+);
+
+CREATE INDEX idx_notifications_recipient ON notifications (recipient, created_at, id);
@@ -0,0 +1,48 @@
+-- the contract's id for the group, needed for its usage views; learned from
+-- GroupCreated or looked up from the creator's groups
+ALTER TABLE groups ADD COLUMN group_id BIGINT UNIQUE;
+
+-- every change to groups.usage_remaining, in order. `opening` carries the
+-- balance of groups that existed before the ledger; `purchase` is the usage
+-- bought at creation
+CREATE TABLE group_usage_ledger (
+    id BIGSERIAL PRIMARY KEY,
+    group_address VARCHAR(66) NOT NULL,
+    kind VARCHAR(16) NOT NULL,
+    delta NUMERIC(20,0) NOT NULL,
+    usage_after NUMERIC(20,0) NOT NULL,
+    tx_hash VARCHAR(66),
+    reason VARCHAR(140),
+    recorded_by VARCHAR(66),
+    recorded_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT fk_group_usage_ledger
+        FOREIGN KEY (group_address)
+        REFERENCES groups (group_address)
+        ON DELETE CASCADE ON UPDATE CASCADE,
+
+    CONSTRAINT usage_ledger_kind
+        CHECK (kind IN ('opening', 'purchase', 'top_up', 'consumption', 'adjustment')),
+    CONSTRAINT chk_usage_ledger_adjustment
+        CHECK (kind <> 'adjustment' OR (reason IS NOT NULL AND recorded_by IS NOT NULL))
+);
+
+CREATE INDEX idx_group_usage_ledger_group ON group_usage_ledger (group_address, recorded_at, id);
+-- a redelivered event is not booked twice
+CREATE UNIQUE INDEX idx_group_usage_ledger_tx ON group_usage_ledger (kind, tx_hash)
+    WHERE tx_hash IS NOT NULL;
+
+CREATE OR REPLACE FUNCTION reject_usage_ledger_update()
+RETURNS TRIGGER AS $$
+BEGIN
+    RAISE EXCEPTION 'group_usage_ledger is append-only';
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER usage_ledger_append_only
+    BEFORE UPDATE ON group_usage_ledger
+    FOR EACH ROW EXECUTE FUNCTION reject_usage_ledger_update();
+
+INSERT INTO group_usage_ledger (group_address, kind, delta, usage_after, recorded_at)
+SELECT group_address, 'opening', usage_remaining, usage_remaining, NOW()
+FROM groups;
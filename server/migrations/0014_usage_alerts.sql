@@ -0,0 +1,19 @@
+-- usages remaining at which the creator is warned; NULL uses
+-- LOW_USAGE_THRESHOLDS, an empty array turns the alerts off
+ALTER TABLE groups ADD COLUMN low_usage_thresholds INTEGER[];
+
+-- thresholds the creator was already warned about; a threshold is cleared
+-- once usage rises above it again, so the next drop warns again
+CREATE TABLE group_usage_alerts (
+    group_address VARCHAR(66) NOT NULL,
+    threshold INTEGER NOT NULL,
+    usage_remaining NUMERIC(20,0) NOT NULL,
+    alerted_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    PRIMARY KEY (group_address, threshold),
+
+    CONSTRAINT fk_group_usage_alert
+        FOREIGN KEY (group_address)
+        REFERENCES groups (group_address)
+        ON DELETE CASCADE ON UPDATE CASCADE
+);
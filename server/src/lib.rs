@@ -1,37 +1,80 @@
 pub mod libs {
+    pub mod audit;
+    pub mod auth;
     pub mod cache;
     pub mod config;
     pub mod db;
     pub mod error;
+    pub mod export;
+    pub mod extract;
+    pub mod forecast;
+    pub mod invoice;
     pub mod logging;
     pub mod middleware;
+    pub mod notification;
+    pub mod pagination;
+    pub mod price;
+    pub mod recurring;
+    pub mod rollup;
+    pub mod share_set;
+    pub mod usage;
 }
 
 pub mod routes {
+    pub mod analytics;
+    pub mod audit;
+    pub mod export;
     pub mod group;
     pub mod health;
+    pub mod invoice;
+    pub mod member;
+    pub mod notification;
     pub mod pay_group;
+    pub mod payer;
+    pub mod payment;
+    pub mod payment_uri;
+    pub mod preview;
+    pub mod proposal;
+    pub mod recurring;
+    pub mod share_set;
     pub mod subscription_topped;
     pub mod types;
+    pub mod usage;
 }
 
 pub mod util {
+    pub mod address;
+    pub mod amount;
     pub mod connector;
+    pub mod ledger;
+    pub mod payment_uri;
+    pub mod split;
     pub mod starknet;
+    pub mod statement;
+    pub mod token;
     pub mod util_types;
 }
 
-use crate::libs::cache::Cache;
+use crate::libs::{
+    cache::Cache,
+    error::route_not_found,
+    middleware::{REQUEST_ID_HEADER, deprecated, make_request_span, on_response},
+};
 use axum::{
     Router,
     http::{
-        HeaderName, Method, StatusCode,
+        HeaderName, Method,
         header::{AUTHORIZATION, CONTENT_TYPE},
     },
+    middleware::map_response,
     routing::{get, post},
 };
 use sqlx::PgPool;
-use tower_http::cors::CorsLayer;
+use tower_http::{
+    cors::CorsLayer,
+    request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer},
+    trace::TraceLayer,
+};
 
 #[derive(Clone)]
 pub struct AppState {
@@ -39,34 +82,174 @@ pub struct AppState {
     pub cache: Cache,
 }
 
-use crate::routes::{group, health, pay_group, subscription_topped};
+use crate::routes::{
+    analytics, audit, export, group, health, invoice, member, notification, pay_group, payer,
+    payment, payment_uri, preview, proposal, recurring, share_set, subscription_topped, usage,
+};
 
-pub fn router(state: AppState) -> Router {
-    let cors = CorsLayer::new()
-        .allow_origin(tower_http::cors::Any)
-        .allow_methods([Method::GET, Method::POST])
-        .allow_headers([
-            CONTENT_TYPE,
-            AUTHORIZATION,
-            HeaderName::from_static("x-requested-with"),
-        ]);
+fn v1_routes() -> Router<AppState> {
+    Router::new()
+        .route("/groups", get(group::get_groups).post(group::create_group))
+        .route("/groups/{address}", get(group::get_group_by_address))
+        .route("/groups/{address}/members", get(group::get_group_members))
+        .route(
+            "/groups/{address}/share-sets",
+            get(share_set::get_share_sets).post(share_set::create_share_set),
+        )
+        .route(
+            "/groups/{address}/composition",
+            get(share_set::get_composition),
+        )
+        .route("/groups/{address}/preview", post(preview::preview_split))
+        .route(
+            "/groups/{address}/recurring-payments",
+            get(recurring::get_group_expectations).post(recurring::create_expectation),
+        )
+        .route(
+            "/groups/{address}/usage-ledger",
+            get(usage::get_usage_ledger),
+        )
+        .route(
+            "/groups/{address}/usage-adjustments",
+            post(usage::adjust_usage),
+        )
+        .route(
+            "/groups/{address}/usage-reconciliation",
+            get(usage::reconcile_usage),
+        )
+        .route(
+            "/groups/{address}/usage-forecast",
+            get(usage::get_usage_forecast),
+        )
+        .route(
+            "/groups/{address}/usage-alerts",
+            get(usage::get_usage_alerts).post(usage::set_usage_alerts),
+        )
+        .route(
+            "/groups/{address}/payment-uri",
+            get(payment_uri::get_group_payment_uri),
+        )
+        .route(
+            "/groups/{address}/update-proposals",
+            get(proposal::get_group_proposals),
+        )
+        .route(
+            "/groups/{address}/update-requests",
+            post(proposal::record_update_request),
+        )
+        .route(
+            "/groups/{address}/update-approvals",
+            post(proposal::record_update_approval),
+        )
+        .route(
+            "/groups/{address}/updates",
+            post(proposal::apply_group_update),
+        )
+        .route(
+            "/groups/{address}/payments",
+            get(payment::get_group_payments),
+        )
+        .route(
+            "/groups/{address}/distributions",
+            get(payment::get_group_distributions),
+        )
+        .route(
+            "/groups/{address}/audit/findings",
+            get(audit::get_group_findings),
+        )
+        .route(
+            "/payments",
+            get(payment::get_payments).post(pay_group::store_payment_distribution_history),
+        )
+        .route("/payments/{tx_hash}", get(payment::get_payment))
+        .route("/distributions", get(payment::get_distributions))
+        .route(
+            "/invoices",
+            get(invoice::get_invoices).post(invoice::create_invoice),
+        )
+        .route("/invoices/{id}", get(invoice::get_invoice))
+        .route("/invoices/{id}/cancel", post(invoice::cancel_invoice))
+        .route(
+            "/invoices/{id}/payment-uri",
+            get(invoice::get_invoice_payment_uri),
+        )
+        .route("/recurring-payments/{id}", get(recurring::get_expectation))
+        .route(
+            "/recurring-payments/{id}/cancel",
+            post(recurring::cancel_expectation),
+        )
+        .route("/groups/{address}/payers", get(payer::get_group_payers))
+        .route("/payers/{address}/groups", get(payer::get_payer_groups))
+        .route("/members/{address}", get(member::get_member_portfolio))
+        .route(
+            "/members/{address}/statement",
+            get(member::get_member_statement),
+        )
+        .route("/creators/{address}/groups", get(group::get_creator_groups))
+        .route(
+            "/creators/{address}/notifications",
+            get(notification::get_creator_notifications),
+        )
+        .route(
+            "/notifications/{id}/read",
+            post(notification::mark_notification_read),
+        )
+        .route("/transfers", post(pay_group::pay_group))
+        .route("/top-ups", post(subscription_topped::subscription_topped))
+        .route("/metrics/groups", get(group::get_groups_metrics))
+        .route("/metrics/payments", get(group::get_payments_totals))
+        .route("/audit/findings", get(audit::get_findings))
+        .route("/audit/summary", get(audit::get_audit_summary))
+        .route("/analytics/volume", get(analytics::get_volume))
+        .route("/exports/payments", get(export::export_payments))
+        .route("/exports/distributions", get(export::export_distributions))
+        .route("/exports/memberships", get(export::export_memberships))
+        .route("/exports/ledger", get(export::export_ledger))
+}
 
+/// Pre-`/v1` routes, kept as aliases while clients migrate.
+fn legacy_routes() -> Router<AppState> {
     Router::new()
-        .route("/health", get(health::health_check))
         .route("/group", get(group::get_group).post(group::create_group))
         .route("/pay_group", post(pay_group::pay_group))
         .route(
             "/subscription_topped",
             post(subscription_topped::subscription_topped),
         )
-        .route("/all_groups", get(group::get_groups))
-        .route("/history", get(group::get_groups_metrics))
+        .route("/all_groups", get(group::get_groups_unpaged))
+        .route("/history", get(group::get_groups_metrics_unpaged))
         .route("/transfer_metrics", get(group::get_payments_totals))
         .route(
             "/store_payment_distribution_history",
             post(pay_group::store_payment_distribution_history),
         )
+        .layer(map_response(deprecated))
+}
+
+pub fn router(state: AppState) -> Router {
+    let cors = CorsLayer::new()
+        .allow_origin(tower_http::cors::Any)
+        .allow_methods([Method::GET, Method::POST])
+        .allow_headers([
+            CONTENT_TYPE,
+            AUTHORIZATION,
+            HeaderName::from_static("x-requested-with"),
+            REQUEST_ID_HEADER,
+        ])
+        .expose_headers([REQUEST_ID_HEADER]);
+
+    Router::new()
+        .route("/health", get(health::health_check))
+        .nest("/v1", v1_routes())
+        .merge(legacy_routes())
+        .fallback(route_not_found)
         .with_state(state)
         .layer(cors)
-        .fallback(|| async { (StatusCode::UNAUTHORIZED, "UNAUTHORIZED ORIGIN") })
+        .layer(PropagateRequestIdLayer::x_request_id())
+        .layer(
+            TraceLayer::new_for_http()
+                .make_span_with(make_request_span)
+                .on_response(on_response),
+        )
+        .layer(SetRequestIdLayer::x_request_id(MakeRequestUuid))
 }
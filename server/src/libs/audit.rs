@@ -0,0 +1,115 @@
+use sqlx::{PgConnection, PgPool};
+use tracing::Instrument;
+
+use crate::libs::db::query_span;
+
+/// Re-checks one payment's distributions, replacing its findings. Call in the
+/// transaction that records them. Returns the number of findings.
+pub async fn audit_payment(conn: &mut PgConnection, tx_hash: &str) -> Result<i64, sqlx::Error> {
+    audit(conn, Some(tx_hash)).await
+}
+
+/// Re-checks every payment.
+pub async fn audit_all(db: &PgPool) -> Result<i64, sqlx::Error> {
+    let mut tx = db.begin().await?;
+    let findings = audit(&mut tx, None).await?;
+    tx.commit().await?;
+    Ok(findings)
+}
+
+/// Recomputes the split the contract makes, `floor(amount * percentage / 100)`
+/// for each member of the share set the payment is linked to, and records
+/// where the stored distributions differ:
+///
+/// - `missing_member`: an expected member got no distribution
+/// - `unexpected_member`: a distribution went to someone outside the share set
+/// - `amount_mismatch`: a member got a different amount than expected
+/// - `over_distributed`: more was distributed than paid in
+/// - `rounding_dust` (info): flooring leaves part of the payment in the group
+async fn audit(conn: &mut PgConnection, tx_hash: Option<&str>) -> Result<i64, sqlx::Error> {
+    sqlx::query!(
+        "DELETE FROM distribution_audit_findings WHERE ($1::text IS NULL OR tx_hash = $1)",
+        tx_hash
+    )
+    .execute(&mut *conn)
+    .instrument(query_span("delete_audit_findings"))
+    .await?;
+
+    let findings = sqlx::query!(
+        r#"
+        WITH p AS (
+            SELECT tx_hash, group_address, token_address, amount, share_set_id
+            FROM payments
+            WHERE ($1::text IS NULL OR tx_hash = $1)
+        ), expected AS (
+            SELECT p.tx_hash, sm.member_address, FLOOR(p.amount * sm.member_percentage / 100) AS amount
+            FROM p
+            JOIN group_share_set_members sm ON sm.share_set_id = p.share_set_id
+        ), actual AS (
+            SELECT d.tx_hash, d.member_address, SUM(d.token_amount) AS amount
+            FROM distributions_history d
+            JOIN p ON p.tx_hash = d.tx_hash
+            GROUP BY d.tx_hash, d.member_address
+        ), members AS (
+            SELECT COALESCE(e.tx_hash, a.tx_hash) AS tx_hash,
+                COALESCE(e.member_address, a.member_address) AS member_address,
+                e.amount AS expected, a.amount AS actual
+            FROM expected e
+            FULL JOIN actual a ON a.tx_hash = e.tx_hash AND a.member_address = e.member_address
+        ), totals AS (
+            SELECT p.tx_hash,
+                (SELECT SUM(amount) FROM expected e WHERE e.tx_hash = p.tx_hash) AS expected,
+                (SELECT SUM(amount) FROM actual a WHERE a.tx_hash = p.tx_hash) AS actual
+            FROM p
+        ), findings AS (
+            SELECT m.tx_hash, m.member_address,
+                CASE
+                    WHEN m.actual IS NULL THEN 'missing_member'
+                    WHEN m.expected IS NULL THEN 'unexpected_member'
+                    ELSE 'amount_mismatch'
+                END AS kind,
+                'error' AS severity, m.expected, m.actual
+            FROM members m
+            WHERE m.expected IS DISTINCT FROM m.actual
+            UNION ALL
+            SELECT p.tx_hash, NULL, 'over_distributed', 'error', p.amount, t.actual
+            FROM p JOIN totals t ON t.tx_hash = p.tx_hash
+            WHERE t.actual > p.amount
+            UNION ALL
+            SELECT p.tx_hash, NULL, 'rounding_dust', 'info', p.amount, t.expected
+            FROM p JOIN totals t ON t.tx_hash = p.tx_hash
+            WHERE t.expected < p.amount
+        )
+        INSERT INTO distribution_audit_findings
+            (tx_hash, group_address, token_address, member_address, kind, severity, expected, actual)
+        SELECT f.tx_hash, p.group_address, p.token_address, f.member_address, f.kind, f.severity,
+            f.expected, f.actual
+        FROM findings f
+        JOIN p ON p.tx_hash = f.tx_hash
+        "#,
+        tx_hash
+    )
+    .execute(&mut *conn)
+    .instrument(query_span("insert_audit_findings"))
+    .await?
+    .rows_affected();
+
+    sqlx::query!(
+        r#"
+        INSERT INTO payment_audits (tx_hash, finding_count)
+        SELECT p.tx_hash, COUNT(f.id)
+        FROM payments p
+        LEFT JOIN distribution_audit_findings f ON f.tx_hash = p.tx_hash
+        WHERE ($1::text IS NULL OR p.tx_hash = $1)
+        GROUP BY p.tx_hash
+        ON CONFLICT (tx_hash) DO UPDATE
+        SET finding_count = EXCLUDED.finding_count, audited_at = NOW()
+        "#,
+        tx_hash
+    )
+    .execute(&mut *conn)
+    .instrument(query_span("upsert_payment_audits"))
+    .await?;
+
+    Ok(findings as i64)
+}
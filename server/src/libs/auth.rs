@@ -0,0 +1,49 @@
+use std::{env::var, sync::LazyLock};
+
+use axum::{
+    extract::FromRequestParts,
+    http::{header::AUTHORIZATION, request::Parts},
+};
+
+use crate::libs::error::{ApiError, ErrorCode};
+
+/// Bearer token for admin routes. Unset, every admin request is refused.
+static ADMIN_API_KEY: LazyLock<Option<String>> =
+    LazyLock::new(|| var("ADMIN_API_KEY").ok().filter(|key| !key.is_empty()));
+
+/// A caller that sent `Authorization: Bearer <ADMIN_API_KEY>`. `identity` is
+/// what gets recorded as having made the change.
+#[derive(Debug, Clone, Copy)]
+pub struct Admin {
+    pub identity: &'static str,
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for Admin {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let Some(key) = ADMIN_API_KEY.as_deref() else {
+            tracing::warn!("Admin request refused: ADMIN_API_KEY is not set");
+            return Err(unauthorized());
+        };
+
+        parts
+            .headers
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .filter(|token| constant_time_eq(token.as_bytes(), key.as_bytes()))
+            .map(|_| Admin { identity: "admin" })
+            .ok_or_else(unauthorized)
+    }
+}
+
+fn unauthorized() -> ApiError {
+    ApiError::new(ErrorCode::Unauthorized, "A valid admin API key is required")
+}
+
+/// Compares without returning early, so response times don't leak how much
+/// of the key matched.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
@@ -38,3 +38,13 @@ impl Db {
             .is_ok()
     }
 }
+
+/// Span for a single query, nested under the handler that issued it.
+pub fn query_span(operation: &'static str) -> tracing::Span {
+    tracing::info_span!(
+        "db.query",
+        otel.kind = "client",
+        db.system = "postgresql",
+        db.operation = operation,
+    )
+}
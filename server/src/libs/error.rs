@@ -1,71 +1,267 @@
+use std::{borrow::Cow, fmt};
+
 use axum::{
     Json,
-    http::StatusCode,
+    http::{HeaderValue, StatusCode, header::CONTENT_TYPE},
     response::{IntoResponse, Response},
 };
 use serde::Serialize;
 
+const PROBLEM_JSON: &str = "application/problem+json";
+
+/// Stable, machine-readable error codes. Clients should branch on these,
+/// never on the human message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCode {
+    BadRequest,
+    ValidationFailed,
+    InvalidAddress,
+    InvalidAmount,
+    InvalidCursor,
+    Unauthorized,
+    UnsupportedMediaType,
+    NotAcceptable,
+    GroupNotFound,
+    PaymentNotFound,
+    ShareSetNotFound,
+    ProposalNotFound,
+    InvoiceNotFound,
+    RecurringPaymentNotFound,
+    NotificationNotFound,
+    RouteNotFound,
+    Conflict,
+    UsageExhausted,
+    ContractCallFailed,
+    DatabaseError,
+    Internal,
+}
+
+impl ErrorCode {
+    pub fn status(self) -> StatusCode {
+        match self {
+            ErrorCode::BadRequest
+            | ErrorCode::InvalidAddress
+            | ErrorCode::InvalidAmount
+            | ErrorCode::InvalidCursor => StatusCode::BAD_REQUEST,
+            ErrorCode::ValidationFailed => StatusCode::UNPROCESSABLE_ENTITY,
+            ErrorCode::Unauthorized => StatusCode::UNAUTHORIZED,
+            ErrorCode::UnsupportedMediaType => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            ErrorCode::NotAcceptable => StatusCode::NOT_ACCEPTABLE,
+            ErrorCode::GroupNotFound
+            | ErrorCode::PaymentNotFound
+            | ErrorCode::ShareSetNotFound
+            | ErrorCode::ProposalNotFound
+            | ErrorCode::InvoiceNotFound
+            | ErrorCode::RecurringPaymentNotFound
+            | ErrorCode::NotificationNotFound
+            | ErrorCode::RouteNotFound => StatusCode::NOT_FOUND,
+            ErrorCode::Conflict | ErrorCode::UsageExhausted => StatusCode::CONFLICT,
+            ErrorCode::ContractCallFailed => StatusCode::BAD_GATEWAY,
+            ErrorCode::DatabaseError | ErrorCode::Internal => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ErrorCode::BadRequest => "BAD_REQUEST",
+            ErrorCode::ValidationFailed => "VALIDATION_FAILED",
+            ErrorCode::InvalidAddress => "INVALID_ADDRESS",
+            ErrorCode::InvalidAmount => "INVALID_AMOUNT",
+            ErrorCode::InvalidCursor => "INVALID_CURSOR",
+            ErrorCode::Unauthorized => "UNAUTHORIZED",
+            ErrorCode::UnsupportedMediaType => "UNSUPPORTED_MEDIA_TYPE",
+            ErrorCode::NotAcceptable => "NOT_ACCEPTABLE",
+            ErrorCode::GroupNotFound => "GROUP_NOT_FOUND",
+            ErrorCode::PaymentNotFound => "PAYMENT_NOT_FOUND",
+            ErrorCode::ShareSetNotFound => "SHARE_SET_NOT_FOUND",
+            ErrorCode::ProposalNotFound => "PROPOSAL_NOT_FOUND",
+            ErrorCode::InvoiceNotFound => "INVOICE_NOT_FOUND",
+            ErrorCode::RecurringPaymentNotFound => "RECURRING_PAYMENT_NOT_FOUND",
+            ErrorCode::NotificationNotFound => "NOTIFICATION_NOT_FOUND",
+            ErrorCode::RouteNotFound => "ROUTE_NOT_FOUND",
+            ErrorCode::Conflict => "CONFLICT",
+            ErrorCode::UsageExhausted => "USAGE_EXHAUSTED",
+            ErrorCode::ContractCallFailed => "CONTRACT_CALL_FAILED",
+            ErrorCode::DatabaseError => "DATABASE_ERROR",
+            ErrorCode::Internal => "INTERNAL_ERROR",
+        }
+    }
+
+    fn title(self) -> &'static str {
+        match self {
+            ErrorCode::BadRequest => "Bad request",
+            ErrorCode::ValidationFailed => "Validation failed",
+            ErrorCode::InvalidAddress => "Invalid address",
+            ErrorCode::InvalidAmount => "Invalid amount",
+            ErrorCode::InvalidCursor => "Invalid cursor",
+            ErrorCode::Unauthorized => "Unauthorized",
+            ErrorCode::UnsupportedMediaType => "Unsupported media type",
+            ErrorCode::NotAcceptable => "Not acceptable",
+            ErrorCode::GroupNotFound => "Group not found",
+            ErrorCode::PaymentNotFound => "Payment not found",
+            ErrorCode::ShareSetNotFound => "Share set not found",
+            ErrorCode::ProposalNotFound => "Proposal not found",
+            ErrorCode::InvoiceNotFound => "Invoice not found",
+            ErrorCode::RecurringPaymentNotFound => "Recurring payment not found",
+            ErrorCode::NotificationNotFound => "Notification not found",
+            ErrorCode::RouteNotFound => "Route not found",
+            ErrorCode::Conflict => "Conflict",
+            ErrorCode::UsageExhausted => "Group usage exhausted",
+            ErrorCode::ContractCallFailed => "Contract call failed",
+            ErrorCode::DatabaseError => "Database error",
+            ErrorCode::Internal => "Internal server error",
+        }
+    }
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A single invalid field, addressed by its path in the request
+/// (e.g. `members[1].addr`).
+#[derive(Debug, Clone, Serialize)]
+pub struct FieldError {
+    pub field: String,
+    pub code: Cow<'static, str>,
+    pub message: Cow<'static, str>,
+}
+
+impl FieldError {
+    pub fn new(
+        field: impl Into<String>,
+        code: impl Into<Cow<'static, str>>,
+        message: impl Into<Cow<'static, str>>,
+    ) -> Self {
+        Self {
+            field: field.into(),
+            code: code.into(),
+            message: message.into(),
+        }
+    }
+}
+
+/// Error returned by every handler, rendered as RFC 7807 `problem+json`.
+///
+/// `cause` is only ever logged; clients see the code and message.
 #[derive(Debug)]
-pub enum ApiError {
-    BadRequest(&'static str),
-    Unauthorized(&'static str),
-    Conflict(&'static str),
-    NotFound(&'static str),
-    Internal(&'static str),
+pub struct ApiError {
+    pub code: ErrorCode,
+    pub message: Cow<'static, str>,
+    pub fields: Vec<FieldError>,
+    cause: Option<String>,
+}
+
+impl ApiError {
+    pub fn new(code: ErrorCode, message: impl Into<Cow<'static, str>>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            fields: Vec::new(),
+            cause: None,
+        }
+    }
+
+    pub fn with_cause(mut self, cause: impl fmt::Display) -> Self {
+        self.cause = Some(cause.to_string());
+        self
+    }
+
+    pub fn with_fields(mut self, fields: Vec<FieldError>) -> Self {
+        self.fields = fields;
+        self
+    }
+
+    pub fn bad_request(message: impl Into<Cow<'static, str>>) -> Self {
+        Self::new(ErrorCode::BadRequest, message)
+    }
+
+    pub fn group_not_found() -> Self {
+        Self::new(ErrorCode::GroupNotFound, "Group not found")
+    }
+
+    pub fn internal(cause: impl fmt::Display) -> Self {
+        Self::new(ErrorCode::Internal, "An internal error occurred").with_cause(cause)
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.code, self.message)?;
+        if let Some(cause) = &self.cause {
+            write!(f, " ({cause})")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+impl From<sqlx::Error> for ApiError {
+    fn from(e: sqlx::Error) -> Self {
+        match &e {
+            sqlx::Error::Database(db) if db.code().as_deref() == Some("23505") => {
+                ApiError::new(ErrorCode::Conflict, "Resource already exists").with_cause(e)
+            }
+            sqlx::Error::Database(db) if db.code().as_deref() == Some("23503") => {
+                ApiError::new(ErrorCode::Conflict, "Referenced resource does not exist")
+                    .with_cause(e)
+            }
+            // a bound value Postgres could not cast, e.g. a tampered cursor
+            sqlx::Error::Database(db)
+                if matches!(db.code().as_deref(), Some("22P02" | "22007" | "22008")) =>
+            {
+                ApiError::bad_request("Malformed query parameter").with_cause(e)
+            }
+            _ => ApiError::new(ErrorCode::DatabaseError, "A database error occurred").with_cause(e),
+        }
+    }
 }
 
 #[derive(Serialize)]
-pub struct ErrorBody {
-    pub error: String,
+pub struct ProblemDetails {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub title: &'static str,
+    pub status: u16,
+    pub detail: String,
+    pub code: ErrorCode,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<FieldError>,
 }
 
 impl IntoResponse for ApiError {
     fn into_response(self) -> Response {
-        match self {
-            ApiError::BadRequest(msg) => (
-                StatusCode::BAD_REQUEST,
-                Json(ErrorBody {
-                    error: msg.to_string(),
-                }),
-            )
-                .into_response(),
-            ApiError::Unauthorized(msg) => (
-                StatusCode::UNAUTHORIZED,
-                Json(ErrorBody {
-                    error: msg.to_string(),
-                }),
-            )
-                .into_response(),
-            ApiError::Conflict(msg) => (
-                StatusCode::CONFLICT,
-                Json(ErrorBody {
-                    error: msg.to_string(),
-                }),
-            )
-                .into_response(),
-            ApiError::NotFound(msg) => (
-                StatusCode::NOT_FOUND,
-                Json(ErrorBody {
-                    error: msg.to_string(),
-                }),
-            )
-                .into_response(),
-            ApiError::Internal(msg) => (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorBody {
-                    error: msg.to_string(),
-                }),
-            )
-                .into_response(),
+        let status = self.code.status();
+        let cause = self.cause.as_deref().unwrap_or_default();
+
+        if status.is_server_error() {
+            tracing::error!(code = %self.code, cause, "{}", self.message);
+        } else {
+            tracing::warn!(code = %self.code, cause, "{}", self.message);
         }
+
+        let body = ProblemDetails {
+            kind: format!("urn:paymesh:error:{}", self.code.as_str().to_lowercase()),
+            title: self.code.title(),
+            status: status.as_u16(),
+            detail: self.message.into_owned(),
+            code: self.code,
+            errors: self.fields,
+        };
+
+        let mut response = (status, Json(body)).into_response();
+        response
+            .headers_mut()
+            .insert(CONTENT_TYPE, HeaderValue::from_static(PROBLEM_JSON));
+        response
     }
 }
 
-pub fn map_sqlx_error(e: &sqlx::Error) -> ApiError {
-    match e {
-        sqlx::Error::Database(db) if db.code().as_deref() == Some("23505") => {
-            ApiError::Conflict("duplicate")
-        }
-        _ => ApiError::Internal("database error"),
-    }
+/// Fallback for unmatched routes.
+pub async fn route_not_found() -> ApiError {
+    ApiError::new(ErrorCode::RouteNotFound, "No route matches this path")
 }
@@ -0,0 +1,243 @@
+use axum::{
+    body::Body,
+    extract::FromRequestParts,
+    http::{
+        HeaderValue,
+        header::{ACCEPT, CONTENT_DISPOSITION, CONTENT_TYPE},
+        request::Parts,
+    },
+    response::{IntoResponse, Response},
+};
+use bytes::Bytes;
+use futures_util::{TryStreamExt, stream};
+use serde::Serialize;
+use sqlx::{FromRow, PgPool, Postgres, QueryBuilder, postgres::PgRow};
+use tokio::sync::mpsc;
+use tracing::Instrument;
+
+use crate::libs::{
+    db::query_span,
+    error::{ApiError, ErrorCode},
+};
+
+/// Rows are sent to the client as they are read; this bounds how far the
+/// query can run ahead of a slow client.
+const EXPORT_BUFFER_ROWS: usize = 256;
+
+/// Export format, negotiated from `Accept`; CSV by default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv,
+    Ndjson,
+}
+
+impl ExportFormat {
+    fn content_type(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "text/csv; charset=utf-8",
+            ExportFormat::Ndjson => "application/x-ndjson",
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Ndjson => "ndjson",
+        }
+    }
+
+    /// What goes before the first row: the CSV header line, taken from the
+    /// field names of `T`, so an export without rows still has its columns.
+    fn header<T: Serialize + Default>(self) -> Result<Vec<u8>, String> {
+        match self {
+            ExportFormat::Csv => {
+                let mut writer = csv::Writer::from_writer(Vec::new());
+                writer.serialize(T::default()).map_err(|e| e.to_string())?;
+                let mut header = writer.into_inner().map_err(|e| e.to_string())?;
+                // field names have no line breaks, so the header is the first line
+                let end = header.iter().position(|b| *b == b'\n').map_or(0, |i| i + 1);
+                header.truncate(end);
+                Ok(header)
+            }
+            ExportFormat::Ndjson => Ok(Vec::new()),
+        }
+    }
+
+    /// One serialized row, without the CSV header.
+    fn encode<T: Serialize>(self, row: &T) -> Result<Vec<u8>, String> {
+        match self {
+            ExportFormat::Csv => {
+                let mut writer = csv::WriterBuilder::new()
+                    .has_headers(false)
+                    .from_writer(Vec::new());
+                writer.serialize(row).map_err(|e| e.to_string())?;
+                writer.into_inner().map_err(|e| e.to_string())
+            }
+            ExportFormat::Ndjson => {
+                let mut line = serde_json::to_vec(row).map_err(|e| e.to_string())?;
+                line.push(b'\n');
+                Ok(line)
+            }
+        }
+    }
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for ExportFormat {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        negotiate(
+            parts,
+            &[
+                ("text/csv", ExportFormat::Csv),
+                ("application/x-ndjson", ExportFormat::Ndjson),
+            ],
+        )
+    }
+}
+
+/// Picks the first of `offers` the `Accept` header allows; the first offer
+/// when the header is missing or accepts anything.
+pub fn negotiate<T: Copy>(parts: &Parts, offers: &[(&'static str, T)]) -> Result<T, ApiError> {
+    let Some(accept) = parts.headers.get(ACCEPT) else {
+        return Ok(offers[0].1);
+    };
+
+    // first supported type wins; quality values are not ranked
+    accept
+        .to_str()
+        .unwrap_or_default()
+        .split(',')
+        .map(|media| media.split(';').next().unwrap_or_default().trim())
+        .find_map(|media| {
+            offers.iter().find_map(|&(offer, value)| {
+                let matches = media == "*/*"
+                    || media == offer
+                    || media
+                        .strip_suffix("/*")
+                        .is_some_and(|kind| offer.split('/').next() == Some(kind));
+                matches.then_some(value)
+            })
+        })
+        .ok_or_else(|| {
+            let offered: Vec<String> = offers
+                .iter()
+                .map(|(offer, _)| format!("`{offer}`"))
+                .collect();
+            ApiError::new(
+                ErrorCode::NotAcceptable,
+                format!("Available as {}", offered.join(", ")),
+            )
+        })
+}
+
+/// Streams the rows of `qb` as a download, converting each with `map`.
+pub fn stream_rows<Row, Out>(
+    db: PgPool,
+    qb: QueryBuilder<'static, Postgres>,
+    format: ExportFormat,
+    name: &str,
+    operation: &'static str,
+    map: fn(Row) -> Out,
+) -> Response
+where
+    Row: for<'r> FromRow<'r, PgRow> + Send + Unpin + 'static,
+    Out: Serialize + Default + Send + 'static,
+{
+    let header = match format.header::<Out>() {
+        Ok(header) => header,
+        Err(e) => return ApiError::internal(e).into_response(),
+    };
+    stream_body(
+        db,
+        qb,
+        format.content_type(),
+        &format!("{name}.{}", format.extension()),
+        operation,
+        header,
+        move |row| format.encode(&map(row)),
+    )
+}
+
+/// Streams the rows of `qb` as the download `filename`, each row becoming the
+/// bytes `encode` returns for it.
+///
+/// Errors after the first byte can't become a problem response any more, so
+/// they are logged and the body is cut short.
+pub fn stream_encoded<Row>(
+    db: PgPool,
+    qb: QueryBuilder<'static, Postgres>,
+    content_type: &'static str,
+    filename: &str,
+    operation: &'static str,
+    encode: impl FnMut(Row) -> Result<Vec<u8>, String> + Send + 'static,
+) -> Response
+where
+    Row: for<'r> FromRow<'r, PgRow> + Send + Unpin + 'static,
+{
+    stream_body(
+        db,
+        qb,
+        content_type,
+        filename,
+        operation,
+        Vec::new(),
+        encode,
+    )
+}
+
+/// `stream_encoded`, sending `header` before the rows.
+fn stream_body<Row>(
+    db: PgPool,
+    mut qb: QueryBuilder<'static, Postgres>,
+    content_type: &'static str,
+    filename: &str,
+    operation: &'static str,
+    header: Vec<u8>,
+    mut encode: impl FnMut(Row) -> Result<Vec<u8>, String> + Send + 'static,
+) -> Response
+where
+    Row: for<'r> FromRow<'r, PgRow> + Send + Unpin + 'static,
+{
+    let (tx, rx) = mpsc::channel::<Result<Bytes, std::io::Error>>(EXPORT_BUFFER_ROWS);
+
+    tokio::spawn(
+        async move {
+            if !header.is_empty() && tx.send(Ok(Bytes::from(header))).await.is_err() {
+                return;
+            }
+            let mut rows = qb.build_query_as::<Row>().fetch(&db);
+            loop {
+                let chunk = match rows.try_next().await {
+                    Ok(Some(row)) => encode(row),
+                    Ok(None) => break,
+                    Err(e) => Err(e.to_string()),
+                };
+
+                let chunk = chunk.map(Bytes::from).map_err(|e| {
+                    tracing::error!(cause = %e, "export aborted");
+                    std::io::Error::other(e)
+                });
+                let failed = chunk.is_err();
+                // the client went away
+                if tx.send(chunk).await.is_err() || failed {
+                    break;
+                }
+            }
+        }
+        .instrument(query_span(operation)),
+    );
+
+    let body = Body::from_stream(stream::unfold(rx, |mut rx| async move {
+        rx.recv().await.map(|chunk| (chunk, rx))
+    }));
+
+    let disposition = format!("attachment; filename=\"{filename}\"");
+    let mut response = body.into_response();
+    let headers = response.headers_mut();
+    headers.insert(CONTENT_TYPE, HeaderValue::from_static(content_type));
+    if let Ok(disposition) = HeaderValue::from_str(&disposition) {
+        headers.insert(CONTENT_DISPOSITION, disposition);
+    }
+    response
+}
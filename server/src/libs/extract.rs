@@ -0,0 +1,132 @@
+use axum::{
+    Json,
+    extract::{FromRequest, FromRequestParts, Path, Query, Request, rejection::JsonRejection},
+    http::request::Parts,
+};
+use serde::de::DeserializeOwned;
+use validator::{Validate, ValidationErrors, ValidationErrorsKind};
+
+use crate::libs::error::{ApiError, ErrorCode, FieldError};
+
+/// `Json<T>` that also runs `T::validate()`, rejecting with field-level
+/// `VALIDATION_FAILED` errors.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ValidatedJson<T>(pub T);
+
+impl<T, S> FromRequest<S> for ValidatedJson<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let Json(value) = Json::<T>::from_request(req, state)
+            .await
+            .map_err(json_rejection)?;
+
+        value.validate().map_err(|e| {
+            ApiError::new(ErrorCode::ValidationFailed, "Request validation failed")
+                .with_fields(field_errors(&e))
+        })?;
+
+        Ok(ValidatedJson(value))
+    }
+}
+
+/// `Path<T>` whose parse failures (e.g. a malformed address segment) come
+/// back as API errors.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ValidatedPath<T>(pub T);
+
+impl<T, S> FromRequestParts<S> for ValidatedPath<T>
+where
+    T: DeserializeOwned + Send,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Path(value) = Path::<T>::from_request_parts(parts, state)
+            .await
+            .map_err(|e| ApiError::bad_request(e.body_text()))?;
+        Ok(ValidatedPath(value))
+    }
+}
+
+/// `Query<T>` that also runs `T::validate()`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ValidatedQuery<T>(pub T);
+
+impl<T, S> FromRequestParts<S> for ValidatedQuery<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Query(value) = Query::<T>::from_request_parts(parts, state)
+            .await
+            .map_err(|e| ApiError::new(ErrorCode::ValidationFailed, e.body_text()))?;
+
+        value.validate().map_err(|e| {
+            ApiError::new(ErrorCode::ValidationFailed, "Query validation failed")
+                .with_fields(field_errors(&e))
+        })?;
+
+        Ok(ValidatedQuery(value))
+    }
+}
+
+fn json_rejection(rejection: JsonRejection) -> ApiError {
+    match rejection {
+        JsonRejection::MissingJsonContentType(_) => ApiError::new(
+            ErrorCode::UnsupportedMediaType,
+            "Expected request with `Content-Type: application/json`",
+        ),
+        JsonRejection::JsonDataError(e) => {
+            ApiError::new(ErrorCode::ValidationFailed, e.body_text())
+        }
+        rejection => ApiError::bad_request(rejection.body_text()),
+    }
+}
+
+/// Flattens nested validator errors into `members[1].addr` style paths.
+pub fn field_errors(errors: &ValidationErrors) -> Vec<FieldError> {
+    let mut out = Vec::new();
+    collect_field_errors(errors, "", &mut out);
+    out.sort_by(|a, b| a.field.cmp(&b.field));
+    out
+}
+
+fn collect_field_errors(errors: &ValidationErrors, prefix: &str, out: &mut Vec<FieldError>) {
+    for (field, kind) in errors.errors() {
+        let path = match (*field, prefix.is_empty()) {
+            ("__all__", _) => prefix.to_owned(),
+            (field, true) => field.to_owned(),
+            (field, false) => format!("{prefix}.{field}"),
+        };
+
+        match kind {
+            ValidationErrorsKind::Field(errors) => {
+                out.extend(errors.iter().map(|e| {
+                    // struct-level (`schema`) rules name the field they are about
+                    let path = match e.params.get("field").and_then(|field| field.as_str()) {
+                        Some(field) if path.is_empty() => field.to_owned(),
+                        Some(field) => format!("{path}.{field}"),
+                        None => path.clone(),
+                    };
+                    let message = e.message.clone().unwrap_or_else(|| e.code.clone());
+                    FieldError::new(path, e.code.clone(), message)
+                }));
+            }
+            ValidationErrorsKind::Struct(errors) => collect_field_errors(errors, &path, out),
+            ValidationErrorsKind::List(items) => {
+                for (index, errors) in items {
+                    collect_field_errors(errors, &format!("{path}[{index}]"), out);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,166 @@
+use std::{env::var, sync::LazyLock};
+
+use bigdecimal::BigDecimal;
+use sqlx::PgConnection;
+use tracing::Instrument;
+
+use crate::libs::{
+    db::query_span,
+    notification::{self, LOW_USAGE},
+};
+
+/// Days of payouts the burn rate is averaged over unless asked otherwise.
+pub const DEFAULT_WINDOW_DAYS: i32 = 30;
+
+/// Usages remaining at which creators are warned, for groups without their
+/// own thresholds. Set `LOW_USAGE_THRESHOLDS`, e.g. `20,5,0`.
+static LOW_USAGE_THRESHOLDS: LazyLock<Vec<i32>> = LazyLock::new(|| {
+    var("LOW_USAGE_THRESHOLDS")
+        .ok()
+        .and_then(|thresholds| {
+            thresholds
+                .split(',')
+                .map(|threshold| threshold.trim().parse().ok().filter(|t| *t >= 0))
+                .collect()
+        })
+        .unwrap_or_else(|| vec![10, 3])
+});
+
+pub fn default_thresholds() -> &'static [i32] {
+    &LOW_USAGE_THRESHOLDS
+}
+
+/// How fast a group spends its usages. Each recorded payout takes one.
+#[derive(Debug)]
+pub struct Forecast {
+    pub usage_remaining: BigDecimal,
+    pub window_days: i32,
+    pub payouts_in_window: i64,
+    /// Payouts per day over the window, or since creation for younger groups.
+    pub burn_rate: BigDecimal,
+    pub last_payout_at: Option<String>,
+    /// `None` while nothing is being spent.
+    pub days_remaining: Option<BigDecimal>,
+    pub projected_exhaustion_at: Option<String>,
+    pub thresholds: Vec<i32>,
+}
+
+/// Returns `None` for an unknown group.
+pub async fn forecast(
+    conn: &mut PgConnection,
+    group_address: &str,
+    window_days: i32,
+) -> Result<Option<Forecast>, sqlx::Error> {
+    let row = sqlx::query!(
+        r#"
+        SELECT g.usage_remaining, g.low_usage_thresholds, w.payouts as "payouts!",
+            w.last_paid_at::text as last_payout_at,
+            ROUND(w.payouts / w.days, 4) as "burn_rate!",
+            CASE WHEN w.payouts > 0 THEN ROUND(g.usage_remaining * w.days / w.payouts, 1) END
+                as days_remaining,
+            CASE WHEN w.payouts > 0 AND g.usage_remaining > 0
+                THEN (NOW() + g.usage_remaining * w.days / w.payouts * INTERVAL '1 day')::text
+            END as projected_exhaustion_at
+        FROM groups g
+        CROSS JOIN LATERAL (
+            SELECT COUNT(p.tx_hash) as payouts,
+                (SELECT MAX(paid_at) FROM payments WHERE group_address = g.group_address)
+                    as last_paid_at,
+                -- a group's first day counts in full so a burst doesn't look endless
+                GREATEST(LEAST($2::int::numeric,
+                    EXTRACT(EPOCH FROM NOW() - g.created_at)::numeric / 86400), 1) as days
+            FROM payments p
+            WHERE p.group_address = g.group_address
+                AND p.paid_at >= NOW() - make_interval(days => $2::int)
+        ) w
+        WHERE g.group_address = $1
+        "#,
+        group_address,
+        window_days
+    )
+    .fetch_optional(&mut *conn)
+    .instrument(query_span("select_usage_forecast"))
+    .await?;
+
+    Ok(row.map(|row| Forecast {
+        usage_remaining: row.usage_remaining,
+        window_days,
+        payouts_in_window: row.payouts,
+        burn_rate: row.burn_rate,
+        last_payout_at: row.last_payout_at,
+        days_remaining: row.days_remaining,
+        projected_exhaustion_at: row.projected_exhaustion_at,
+        thresholds: row
+            .low_usage_thresholds
+            .unwrap_or_else(|| default_thresholds().to_vec()),
+    }))
+}
+
+/// Warns the creator when usage has dropped to one of the group's
+/// thresholds. Each threshold warns once until usage rises above it again,
+/// and a drop past several thresholds sends one notification. Call it once
+/// usage and payouts are written, so the burn rate includes the latest.
+/// Returns the lowest threshold newly reached.
+pub async fn check_thresholds(
+    conn: &mut PgConnection,
+    group_address: &str,
+) -> Result<Option<i32>, sqlx::Error> {
+    let Some(forecast) = forecast(conn, group_address, DEFAULT_WINDOW_DAYS).await? else {
+        return Ok(None);
+    };
+
+    sqlx::query!(
+        r#"
+        DELETE FROM group_usage_alerts
+        WHERE group_address = $1 AND (threshold < $2::numeric OR threshold <> ALL($3))
+        "#,
+        group_address,
+        forecast.usage_remaining,
+        &forecast.thresholds
+    )
+    .execute(&mut *conn)
+    .instrument(query_span("rearm_usage_alerts"))
+    .await?;
+
+    let reached = sqlx::query_scalar!(
+        r#"
+        INSERT INTO group_usage_alerts (group_address, threshold, usage_remaining)
+        SELECT $1, t.threshold, $2::numeric
+        FROM UNNEST($3::int[]) AS t(threshold)
+        WHERE t.threshold >= $2::numeric
+        ON CONFLICT (group_address, threshold) DO NOTHING
+        RETURNING threshold
+        "#,
+        group_address,
+        forecast.usage_remaining,
+        &forecast.thresholds
+    )
+    .fetch_all(&mut *conn)
+    .instrument(query_span("insert_usage_alerts"))
+    .await?;
+
+    let Some(threshold) = reached.into_iter().min() else {
+        return Ok(None);
+    };
+
+    let message = if forecast.usage_remaining == BigDecimal::from(0) {
+        format!("Group {group_address} has no usages left; payouts fail until it is topped up")
+    } else {
+        let outlook = match &forecast.days_remaining {
+            Some(days) => format!(
+                "at {} payouts a day it runs out in about {} days",
+                forecast.burn_rate.normalized(),
+                days.normalized()
+            ),
+            None => "no payouts were made recently".to_owned(),
+        };
+        format!(
+            "Group {group_address} has {} usages left; {outlook}. Top it up to keep payouts going",
+            forecast.usage_remaining
+        )
+    };
+    notification::notify_creator(conn, group_address, LOW_USAGE, &message).await?;
+    tracing::info!(threshold, "Low usage alert for {group_address}");
+
+    Ok(Some(threshold))
+}
@@ -0,0 +1,80 @@
+use sqlx::PgConnection;
+use tracing::Instrument;
+use uuid::Uuid;
+
+use crate::libs::db::query_span;
+
+/// Status as clients see it: unpaid invoices past `due_at` read as
+/// `overdue`. Expects `invoices` aliased as `i`.
+pub const STATUS_SQL: &str = "CASE WHEN i.status IN ('open', 'partially_paid') AND i.due_at < NOW() \
+     THEN 'overdue' ELSE i.status END";
+
+/// Crockford base32: no I, L, O or U, so IDs survive being read aloud.
+const ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+/// Shareable invoice ID, e.g. `INV-7K3M9Q2XWD`: 50 random bits.
+pub fn new_public_id() -> String {
+    let bits = Uuid::new_v4().as_u128();
+    let id: String = (0..10)
+        .map(|i| ALPHABET[((bits >> (i * 5)) & 31) as usize] as char)
+        .collect();
+    format!("INV-{id}")
+}
+
+/// Applies a recorded transfer to the unpaid invoice of its group and token
+/// it most likely pays, in order of preference:
+///
+/// - `memo`: the transfer's memo is the invoice ID or reference
+/// - `payer`: the invoice is addressed to the sender
+/// - `amount`: an invoice open to anyone has exactly the amount outstanding
+///
+/// Ties go to the invoice due first. Returns the public ID of the invoice
+/// paid, if any. Transfers are applied once.
+pub async fn match_transfer(
+    conn: &mut PgConnection,
+    tx_hash: &str,
+) -> Result<Option<String>, sqlx::Error> {
+    sqlx::query_scalar!(
+        r#"
+        WITH t AS (
+            SELECT tx_hash, group_address, token_address, from_address, token_amount,
+                NULLIF(TRIM(memo), '') AS memo
+            FROM group_tx_hashes
+            WHERE tx_hash = $1
+                AND NOT EXISTS (SELECT 1 FROM invoice_payments WHERE tx_hash = $1)
+        ), candidate AS (
+            SELECT i.id, m.matched_by
+            FROM t
+            JOIN invoices i ON i.group_address = t.group_address
+                AND i.token_address = t.token_address
+                AND i.status IN ('open', 'partially_paid')
+            CROSS JOIN LATERAL (SELECT CASE
+                WHEN UPPER(t.memo) = i.public_id OR LOWER(t.memo) = LOWER(i.reference) THEN 'memo'
+                WHEN i.payer_address = t.from_address THEN 'payer'
+                WHEN i.payer_address IS NULL AND i.amount - i.paid_amount = t.token_amount THEN 'amount'
+            END AS matched_by) m
+            WHERE m.matched_by IS NOT NULL
+            ORDER BY CASE m.matched_by WHEN 'memo' THEN 0 WHEN 'payer' THEN 1 ELSE 2 END,
+                i.due_at, i.created_at
+            LIMIT 1
+            FOR UPDATE OF i
+        ), applied AS (
+            INSERT INTO invoice_payments (invoice_id, tx_hash, amount, matched_by)
+            SELECT c.id, t.tx_hash, t.token_amount, c.matched_by
+            FROM candidate c, t
+            RETURNING invoice_id, amount
+        )
+        UPDATE invoices i
+        SET paid_amount = i.paid_amount + a.amount,
+            status = CASE WHEN i.paid_amount + a.amount >= i.amount THEN 'paid' ELSE 'partially_paid' END,
+            paid_at = CASE WHEN i.paid_amount + a.amount >= i.amount THEN NOW() END
+        FROM applied a
+        WHERE i.id = a.invoice_id
+        RETURNING i.public_id
+        "#,
+        tx_hash
+    )
+    .fetch_optional(&mut *conn)
+    .instrument(query_span("match_transfer_invoice"))
+    .await
+}
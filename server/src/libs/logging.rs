@@ -1,17 +1,89 @@
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry_otlp::{SpanExporter, WithExportConfig};
+use opentelemetry_sdk::{Resource, trace::SdkTracerProvider};
 use tracing_subscriber::{EnvFilter, fmt, layer::SubscriberExt, util::SubscriberInitExt};
 
-pub fn init_tracing() {
+const DEFAULT_SERVICE_NAME: &str = "paymesh-server";
+
+/// Keeps the OTLP exporter alive and flushes pending spans when dropped.
+pub struct TracingGuard {
+    provider: Option<SdkTracerProvider>,
+}
+
+impl Drop for TracingGuard {
+    fn drop(&mut self) {
+        if let Some(provider) = self.provider.take()
+            && let Err(e) = provider.shutdown()
+        {
+            eprintln!("Failed to shut down tracer provider: {e}");
+        }
+    }
+}
+
+/// Installs the global subscriber.
+///
+/// `LOG_FORMAT=json` switches stdout logs to JSON lines, and setting
+/// `OTEL_EXPORTER_OTLP_ENDPOINT` also exports spans over OTLP/HTTP.
+pub fn init_tracing() -> TracingGuard {
     let filter = EnvFilter::try_from_default_env()
         .unwrap_or_else(|_| EnvFilter::new("info,tower_http=info,axum=info"));
 
+    let json = std::env::var("LOG_FORMAT").is_ok_and(|format| format.eq_ignore_ascii_case("json"));
+
+    let json_layer = json.then(|| {
+        fmt::layer()
+            .json()
+            .with_current_span(true)
+            .with_span_list(false)
+            .with_timer(fmt::time::UtcTime::rfc_3339())
+    });
+    let text_layer = (!json).then(|| {
+        fmt::layer()
+            .with_target(false)
+            .with_file(false)
+            .with_line_number(false)
+            .with_timer(fmt::time::UtcTime::rfc_3339())
+    });
+
+    let provider = std::env::var("OTEL_EXPORTER_OTLP_ENDPOINT")
+        .ok()
+        .filter(|endpoint| !endpoint.is_empty())
+        .map(otlp_provider);
+
+    let otel_layer = provider.as_ref().map(|provider| {
+        tracing_opentelemetry::layer().with_tracer(provider.tracer(DEFAULT_SERVICE_NAME))
+    });
+
     tracing_subscriber::registry()
         .with(filter)
-        .with(
-            fmt::layer()
-                .with_target(false)
-                .with_file(false)
-                .with_line_number(false)
-                .with_timer(fmt::time::UtcTime::rfc_3339()),
-        )
+        .with(json_layer)
+        .with(text_layer)
+        .with(otel_layer)
         .init();
+
+    if provider.is_some() {
+        tracing::info!("Exporting traces over OTLP");
+    }
+
+    TracingGuard { provider }
+}
+
+fn otlp_provider(endpoint: String) -> SdkTracerProvider {
+    let endpoint = format!("{}/v1/traces", endpoint.trim_end_matches('/'));
+    let exporter = SpanExporter::builder()
+        .with_http()
+        .with_endpoint(endpoint)
+        .build()
+        .expect("Failed to build OTLP span exporter");
+
+    let service_name =
+        std::env::var("OTEL_SERVICE_NAME").unwrap_or_else(|_| DEFAULT_SERVICE_NAME.to_owned());
+
+    let provider = SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(Resource::builder().with_service_name(service_name).build())
+        .build();
+
+    opentelemetry::global::set_tracer_provider(provider.clone());
+    provider
 }
@@ -1 +1,75 @@
+use std::{convert::Infallible, time::Duration};
 
+use axum::{
+    body::Body,
+    extract::{FromRequestParts, MatchedPath},
+    http::{HeaderName, HeaderValue, Request, Response, header::LINK, request::Parts},
+};
+use tracing::{Span, field::Empty};
+
+pub const REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+/// The `x-request-id` of the current request, either supplied by the caller
+/// or generated by `SetRequestIdLayer`.
+#[derive(Debug, Clone, Default)]
+pub struct RequestId(pub String);
+
+impl<S: Send + Sync> FromRequestParts<S> for RequestId {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(RequestId(read_request_id(
+            parts.headers.get(REQUEST_ID_HEADER),
+        )))
+    }
+}
+
+fn read_request_id(value: Option<&axum::http::HeaderValue>) -> String {
+    value
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default()
+        .to_owned()
+}
+
+/// Root span for every HTTP request, tagged with its request id.
+/// `http.route` is the route template (e.g. `/v1/groups/{address}`) so it
+/// stays low-cardinality; requests that match no route leave it empty.
+pub fn make_request_span(request: &Request<Body>) -> Span {
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(MatchedPath::as_str);
+    tracing::info_span!(
+        "http.request",
+        otel.kind = "server",
+        http.method = %request.method(),
+        http.route = route,
+        url.path = %request.uri().path(),
+        http.status_code = Empty,
+        request_id = %read_request_id(request.headers().get(REQUEST_ID_HEADER)),
+    )
+}
+
+pub fn on_response(response: &Response<Body>, latency: Duration, span: &Span) {
+    span.record("http.status_code", response.status().as_u16());
+    tracing::info!(
+        status = response.status().as_u16(),
+        latency_ms = latency.as_millis() as u64,
+        "request completed"
+    );
+}
+
+/// Marks responses from the pre-`/v1` routes as deprecated (RFC 8594 style)
+/// and points clients at the versioned API.
+pub async fn deprecated(mut response: Response<Body>) -> Response<Body> {
+    let headers = response.headers_mut();
+    headers.insert(
+        HeaderName::from_static("deprecation"),
+        HeaderValue::from_static("true"),
+    );
+    headers.insert(
+        LINK,
+        HeaderValue::from_static("</v1>; rel=\"successor-version\""),
+    );
+    response
+}
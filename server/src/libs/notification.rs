@@ -0,0 +1,36 @@
+use sqlx::PgConnection;
+use tracing::Instrument;
+
+use crate::libs::db::query_span;
+
+/// A recurring payment was not received by the end of its grace period.
+pub const RECURRING_PAYMENT_MISSED: &str = "recurring_payment_missed";
+
+/// A group's usage dropped to one of its low-usage thresholds.
+pub const LOW_USAGE: &str = "low_usage";
+
+/// Queues `message` for the creator of `group_address`, who reads it from
+/// `/v1/creators/{address}/notifications`.
+pub async fn notify_creator(
+    conn: &mut PgConnection,
+    group_address: &str,
+    kind: &str,
+    message: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+        INSERT INTO notifications (recipient, group_address, kind, message)
+        SELECT created_by, group_address, $2, $3
+        FROM groups
+        WHERE group_address = $1
+        "#,
+        group_address,
+        kind,
+        message
+    )
+    .execute(&mut *conn)
+    .instrument(query_span("insert_notification"))
+    .await?;
+
+    Ok(())
+}
@@ -0,0 +1,203 @@
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use sqlx::{FromRow, Postgres, QueryBuilder};
+
+use crate::libs::error::{ApiError, ErrorCode};
+
+/// One page of a list endpoint. `next_cursor` is absent on the last page.
+#[derive(Debug, Serialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub next_cursor: Option<String>,
+}
+
+impl<T> Page<T> {
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> Page<U> {
+        Page {
+            items: self.items.into_iter().map(f).collect(),
+            next_cursor: self.next_cursor,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    Asc,
+    #[default]
+    Desc,
+}
+
+impl SortOrder {
+    fn sql(self) -> &'static str {
+        match self {
+            SortOrder::Asc => "ASC",
+            SortOrder::Desc => "DESC",
+        }
+    }
+
+    fn after(self) -> &'static str {
+        match self {
+            SortOrder::Asc => ">",
+            SortOrder::Desc => "<",
+        }
+    }
+}
+
+/// A whitelisted sort field of a list endpoint. Columns must be `NOT NULL`
+/// so the keyset comparison is total.
+pub trait SortField: Copy + Default + PartialEq + Serialize + DeserializeOwned {
+    /// SQL expression to order by.
+    fn column(self) -> &'static str;
+    /// Postgres type the textual cursor value is cast back to.
+    fn sql_type(self) -> &'static str;
+}
+
+/// The unique column that breaks ties between rows with equal sort values.
+#[derive(Debug, Clone, Copy)]
+pub struct KeyColumn {
+    pub column: &'static str,
+    pub sql_type: &'static str,
+}
+
+/// Opaque position after the last row of a page: the sort it was issued for,
+/// that row's sort value (as Postgres text) and its unique key.
+#[derive(Debug, Serialize, Deserialize)]
+struct Cursor<F> {
+    sort: F,
+    order: SortOrder,
+    value: String,
+    key: String,
+}
+
+impl<F: SortField> Cursor<F> {
+    fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(serde_json::to_vec(self).expect("cursor serializes"))
+    }
+
+    fn decode(cursor: &str) -> Result<Self, ApiError> {
+        URL_SAFE_NO_PAD
+            .decode(cursor)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .ok_or_else(invalid_cursor)
+    }
+}
+
+fn invalid_cursor() -> ApiError {
+    ApiError::new(
+        ErrorCode::InvalidCursor,
+        "Cursor is malformed or does not match the requested sort",
+    )
+}
+
+/// A list row together with the values its cursor is built from. Queries
+/// select them as `sort_value` and `cursor_key`, both cast to text.
+#[derive(Debug, FromRow)]
+pub struct Keyed<T> {
+    #[sqlx(flatten)]
+    pub item: T,
+    pub sort_value: String,
+    pub cursor_key: String,
+}
+
+/// Keyset pagination state for one list request.
+#[derive(Debug)]
+pub struct Keyset<F> {
+    key: KeyColumn,
+    sort: F,
+    order: SortOrder,
+    after: Option<Cursor<F>>,
+    limit: i64,
+}
+
+impl<F: SortField> Keyset<F> {
+    /// A cursor carries its own sort, so follow-up requests may omit
+    /// `sort`/`order`; passing different ones is rejected.
+    pub fn new(
+        key: KeyColumn,
+        sort: Option<F>,
+        order: Option<SortOrder>,
+        cursor: Option<&str>,
+        limit: i64,
+    ) -> Result<Self, ApiError> {
+        let after = cursor.map(Cursor::<F>::decode).transpose()?;
+
+        let (sort, order) = match &after {
+            Some(after) => {
+                if sort.is_some_and(|sort| sort != after.sort)
+                    || order.is_some_and(|order| order != after.order)
+                {
+                    return Err(invalid_cursor());
+                }
+                (after.sort, after.order)
+            }
+            None => (sort.unwrap_or_default(), order.unwrap_or_default()),
+        };
+
+        Ok(Self {
+            key,
+            sort,
+            order,
+            after,
+            limit,
+        })
+    }
+
+    /// Selects `sort_value` and `cursor_key`; push right after the row columns.
+    pub fn push_select(&self, qb: &mut QueryBuilder<'_, Postgres>) {
+        qb.push(format_args!(
+            ", {}::text AS sort_value, {}::text AS cursor_key",
+            self.sort.column(),
+            self.key.column
+        ));
+    }
+
+    /// Appends `AND (sort, key) > (cursor)` (or `<` when descending).
+    pub fn push_after(&self, qb: &mut QueryBuilder<'_, Postgres>) {
+        let Some(after) = &self.after else {
+            return;
+        };
+        qb.push(format_args!(
+            " AND ({}, {}) {} (CAST(",
+            self.sort.column(),
+            self.key.column,
+            self.order.after()
+        ))
+        .push_bind(after.value.clone())
+        .push(format_args!(" AS {}), CAST(", self.sort.sql_type()))
+        .push_bind(after.key.clone())
+        .push(format_args!(" AS {}))", self.key.sql_type));
+    }
+
+    /// Appends `ORDER BY .. LIMIT`, fetching one extra row to detect the end.
+    pub fn push_order_limit(&self, qb: &mut QueryBuilder<'_, Postgres>) {
+        let order = self.order.sql();
+        qb.push(format_args!(
+            " ORDER BY {} {order}, {} {order} LIMIT ",
+            self.sort.column(),
+            self.key.column
+        ))
+        .push_bind(self.limit + 1);
+    }
+
+    pub fn page<T>(&self, mut rows: Vec<Keyed<T>>) -> Page<T> {
+        let has_more = rows.len() as i64 > self.limit;
+        rows.truncate(self.limit as usize);
+
+        let next_cursor = rows.last().filter(|_| has_more).map(|last| {
+            Cursor {
+                sort: self.sort,
+                order: self.order,
+                value: last.sort_value.clone(),
+                key: last.cursor_key.clone(),
+            }
+            .encode()
+        });
+
+        Page {
+            items: rows.into_iter().map(|row| row.item).collect(),
+            next_cursor,
+        }
+    }
+}
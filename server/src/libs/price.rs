@@ -0,0 +1,214 @@
+use std::{collections::BTreeMap, fmt, path::PathBuf, str::FromStr, time::Duration};
+
+use anyhow::{Context, Result};
+use bigdecimal::BigDecimal;
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de};
+use sqlx::PgPool;
+use time::OffsetDateTime;
+use tracing::Instrument;
+
+use crate::{
+    libs::db::query_span,
+    util::{address::Address, token},
+};
+
+/// Fiat values are reported rounded to this many decimals.
+pub const FIAT_SCALE: i64 = 2;
+
+/// An ISO 4217 currency code such as `USD`; accepted in any case, stored
+/// upper case.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Currency(String);
+
+impl Currency {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for Currency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl FromStr for Currency {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.len() == 3 && s.bytes().all(|b| b.is_ascii_alphabetic()) {
+            Ok(Currency(s.to_ascii_uppercase()))
+        } else {
+            Err("must be a three-letter currency code")
+        }
+    }
+}
+
+impl Serialize for Currency {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for Currency {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = <std::borrow::Cow<'de, str>>::deserialize(deserializer)?;
+        s.parse()
+            .map_err(|e| de::Error::custom(format!("invalid currency `{s}`: {e}")))
+    }
+}
+
+/// Price of one whole token in `currency` at `priced_at`.
+#[derive(Debug, Clone)]
+pub struct PricePoint {
+    pub token_address: String,
+    pub currency: Currency,
+    pub priced_at: OffsetDateTime,
+    pub price: BigDecimal,
+}
+
+/// Where historical prices come from. Sources only report prices; [`sync`]
+/// stores them in `token_prices`, which valuation queries read.
+pub trait PriceSource: Send + Sync {
+    /// Recorded with each price it reported.
+    fn name(&self) -> &'static str;
+
+    /// Prices the source knows. Prices already stored are overwritten, so a
+    /// source may report its whole history every time.
+    fn prices(&self) -> impl Future<Output = Result<Vec<PricePoint>>> + Send;
+}
+
+/// Prices from a CSV file with the columns `token`, `currency`, `priced_at`
+/// (RFC 3339) and `price`. `token` is a supported token's symbol or address.
+#[derive(Debug, Clone)]
+pub struct FilePriceSource {
+    path: PathBuf,
+}
+
+#[derive(Deserialize)]
+struct FilePriceRow {
+    token: String,
+    currency: Currency,
+    #[serde(with = "time::serde::rfc3339")]
+    priced_at: OffsetDateTime,
+    price: BigDecimal,
+}
+
+impl FilePriceSource {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+impl PriceSource for FilePriceSource {
+    fn name(&self) -> &'static str {
+        "file"
+    }
+
+    async fn prices(&self) -> Result<Vec<PricePoint>> {
+        let contents = tokio::fs::read(&self.path)
+            .await
+            .with_context(|| format!("could not read {}", self.path.display()))?;
+
+        let mut reader = csv::Reader::from_reader(contents.as_slice());
+        let mut prices = Vec::new();
+        for (line, row) in reader.deserialize::<FilePriceRow>().enumerate() {
+            let row = row.with_context(|| format!("{} row {}", self.path.display(), line + 1))?;
+            let token_address = match row.token.parse::<Address>() {
+                Ok(address) => address.to_string(),
+                Err(_) => match token::TOKENS
+                    .iter()
+                    .find(|token| token.symbol.eq_ignore_ascii_case(&row.token))
+                {
+                    Some(token) => token.address.to_owned(),
+                    None => anyhow::bail!(
+                        "{} row {}: unknown token `{}`",
+                        self.path.display(),
+                        line + 1,
+                        row.token
+                    ),
+                },
+            };
+            prices.push(PricePoint {
+                token_address,
+                currency: row.currency,
+                priced_at: row.priced_at,
+                price: row.price,
+            });
+        }
+        Ok(prices)
+    }
+}
+
+/// Stores the prices `source` reports and returns how many were stored.
+/// Prices of tokens without known decimals can't value base units and are
+/// skipped.
+pub async fn sync(db: &PgPool, source: &impl PriceSource) -> Result<usize> {
+    let prices = source.prices().await?;
+
+    // one row per key, or the upsert would touch a row twice
+    let prices: BTreeMap<_, _> = prices
+        .into_iter()
+        .map(|price| {
+            (
+                (price.token_address, price.currency.0, price.priced_at),
+                price.price,
+            )
+        })
+        .collect();
+
+    let mut token_addresses = Vec::new();
+    let mut currencies = Vec::new();
+    let mut priced_at = Vec::new();
+    let mut amounts = Vec::new();
+    let mut decimals = Vec::new();
+    for ((token_address, currency, at), price) in prices {
+        let Some(token) = token::lookup(&token_address) else {
+            tracing::warn!(%token_address, "skipping price of unknown token");
+            continue;
+        };
+        token_addresses.push(token_address);
+        currencies.push(currency);
+        priced_at.push(at);
+        amounts.push(price);
+        decimals.push(token.decimals as i16);
+    }
+
+    let stored = sqlx::query!(
+        r#"
+        INSERT INTO token_prices (token_address, currency, priced_at, price, decimals, source)
+        SELECT token_address, currency, priced_at, price, decimals, $6
+        FROM UNNEST($1::text[], $2::text[], $3::timestamptz[], $4::numeric[], $5::smallint[])
+            AS p(token_address, currency, priced_at, price, decimals)
+        ON CONFLICT (token_address, currency, priced_at) DO UPDATE
+        SET price = EXCLUDED.price, decimals = EXCLUDED.decimals, source = EXCLUDED.source,
+            updated_at = NOW()
+        "#,
+        &token_addresses,
+        &currencies,
+        &priced_at,
+        &amounts,
+        &decimals,
+        source.name()
+    )
+    .execute(db)
+    .instrument(query_span("upsert_token_prices"))
+    .await?
+    .rows_affected();
+
+    Ok(stored as usize)
+}
+
+/// Syncs `source` now and then every `every`, logging failures.
+pub fn spawn_sync(db: PgPool, source: impl PriceSource + 'static, every: Duration) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(every);
+        loop {
+            interval.tick().await;
+            match sync(&db, &source).await {
+                Ok(stored) => tracing::info!(source = source.name(), stored, "prices synced"),
+                Err(e) => tracing::error!(source = source.name(), cause = %e, "price sync failed"),
+            }
+        }
+    });
+}
@@ -0,0 +1,203 @@
+use std::time::Duration;
+
+use bigdecimal::BigDecimal;
+use sqlx::PgPool;
+use time::{Date, OffsetDateTime};
+use tracing::Instrument;
+
+use crate::{
+    libs::{
+        db::query_span,
+        notification::{self, RECURRING_PAYMENT_MISSED},
+    },
+    util::token,
+};
+
+#[derive(Debug, Default)]
+pub struct CheckSummary {
+    pub opened: u64,
+    pub matched: u64,
+    pub missed: u64,
+}
+
+/// Brings recurring expectations up to `today` (UTC):
+///
+/// - opens an occurrence once `grace_days` before its due date
+/// - matches each open occurrence to the earliest unclaimed transfer from the
+///   payer, in the token and amount range, inside its window. The window runs
+///   from `grace_days` before the due date to `grace_days` before the next
+///   one; transfers after the grace period count as `late`
+/// - marks occurrences still pending after the grace period `missed` and
+///   alerts the group's creator. A later transfer can still turn them `late`
+///
+/// Occurrences whose grace period ended before the expectation was declared
+/// are marked without an alert.
+pub async fn check(db: &PgPool, today: Date) -> Result<CheckSummary, sqlx::Error> {
+    let mut summary = CheckSummary::default();
+    let mut tx = db.begin().await?;
+
+    summary.opened = sqlx::query!(
+        r#"
+        INSERT INTO recurring_occurrences (expectation_id, seq, due_on)
+        SELECT e.id, s.seq, d.due_on
+        FROM recurring_expectations e
+        CROSS JOIN LATERAL generate_series(
+            COALESCE((SELECT MAX(seq) + 1 FROM recurring_occurrences o WHERE o.expectation_id = e.id), 0),
+            recurring_seq_bound(e.starts_on, e.frequency, e.interval_count, $1::date + e.grace_days)
+        ) AS s(seq)
+        CROSS JOIN LATERAL recurring_due_on(e.starts_on, e.frequency, e.interval_count, s.seq) AS d(due_on)
+        WHERE e.cancelled_at IS NULL
+            AND d.due_on - e.grace_days <= $1::date
+            AND (e.ends_on IS NULL OR d.due_on <= e.ends_on)
+        ON CONFLICT DO NOTHING
+        "#,
+        today
+    )
+    .execute(&mut *tx)
+    .instrument(query_span("open_recurring_occurrences"))
+    .await?
+    .rows_affected();
+
+    // missed occurrences stay matchable until the next window opens
+    let open = sqlx::query!(
+        r#"
+        SELECT o.expectation_id, o.seq
+        FROM recurring_occurrences o
+        JOIN recurring_expectations e ON e.id = o.expectation_id
+        WHERE o.status = 'pending'
+            OR (o.status = 'missed'
+                AND recurring_due_on(e.starts_on, e.frequency, e.interval_count, o.seq + 1)
+                    - e.grace_days > $1::date)
+        ORDER BY o.expectation_id, o.seq
+        "#,
+        today
+    )
+    .fetch_all(&mut *tx)
+    .instrument(query_span("select_open_recurring_occurrences"))
+    .await?;
+
+    // one at a time, so earlier occurrences claim transfers first
+    for occurrence in open {
+        summary.matched += sqlx::query!(
+            r#"
+            WITH w AS (
+                SELECT e.group_address, e.payer_address, e.token_address, e.min_amount,
+                    e.max_amount,
+                    ((o.due_on - e.grace_days)::timestamp AT TIME ZONE 'UTC') AS opens_at,
+                    ((o.due_on + e.grace_days + 1)::timestamp AT TIME ZONE 'UTC') AS late_at,
+                    ((recurring_due_on(e.starts_on, e.frequency, e.interval_count, o.seq + 1)
+                        - e.grace_days)::timestamp AT TIME ZONE 'UTC') AS closes_at
+                FROM recurring_occurrences o
+                JOIN recurring_expectations e ON e.id = o.expectation_id
+                WHERE o.expectation_id = $1 AND o.seq = $2
+            ), t AS (
+                SELECT t.tx_hash, t.token_amount, t.created_at, w.late_at
+                FROM w
+                JOIN group_tx_hashes t ON t.group_address = w.group_address
+                    AND t.token_address = w.token_address
+                    AND t.from_address = w.payer_address
+                    AND t.token_amount BETWEEN w.min_amount AND w.max_amount
+                    AND t.created_at >= w.opens_at
+                    AND t.created_at < w.closes_at
+                WHERE NOT EXISTS (
+                    SELECT 1 FROM recurring_occurrences r WHERE r.tx_hash = t.tx_hash
+                )
+                ORDER BY t.created_at
+                LIMIT 1
+            )
+            UPDATE recurring_occurrences o
+            SET tx_hash = t.tx_hash, amount = t.token_amount, received_at = t.created_at,
+                status = CASE WHEN t.created_at < t.late_at THEN 'received' ELSE 'late' END
+            FROM t
+            WHERE o.expectation_id = $1 AND o.seq = $2
+            "#,
+            occurrence.expectation_id,
+            occurrence.seq
+        )
+        .execute(&mut *tx)
+        .instrument(query_span("match_recurring_occurrence"))
+        .await?
+        .rows_affected();
+    }
+
+    let missed = sqlx::query!(
+        r#"
+        UPDATE recurring_occurrences o SET status = 'missed', missed_at = NOW()
+        FROM recurring_expectations e
+        WHERE e.id = o.expectation_id
+            AND o.status = 'pending'
+            AND o.due_on + e.grace_days < $1::date
+        RETURNING e.group_address, e.payer_address, e.token_address, e.min_amount,
+            e.max_amount, e.description, o.due_on,
+            o.due_on + e.grace_days >= e.created_at::date as "alert!"
+        "#,
+        today
+    )
+    .fetch_all(&mut *tx)
+    .instrument(query_span("mark_recurring_occurrences_missed"))
+    .await?;
+
+    summary.missed = missed.len() as u64;
+    for occurrence in missed.into_iter().filter(|occurrence| occurrence.alert) {
+        let expected = expected_amount(
+            &occurrence.token_address,
+            &occurrence.min_amount,
+            &occurrence.max_amount,
+        );
+        let what = occurrence
+            .description
+            .map(|description| format!(" ({description})"))
+            .unwrap_or_default();
+        let message = format!(
+            "Missed recurring payment{what} of {expected} from {} due {}",
+            occurrence.payer_address, occurrence.due_on
+        );
+        notification::notify_creator(
+            &mut tx,
+            &occurrence.group_address,
+            RECURRING_PAYMENT_MISSED,
+            &message,
+        )
+        .await?;
+    }
+
+    tx.commit().await?;
+    Ok(summary)
+}
+
+/// `100 USDC` or `90-110 USDC`. Expectations are only created for supported
+/// tokens; base units cover one whose token was since dropped.
+fn expected_amount(token_address: &str, min: &BigDecimal, max: &BigDecimal) -> String {
+    let (min, max, unit) = match token::lookup(token_address) {
+        Some(token) => (
+            token.format_amount(min),
+            token.format_amount(max),
+            token.symbol,
+        ),
+        None => (min.to_string(), max.to_string(), "base units"),
+    };
+    if min == max {
+        format!("{min} {unit}")
+    } else {
+        format!("{min}-{max} {unit}")
+    }
+}
+
+/// Checks now and then every `every`, logging failures.
+pub fn spawn_checks(db: PgPool, every: Duration) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(every);
+        loop {
+            interval.tick().await;
+            match check(&db, OffsetDateTime::now_utc().date()).await {
+                Ok(summary) => tracing::info!(
+                    opened = summary.opened,
+                    matched = summary.matched,
+                    missed = summary.missed,
+                    "recurring payments checked"
+                ),
+                Err(e) => tracing::error!(cause = %e, "recurring payment check failed"),
+            }
+        }
+    });
+}
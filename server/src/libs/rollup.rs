@@ -0,0 +1,133 @@
+use bigdecimal::BigDecimal;
+use sqlx::{PgConnection, PgPool};
+use tracing::Instrument;
+
+use crate::libs::db::query_span;
+
+/// Adds one payment to the rollups. Call in the transaction that inserts the
+/// payment, after the group's row has been locked (e.g. by updating it), so
+/// concurrent first payments to a group can't both count it.
+pub async fn record_payment(
+    conn: &mut PgConnection,
+    group_address: &str,
+    token_address: &str,
+    amount: &BigDecimal,
+) -> Result<(), sqlx::Error> {
+    let first_for_group = sqlx::query_scalar!(
+        r#"SELECT NOT EXISTS(SELECT 1 FROM group_token_daily_rollups WHERE group_address = $1) as "first!""#,
+        group_address
+    )
+    .fetch_one(&mut *conn)
+    .instrument(query_span("select_group_has_rollups"))
+    .await?;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO group_token_daily_rollups (group_address, token_address, day, volume, payment_count)
+        VALUES ($1, $2, (NOW() AT TIME ZONE 'UTC')::date, $3, 1)
+        ON CONFLICT (group_address, token_address, day) DO UPDATE
+        SET volume = group_token_daily_rollups.volume + EXCLUDED.volume,
+            payment_count = group_token_daily_rollups.payment_count + 1
+        "#,
+        group_address,
+        token_address,
+        amount
+    )
+    .execute(&mut *conn)
+    .instrument(query_span("upsert_group_token_daily_rollup"))
+    .await?;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO token_rollups (token_address, volume, payment_count)
+        VALUES ($1, $2, 1)
+        ON CONFLICT (token_address) DO UPDATE
+        SET volume = token_rollups.volume + EXCLUDED.volume,
+            payment_count = token_rollups.payment_count + 1,
+            updated_at = NOW()
+        "#,
+        token_address,
+        amount
+    )
+    .execute(&mut *conn)
+    .instrument(query_span("upsert_token_rollup"))
+    .await?;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO payment_rollup_totals (group_count, payment_count)
+        VALUES ($1, 1)
+        ON CONFLICT (id) DO UPDATE
+        SET group_count = payment_rollup_totals.group_count + EXCLUDED.group_count,
+            payment_count = payment_rollup_totals.payment_count + 1,
+            updated_at = NOW()
+        "#,
+        i64::from(first_for_group)
+    )
+    .execute(&mut *conn)
+    .instrument(query_span("upsert_payment_rollup_totals"))
+    .await?;
+
+    Ok(())
+}
+
+/// Recomputes every rollup from `payments`. Payment inserts wait until it
+/// commits.
+pub async fn rebuild(db: &PgPool) -> Result<(), sqlx::Error> {
+    let mut tx = db.begin().await?;
+
+    sqlx::query!("LOCK TABLE payments IN SHARE MODE")
+        .execute(&mut *tx)
+        .instrument(query_span("lock_payments"))
+        .await?;
+
+    sqlx::query!("DELETE FROM group_token_daily_rollups")
+        .execute(&mut *tx)
+        .instrument(query_span("delete_group_token_daily_rollups"))
+        .await?;
+    sqlx::query!("DELETE FROM token_rollups")
+        .execute(&mut *tx)
+        .instrument(query_span("delete_token_rollups"))
+        .await?;
+    sqlx::query!("DELETE FROM payment_rollup_totals")
+        .execute(&mut *tx)
+        .instrument(query_span("delete_payment_rollup_totals"))
+        .await?;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO group_token_daily_rollups (group_address, token_address, day, volume, payment_count)
+        SELECT group_address, token_address, (paid_at AT TIME ZONE 'UTC')::date, SUM(amount), COUNT(*)
+        FROM payments
+        GROUP BY 1, 2, 3
+        "#
+    )
+    .execute(&mut *tx)
+    .instrument(query_span("rebuild_group_token_daily_rollups"))
+    .await?;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO token_rollups (token_address, volume, payment_count)
+        SELECT token_address, SUM(amount), COUNT(*)
+        FROM payments
+        GROUP BY token_address
+        "#
+    )
+    .execute(&mut *tx)
+    .instrument(query_span("rebuild_token_rollups"))
+    .await?;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO payment_rollup_totals (group_count, payment_count)
+        SELECT COUNT(DISTINCT group_address), COUNT(*)
+        FROM payments
+        "#
+    )
+    .execute(&mut *tx)
+    .instrument(query_span("rebuild_payment_rollup_totals"))
+    .await?;
+
+    tx.commit().await
+}
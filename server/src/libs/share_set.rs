@@ -0,0 +1,118 @@
+use bigdecimal::BigDecimal;
+use sqlx::PgConnection;
+use time::OffsetDateTime;
+use tracing::Instrument;
+use uuid::Uuid;
+
+use crate::libs::db::query_span;
+
+/// Makes `members` the group's share set from `valid_from` on, closing the
+/// current version at that instant, and mirrors it into `group_members`.
+/// Payments made since `valid_from` are relinked to the new version; their
+/// hashes are returned so they can be re-audited.
+///
+/// Call in a transaction, after locking the group's row, with `valid_from`
+/// later than the current version's start.
+pub async fn open(
+    conn: &mut PgConnection,
+    group_address: &str,
+    members: &[(String, BigDecimal)],
+    valid_from: OffsetDateTime,
+) -> Result<(i32, Vec<String>), sqlx::Error> {
+    sqlx::query!(
+        "UPDATE group_share_sets SET valid_to = $2 WHERE group_address = $1 AND valid_to IS NULL",
+        group_address,
+        valid_from
+    )
+    .execute(&mut *conn)
+    .instrument(query_span("close_share_set"))
+    .await?;
+
+    let share_set = sqlx::query!(
+        r#"
+        INSERT INTO group_share_sets (group_address, version, valid_from)
+        SELECT $1::text, COALESCE(MAX(version), 0) + 1, $2
+        FROM group_share_sets WHERE group_address = $1
+        RETURNING id, version
+        "#,
+        group_address,
+        valid_from
+    )
+    .fetch_one(&mut *conn)
+    .instrument(query_span("insert_share_set"))
+    .await?;
+
+    let (addresses, percentages): (Vec<String>, Vec<BigDecimal>) = members.iter().cloned().unzip();
+
+    sqlx::query!(
+        r#"
+        INSERT INTO group_share_set_members (share_set_id, member_address, member_percentage)
+        SELECT $1, member_address, member_percentage
+        FROM UNNEST($2::text[], $3::numeric[]) AS m(member_address, member_percentage)
+        "#,
+        share_set.id,
+        &addresses,
+        &percentages
+    )
+    .execute(&mut *conn)
+    .instrument(query_span("insert_share_set_members"))
+    .await?;
+
+    sync_group_members(conn, group_address, share_set.id).await?;
+
+    let relinked = sqlx::query_scalar!(
+        r#"
+        UPDATE payments SET share_set_id = $2
+        WHERE group_address = $1 AND paid_at >= $3
+        RETURNING tx_hash
+        "#,
+        group_address,
+        share_set.id,
+        valid_from
+    )
+    .fetch_all(&mut *conn)
+    .instrument(query_span("relink_payments_share_set"))
+    .await?;
+
+    Ok((share_set.version, relinked))
+}
+
+/// `group_members` keeps the current composition: members of the share set
+/// are (re)activated with its percentages, everyone else is deactivated.
+async fn sync_group_members(
+    conn: &mut PgConnection,
+    group_address: &str,
+    share_set_id: Uuid,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+        UPDATE group_members gm SET is_active = false
+        WHERE gm.group_address = $1 AND NOT EXISTS (
+            SELECT 1 FROM group_share_set_members sm
+            WHERE sm.share_set_id = $2 AND sm.member_address = gm.member_address
+        )
+        "#,
+        group_address,
+        share_set_id
+    )
+    .execute(&mut *conn)
+    .instrument(query_span("deactivate_group_members"))
+    .await?;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO group_members (group_address, member_address, member_percentage)
+        SELECT $1, member_address, member_percentage
+        FROM group_share_set_members WHERE share_set_id = $2
+        ON CONFLICT (group_address, member_address) DO UPDATE
+        SET member_percentage = EXCLUDED.member_percentage, is_active = true
+        "#,
+        group_address,
+        share_set_id
+    )
+    .execute(&mut *conn)
+    .instrument(query_span("upsert_group_members"))
+    .await?;
+
+    Ok(())
+}
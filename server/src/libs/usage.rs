@@ -0,0 +1,240 @@
+use bigdecimal::BigDecimal;
+use sqlx::PgConnection;
+use tracing::Instrument;
+
+use crate::libs::db::query_span;
+
+/// Why a group's usage changed; see `group_usage_ledger`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UsageKind {
+    Purchase,
+    TopUp,
+    Consumption,
+    Adjustment,
+}
+
+impl UsageKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            UsageKind::Purchase => "purchase",
+            UsageKind::TopUp => "top_up",
+            UsageKind::Consumption => "consumption",
+            UsageKind::Adjustment => "adjustment",
+        }
+    }
+}
+
+/// A ledger entry to append; the group's `usage_remaining` becomes
+/// `usage_after`.
+#[derive(Debug)]
+struct Entry<'a> {
+    kind: UsageKind,
+    delta: BigDecimal,
+    usage_after: BigDecimal,
+    tx_hash: Option<&'a str>,
+    reason: Option<&'a str>,
+    recorded_by: Option<&'a str>,
+}
+
+/// Locks the group and returns its usage remaining, `None` if unknown.
+async fn lock_usage(
+    conn: &mut PgConnection,
+    group_address: &str,
+) -> Result<Option<BigDecimal>, sqlx::Error> {
+    sqlx::query_scalar!(
+        "SELECT usage_remaining FROM groups WHERE group_address = $1 FOR UPDATE",
+        group_address
+    )
+    .fetch_optional(&mut *conn)
+    .instrument(query_span("lock_group_usage"))
+    .await
+}
+
+async fn append(
+    conn: &mut PgConnection,
+    group_address: &str,
+    entry: Entry<'_>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        "UPDATE groups SET usage_remaining = $2 WHERE group_address = $1",
+        group_address,
+        entry.usage_after
+    )
+    .execute(&mut *conn)
+    .instrument(query_span("update_group_usage"))
+    .await?;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO group_usage_ledger
+            (group_address, kind, delta, usage_after, tx_hash, reason, recorded_by)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        "#,
+        group_address,
+        entry.kind.as_str(),
+        entry.delta,
+        entry.usage_after,
+        entry.tx_hash,
+        entry.reason,
+        entry.recorded_by
+    )
+    .execute(&mut *conn)
+    .instrument(query_span("insert_usage_ledger_entry"))
+    .await?;
+
+    Ok(())
+}
+
+/// Books the usage bought with a new group.
+pub async fn purchase(
+    conn: &mut PgConnection,
+    group_address: &str,
+    usage: &BigDecimal,
+) -> Result<(), sqlx::Error> {
+    append(
+        conn,
+        group_address,
+        Entry {
+            kind: UsageKind::Purchase,
+            delta: usage.clone(),
+            usage_after: usage.clone(),
+            tx_hash: None,
+            reason: None,
+            recorded_by: None,
+        },
+    )
+    .await
+}
+
+/// Outcome of a top-up.
+#[derive(Debug)]
+pub enum ToppedUp {
+    /// The usage bought.
+    Booked(BigDecimal),
+    GroupNotFound,
+    /// A top-up with this `tx_hash` is already booked, e.g. a redelivered
+    /// event.
+    AlreadyBooked,
+}
+
+/// `SubscriptionTopped` reports the new total, so the usage bought is the
+/// difference from the current balance.
+pub async fn top_up(
+    conn: &mut PgConnection,
+    group_address: &str,
+    usage_after: BigDecimal,
+    tx_hash: Option<&str>,
+) -> Result<ToppedUp, sqlx::Error> {
+    let Some(usage_before) = lock_usage(conn, group_address).await? else {
+        return Ok(ToppedUp::GroupNotFound);
+    };
+
+    if let Some(tx_hash) = tx_hash {
+        let booked = sqlx::query_scalar!(
+            r#"
+            SELECT EXISTS (
+                SELECT 1 FROM group_usage_ledger WHERE kind = $1 AND tx_hash = $2
+            ) as "booked!"
+            "#,
+            UsageKind::TopUp.as_str(),
+            tx_hash
+        )
+        .fetch_one(&mut *conn)
+        .instrument(query_span("select_top_up_booked"))
+        .await?;
+        if booked {
+            return Ok(ToppedUp::AlreadyBooked);
+        }
+    }
+
+    let delta = &usage_after - usage_before;
+
+    append(
+        conn,
+        group_address,
+        Entry {
+            kind: UsageKind::TopUp,
+            delta: delta.clone(),
+            usage_after,
+            tx_hash,
+            reason: None,
+            recorded_by: None,
+        },
+    )
+    .await?;
+
+    Ok(ToppedUp::Booked(delta))
+}
+
+/// Books the usage one payout consumed. The contract always takes one;
+/// `usage_after` is what it reported, so an entry that doesn't follow from
+/// the previous one shows where the ledger and the contract diverged.
+/// Returns `false` for an unknown group.
+pub async fn consume(
+    conn: &mut PgConnection,
+    group_address: &str,
+    usage_after: BigDecimal,
+    tx_hash: &str,
+) -> Result<bool, sqlx::Error> {
+    if lock_usage(conn, group_address).await?.is_none() {
+        return Ok(false);
+    }
+
+    append(
+        conn,
+        group_address,
+        Entry {
+            kind: UsageKind::Consumption,
+            delta: BigDecimal::from(-1),
+            usage_after,
+            tx_hash: Some(tx_hash),
+            reason: None,
+            recorded_by: None,
+        },
+    )
+    .await?;
+
+    Ok(true)
+}
+
+/// Outcome of a manual adjustment.
+#[derive(Debug)]
+pub enum Adjusted {
+    Applied(BigDecimal),
+    GroupNotFound,
+    /// The adjustment would take usage below zero.
+    Negative(BigDecimal),
+}
+
+/// Corrects a group's usage by `delta`, recording who did it and why.
+pub async fn adjust(
+    conn: &mut PgConnection,
+    group_address: &str,
+    delta: BigDecimal,
+    reason: &str,
+    recorded_by: &str,
+) -> Result<Adjusted, sqlx::Error> {
+    let Some(usage_before) = lock_usage(conn, group_address).await? else {
+        return Ok(Adjusted::GroupNotFound);
+    };
+    let usage_after = &usage_before + &delta;
+    if usage_after < BigDecimal::from(0) {
+        return Ok(Adjusted::Negative(usage_before));
+    }
+
+    append(
+        conn,
+        group_address,
+        Entry {
+            kind: UsageKind::Adjustment,
+            delta,
+            usage_after: usage_after.clone(),
+            tx_hash: None,
+            reason: Some(reason),
+            recorded_by: Some(recorded_by),
+        },
+    )
+    .await?;
+
+    Ok(Adjusted::Applied(usage_after))
+}
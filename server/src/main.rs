@@ -1,7 +1,15 @@
 use server::{
     AppState,
-    libs::{cache::init_cache, db::Db, logging::init_tracing},
+    libs::{
+        audit,
+        cache::init_cache,
+        db::Db,
+        logging::init_tracing,
+        price::{self, FilePriceSource},
+        recurring, rollup,
+    },
     router,
+    util::split,
 };
 use tokio::net::TcpListener;
 
@@ -9,11 +17,58 @@ use tokio::net::TcpListener;
 async fn main() {
     dotenvy::dotenv().ok();
 
-    init_tracing();
+    let _tracing = init_tracing();
 
     tracing::debug!("Initializing db");
     let db = Db::new().await.expect("Failed to initialize DB");
 
+    if std::env::args().nth(1).as_deref() == Some("rebuild-rollups") {
+        db.run_migrations().await.expect("Failed to run migrations");
+        rollup::rebuild(&db.pool)
+            .await
+            .expect("Failed to rebuild rollups");
+        tracing::info!("Rollups rebuilt");
+        return;
+    }
+
+    if std::env::args().nth(1).as_deref() == Some("audit-distributions") {
+        db.run_migrations().await.expect("Failed to run migrations");
+        let findings = audit::audit_all(&db.pool)
+            .await
+            .expect("Failed to audit distributions");
+        tracing::info!(findings, "Distributions audited");
+        return;
+    }
+
+    if std::env::args().nth(1).as_deref() == Some("check-recurring") {
+        db.run_migrations().await.expect("Failed to run migrations");
+        let summary = recurring::check(&db.pool, time::OffsetDateTime::now_utc().date())
+            .await
+            .expect("Failed to check recurring payments");
+        tracing::info!(
+            opened = summary.opened,
+            matched = summary.matched,
+            missed = summary.missed,
+            "Recurring payments checked"
+        );
+        return;
+    }
+
+    let price_source = std::env::var("PRICE_FILE").ok().map(FilePriceSource::new);
+
+    if std::env::args().nth(1).as_deref() == Some("sync-prices") {
+        db.run_migrations().await.expect("Failed to run migrations");
+        let source = price_source.expect("PRICE_FILE not set");
+        let stored = price::sync(&db.pool, &source)
+            .await
+            .expect("Failed to sync prices");
+        tracing::info!(stored, "Prices synced");
+        return;
+    }
+
+    // a bad USAGE_FEE stops the server here instead of on the first preview
+    split::usage_fee();
+
     let cache = init_cache(&db.pool.clone()).await;
 
     let config = AppState {
@@ -38,12 +93,57 @@ async fn main() {
     tracing::debug!("Running Migrations");
     db.run_migrations().await.expect("Failed to run migrations");
 
+    if let Some(source) = price_source {
+        let every = std::env::var("PRICE_SYNC_INTERVAL_SECS")
+            .ok()
+            .and_then(|secs| secs.parse().ok())
+            .unwrap_or(3600);
+        price::spawn_sync(
+            db.pool.clone(),
+            source,
+            std::time::Duration::from_secs(every),
+        );
+    }
+
+    let every = std::env::var("RECURRING_CHECK_INTERVAL_SECS")
+        .ok()
+        .and_then(|secs| secs.parse().ok())
+        .unwrap_or(900);
+    recurring::spawn_checks(db.pool.clone(), std::time::Duration::from_secs(every));
+
     let listener = TcpListener::bind("0.0.0.0:8080").await.unwrap();
     tracing::info!("listening on {}", listener.local_addr().unwrap());
 
     let router = router(config);
 
     axum::serve(listener, router)
+        .with_graceful_shutdown(shutdown_signal())
         .await
         .expect("Failed to start server")
 }
+
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("Failed to listen for ctrl-c");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("Failed to listen for SIGTERM")
+            .recv()
+            .await;
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+
+    tracing::info!("Shutting down");
+}
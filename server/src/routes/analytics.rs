@@ -0,0 +1,226 @@
+use axum::{Json, extract::State};
+use time::{OffsetDateTime, Time, UtcOffset};
+use tracing::Instrument;
+
+use crate::{
+    AppState,
+    libs::{db::query_span, error::ApiError, extract::ValidatedQuery, price::FIAT_SCALE},
+    routes::types::{Granularity, VolumeAnalyticsResponse, VolumePoint, VolumeQuery, VolumeSeries},
+};
+
+struct BucketRow {
+    bucket: OffsetDateTime,
+    token_address: String,
+    volume: bigdecimal::BigDecimal,
+    payment_count: i64,
+    value: Option<bigdecimal::BigDecimal>,
+    unpriced_count: Option<i64>,
+}
+
+fn is_utc_midnight(at: OffsetDateTime) -> bool {
+    at.to_offset(UtcOffset::UTC).time() == Time::MIDNIGHT
+}
+
+// Bucketed volume per token. Buckets are truncated in UTC.
+#[tracing::instrument(skip_all, fields(granularity = query.granularity.as_str()))]
+pub async fn get_volume(
+    State(state): State<AppState>,
+    ValidatedQuery(query): ValidatedQuery<VolumeQuery>,
+) -> Result<Json<VolumeAnalyticsResponse>, ApiError> {
+    let to = query.to.unwrap_or_else(OffsetDateTime::now_utc);
+    let granularity = query.granularity.as_str();
+    let group_address = query.group_address.map(|address| address.to_string());
+    let created_by = query.created_by.map(|address| address.to_string());
+    let token_address = query.token_address.map(|address| address.to_string());
+
+    let currency = query.currency.as_ref().map(|currency| currency.as_str());
+
+    // whole-day ranges of paid-in volume can be answered from the daily rollup,
+    // which has no prices
+    let from_rollup = query.member.is_none()
+        && currency.is_none()
+        && query.granularity != Granularity::Hour
+        && is_utc_midnight(query.from)
+        && is_utc_midnight(to);
+
+    let rows = match query.member {
+        // what the member received, from its distributions
+        Some(member) => {
+            sqlx::query_as!(
+                BucketRow,
+                r#"
+            WITH buckets AS (
+                SELECT generate_series(
+                    date_trunc($1, $2::timestamptz AT TIME ZONE 'UTC'),
+                    ($3::timestamptz AT TIME ZONE 'UTC') - interval '1 microsecond',
+                    ('1 ' || $1)::interval
+                ) AS bucket
+            ), agg AS (
+                SELECT date_trunc($1, d.sent_at AT TIME ZONE 'UTC') AS bucket, d.token_address,
+                    SUM(d.token_amount) AS volume, COUNT(DISTINCT d.tx_hash) AS payment_count,
+                    SUM(d.token_amount * px.price / power(10::numeric, px.decimals)) AS value,
+                    COUNT(DISTINCT d.tx_hash) FILTER (WHERE px.price IS NULL) AS unpriced_count
+                FROM distributions_history d
+                JOIN groups g ON g.group_address = d.group_address
+                LEFT JOIN LATERAL (
+                    SELECT tp.price, tp.decimals FROM token_prices tp
+                    WHERE tp.token_address = d.token_address AND tp.currency = $8
+                        AND tp.priced_at <= d.sent_at
+                    ORDER BY tp.priced_at DESC LIMIT 1
+                ) px ON TRUE
+                WHERE d.member_address = $4
+                    AND d.sent_at >= $2 AND d.sent_at < $3
+                    AND ($5::text IS NULL OR d.group_address = $5)
+                    AND ($6::text IS NULL OR g.created_by = $6)
+                    AND ($7::text IS NULL OR d.token_address = $7)
+                GROUP BY 1, 2
+            ), tokens AS (
+                SELECT DISTINCT token_address FROM agg
+            )
+            SELECT b.bucket AT TIME ZONE 'UTC' as "bucket!", t.token_address as "token_address!",
+                COALESCE(a.volume, 0) as "volume!", COALESCE(a.payment_count, 0) as "payment_count!",
+                CASE WHEN $8::text IS NOT NULL THEN COALESCE(a.value, 0) END as "value?",
+                CASE WHEN $8::text IS NOT NULL THEN COALESCE(a.unpriced_count, 0) END as "unpriced_count?"
+            FROM buckets b
+            CROSS JOIN tokens t
+            LEFT JOIN agg a ON a.bucket = b.bucket AND a.token_address = t.token_address
+            ORDER BY t.token_address, b.bucket
+            "#,
+                granularity,
+                query.from,
+                to,
+                member.to_string(),
+                group_address,
+                created_by,
+                token_address,
+                currency
+            )
+            .fetch_all(&state.db)
+            .instrument(query_span("select_member_volume_buckets"))
+            .await?
+        }
+        None if from_rollup => {
+            sqlx::query_as!(
+                BucketRow,
+                r#"
+            WITH buckets AS (
+                SELECT generate_series(
+                    date_trunc($1, $2::timestamptz AT TIME ZONE 'UTC'),
+                    ($3::timestamptz AT TIME ZONE 'UTC') - interval '1 microsecond',
+                    ('1 ' || $1)::interval
+                ) AS bucket
+            ), agg AS (
+                SELECT date_trunc($1, r.day::timestamp) AS bucket, r.token_address,
+                    SUM(r.volume) AS volume, SUM(r.payment_count)::bigint AS payment_count
+                FROM group_token_daily_rollups r
+                JOIN groups g ON g.group_address = r.group_address
+                WHERE r.day >= ($2::timestamptz AT TIME ZONE 'UTC')::date
+                    AND r.day < ($3::timestamptz AT TIME ZONE 'UTC')::date
+                    AND ($4::text IS NULL OR r.group_address = $4)
+                    AND ($5::text IS NULL OR g.created_by = $5)
+                    AND ($6::text IS NULL OR r.token_address = $6)
+                GROUP BY 1, 2
+            ), tokens AS (
+                SELECT DISTINCT token_address FROM agg
+            )
+            SELECT b.bucket AT TIME ZONE 'UTC' as "bucket!", t.token_address as "token_address!",
+                COALESCE(a.volume, 0) as "volume!", COALESCE(a.payment_count, 0) as "payment_count!",
+                NULL::numeric as "value?", NULL::bigint as "unpriced_count?"
+            FROM buckets b
+            CROSS JOIN tokens t
+            LEFT JOIN agg a ON a.bucket = b.bucket AND a.token_address = t.token_address
+            ORDER BY t.token_address, b.bucket
+            "#,
+                granularity,
+                query.from,
+                to,
+                group_address,
+                created_by,
+                token_address
+            )
+            .fetch_all(&state.db)
+            .instrument(query_span("select_rollup_volume_buckets"))
+            .await?
+        }
+        // what was paid into groups
+        None => {
+            sqlx::query_as!(
+                BucketRow,
+                r#"
+            WITH buckets AS (
+                SELECT generate_series(
+                    date_trunc($1, $2::timestamptz AT TIME ZONE 'UTC'),
+                    ($3::timestamptz AT TIME ZONE 'UTC') - interval '1 microsecond',
+                    ('1 ' || $1)::interval
+                ) AS bucket
+            ), agg AS (
+                SELECT date_trunc($1, p.paid_at AT TIME ZONE 'UTC') AS bucket, p.token_address,
+                    SUM(p.amount) AS volume, COUNT(*) AS payment_count,
+                    SUM(p.amount * px.price / power(10::numeric, px.decimals)) AS value,
+                    COUNT(*) FILTER (WHERE px.price IS NULL) AS unpriced_count
+                FROM payments p
+                JOIN groups g ON g.group_address = p.group_address
+                LEFT JOIN LATERAL (
+                    SELECT tp.price, tp.decimals FROM token_prices tp
+                    WHERE tp.token_address = p.token_address AND tp.currency = $7
+                        AND tp.priced_at <= p.paid_at
+                    ORDER BY tp.priced_at DESC LIMIT 1
+                ) px ON TRUE
+                WHERE p.paid_at >= $2 AND p.paid_at < $3
+                    AND ($4::text IS NULL OR p.group_address = $4)
+                    AND ($5::text IS NULL OR g.created_by = $5)
+                    AND ($6::text IS NULL OR p.token_address = $6)
+                GROUP BY 1, 2
+            ), tokens AS (
+                SELECT DISTINCT token_address FROM agg
+            )
+            SELECT b.bucket AT TIME ZONE 'UTC' as "bucket!", t.token_address as "token_address!",
+                COALESCE(a.volume, 0) as "volume!", COALESCE(a.payment_count, 0) as "payment_count!",
+                CASE WHEN $7::text IS NOT NULL THEN COALESCE(a.value, 0) END as "value?",
+                CASE WHEN $7::text IS NOT NULL THEN COALESCE(a.unpriced_count, 0) END as "unpriced_count?"
+            FROM buckets b
+            CROSS JOIN tokens t
+            LEFT JOIN agg a ON a.bucket = b.bucket AND a.token_address = t.token_address
+            ORDER BY t.token_address, b.bucket
+            "#,
+                granularity,
+                query.from,
+                to,
+                group_address,
+                created_by,
+                token_address,
+                currency
+            )
+            .fetch_all(&state.db)
+            .instrument(query_span("select_volume_buckets"))
+            .await?
+        }
+    };
+
+    // rows come ordered by token, then bucket
+    let mut series: Vec<VolumeSeries> = Vec::new();
+    for row in rows {
+        let point = VolumePoint {
+            bucket: row.bucket,
+            volume: row.volume,
+            payment_count: row.payment_count,
+            value: row.value.map(|value| value.round(FIAT_SCALE)),
+            unpriced_count: row.unpriced_count,
+        };
+        match series.last_mut() {
+            Some(last) if last.token_address == row.token_address => last.points.push(point),
+            _ => series.push(VolumeSeries {
+                token_address: row.token_address,
+                points: vec![point],
+            }),
+        }
+    }
+
+    Ok(Json(VolumeAnalyticsResponse {
+        granularity: query.granularity,
+        from: query.from,
+        to,
+        currency: query.currency,
+        series,
+    }))
+}
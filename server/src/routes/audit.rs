@@ -0,0 +1,150 @@
+use axum::{Json, extract::State};
+use sqlx::{PgPool, QueryBuilder};
+use tracing::Instrument;
+
+use crate::{
+    AppState,
+    libs::{
+        db::query_span,
+        error::ApiError,
+        extract::{ValidatedPath, ValidatedQuery},
+        pagination::{KeyColumn, Keyed, Keyset, Page},
+    },
+    routes::{
+        group::ensure_group_exists,
+        types::{
+            AuditFindingResponse, AuditSummaryQuery, AuditSummaryResponse, DEFAULT_PAGE_LIMIT,
+            FindingCount, FindingListQuery,
+        },
+    },
+    util::address::Address,
+};
+
+const FINDING_KEY: KeyColumn = KeyColumn {
+    column: "f.id",
+    sql_type: "uuid",
+};
+
+#[tracing::instrument(skip_all)]
+pub async fn get_findings(
+    State(state): State<AppState>,
+    ValidatedQuery(query): ValidatedQuery<FindingListQuery>,
+) -> Result<Json<Page<AuditFindingResponse>>, ApiError> {
+    list_findings(&state.db, &query).await.map(Json)
+}
+
+#[tracing::instrument(skip_all, fields(group_address = %group_address))]
+pub async fn get_group_findings(
+    State(state): State<AppState>,
+    ValidatedPath(group_address): ValidatedPath<Address>,
+    ValidatedQuery(mut query): ValidatedQuery<FindingListQuery>,
+) -> Result<Json<Page<AuditFindingResponse>>, ApiError> {
+    ensure_group_exists(&state.db, &group_address.to_string()).await?;
+    query.group_address = Some(group_address);
+    list_findings(&state.db, &query).await.map(Json)
+}
+
+async fn list_findings(
+    db: &PgPool,
+    query: &FindingListQuery,
+) -> Result<Page<AuditFindingResponse>, ApiError> {
+    let keyset = Keyset::new(
+        FINDING_KEY,
+        query.sort,
+        query.order,
+        query.cursor.as_deref(),
+        query.limit.unwrap_or(DEFAULT_PAGE_LIMIT),
+    )?;
+
+    let mut qb = QueryBuilder::new(
+        "SELECT f.tx_hash, f.group_address, f.token_address, f.member_address, f.kind, \
+         f.severity, f.expected, f.actual, p.paid_at::text AS paid_at, \
+         f.detected_at::text AS detected_at",
+    );
+    keyset.push_select(&mut qb);
+    qb.push(
+        " FROM distribution_audit_findings f JOIN payments p ON p.tx_hash = f.tx_hash WHERE TRUE",
+    );
+    if let Some(group_address) = query.group_address {
+        qb.push(" AND f.group_address = ")
+            .push_bind(group_address.to_string());
+    }
+    if let Some(member) = query.member {
+        qb.push(" AND f.member_address = ")
+            .push_bind(member.to_string());
+    }
+    if let Some(tx_hash) = query.tx_hash {
+        qb.push(" AND f.tx_hash = ").push_bind(tx_hash.to_string());
+    }
+    if let Some(kind) = query.kind {
+        qb.push(" AND f.kind = ").push_bind(kind.as_str());
+    }
+    if let Some(severity) = query.severity {
+        qb.push(" AND f.severity = ").push_bind(severity.as_str());
+    }
+    if let Some(from) = query.from {
+        qb.push(" AND p.paid_at >= ").push_bind(from);
+    }
+    if let Some(to) = query.to {
+        qb.push(" AND p.paid_at < ").push_bind(to);
+    }
+    keyset.push_after(&mut qb);
+    keyset.push_order_limit(&mut qb);
+
+    let rows = qb
+        .build_query_as::<Keyed<AuditFindingResponse>>()
+        .fetch_all(db)
+        .instrument(query_span("select_audit_findings"))
+        .await?;
+
+    Ok(keyset.page(rows))
+}
+
+/// Audit coverage and findings per kind, overall or for one group.
+#[tracing::instrument(skip_all)]
+pub async fn get_audit_summary(
+    State(state): State<AppState>,
+    ValidatedQuery(query): ValidatedQuery<AuditSummaryQuery>,
+) -> Result<Json<AuditSummaryResponse>, ApiError> {
+    let group_address = query.group_address.map(|address| address.to_string());
+
+    let coverage = sqlx::query!(
+        r#"
+        SELECT COUNT(*) as "payment_count!", COUNT(a.tx_hash) as "audited_count!",
+            COUNT(*) FILTER (WHERE EXISTS (
+                SELECT 1 FROM distribution_audit_findings f
+                WHERE f.tx_hash = p.tx_hash AND f.severity = 'error'
+            )) as "flagged_count!"
+        FROM payments p
+        LEFT JOIN payment_audits a ON a.tx_hash = p.tx_hash
+        WHERE ($1::text IS NULL OR p.group_address = $1)
+        "#,
+        group_address
+    )
+    .fetch_one(&state.db)
+    .instrument(query_span("select_audit_coverage"))
+    .await?;
+
+    let findings = sqlx::query_as!(
+        FindingCount,
+        r#"
+        SELECT kind, severity, COUNT(*) as "finding_count!",
+            COUNT(DISTINCT tx_hash) as "payment_count!"
+        FROM distribution_audit_findings
+        WHERE ($1::text IS NULL OR group_address = $1)
+        GROUP BY kind, severity
+        ORDER BY severity, kind
+        "#,
+        group_address
+    )
+    .fetch_all(&state.db)
+    .instrument(query_span("select_audit_finding_counts"))
+    .await?;
+
+    Ok(Json(AuditSummaryResponse {
+        payment_count: coverage.payment_count,
+        audited_count: coverage.audited_count,
+        flagged_count: coverage.flagged_count,
+        findings,
+    }))
+}
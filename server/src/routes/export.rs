@@ -0,0 +1,429 @@
+use axum::{extract::State, response::Response};
+use bigdecimal::BigDecimal;
+use serde::Serialize;
+use sqlx::{FromRow, QueryBuilder};
+use time::Date;
+
+use crate::{
+    AppState,
+    libs::{
+        error::ApiError,
+        export::{ExportFormat, stream_encoded, stream_rows},
+        extract::ValidatedQuery,
+    },
+    routes::types::{ExportQuery, LedgerExportQuery},
+    util::{
+        ledger::{Entry, Posting},
+        token,
+    },
+};
+
+const DEFAULT_ASSETS_ACCOUNT: &str = "Assets:Starknet:PayMesh";
+const DEFAULT_INCOME_ACCOUNT: &str = "Income:PayMesh";
+const DEFAULT_EXPENSES_ACCOUNT: &str = "Expenses:PayMesh";
+
+#[derive(FromRow)]
+struct PaymentRow {
+    tx_hash: String,
+    group_address: String,
+    group_name: String,
+    token_address: String,
+    amount: BigDecimal,
+    paid_at: String,
+}
+
+/// Amounts are in base units; `amount_formatted` is in whole tokens and
+/// empty for unknown tokens.
+#[derive(Serialize, Default)]
+struct PaymentExport {
+    tx_hash: String,
+    group_address: String,
+    group_name: String,
+    token_address: String,
+    token_symbol: Option<&'static str>,
+    amount: String,
+    amount_formatted: Option<String>,
+    paid_at: String,
+}
+
+impl From<PaymentRow> for PaymentExport {
+    fn from(row: PaymentRow) -> Self {
+        let token = token::lookup(&row.token_address);
+        PaymentExport {
+            token_symbol: token.map(|token| token.symbol),
+            amount_formatted: token.map(|token| token.format_amount(&row.amount)),
+            amount: row.amount.to_string(),
+            tx_hash: row.tx_hash,
+            group_address: row.group_address,
+            group_name: row.group_name,
+            token_address: row.token_address,
+            paid_at: row.paid_at,
+        }
+    }
+}
+
+#[derive(FromRow)]
+struct DistributionRow {
+    tx_hash: String,
+    group_address: String,
+    group_name: String,
+    member_address: String,
+    token_address: String,
+    token_amount: BigDecimal,
+    sent_at: String,
+}
+
+#[derive(Serialize, Default)]
+struct DistributionExport {
+    tx_hash: String,
+    group_address: String,
+    group_name: String,
+    member_address: String,
+    token_address: String,
+    token_symbol: Option<&'static str>,
+    amount: String,
+    amount_formatted: Option<String>,
+    sent_at: String,
+}
+
+impl From<DistributionRow> for DistributionExport {
+    fn from(row: DistributionRow) -> Self {
+        let token = token::lookup(&row.token_address);
+        DistributionExport {
+            token_symbol: token.map(|token| token.symbol),
+            amount_formatted: token.map(|token| token.format_amount(&row.token_amount)),
+            amount: row.token_amount.to_string(),
+            tx_hash: row.tx_hash,
+            group_address: row.group_address,
+            group_name: row.group_name,
+            member_address: row.member_address,
+            token_address: row.token_address,
+            sent_at: row.sent_at,
+        }
+    }
+}
+
+#[derive(FromRow, Serialize, Default)]
+struct MembershipExport {
+    group_address: String,
+    group_name: String,
+    member_address: String,
+    member_percentage: BigDecimal,
+    is_active: bool,
+    added_at: String,
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn export_payments(
+    State(state): State<AppState>,
+    format: ExportFormat,
+    ValidatedQuery(query): ValidatedQuery<ExportQuery>,
+) -> Result<Response, ApiError> {
+    let mut qb = QueryBuilder::new(
+        "SELECT p.tx_hash, p.group_address, g.group_name, p.token_address, p.amount, \
+         p.paid_at::text AS paid_at \
+         FROM payments p JOIN groups g ON g.group_address = p.group_address WHERE TRUE",
+    );
+    if let Some(group_address) = query.group_address {
+        qb.push(" AND p.group_address = ")
+            .push_bind(group_address.to_string());
+    }
+    if let Some(member) = query.member {
+        qb.push(
+            " AND EXISTS (SELECT 1 FROM distributions_history d \
+             WHERE d.tx_hash = p.tx_hash AND d.member_address = ",
+        )
+        .push_bind(member.to_string())
+        .push(")");
+    }
+    if let Some(token_address) = query.token_address {
+        qb.push(" AND p.token_address = ")
+            .push_bind(token_address.to_string());
+    }
+    if let Some(from) = query.from {
+        qb.push(" AND p.paid_at >= ").push_bind(from);
+    }
+    if let Some(to) = query.to {
+        qb.push(" AND p.paid_at < ").push_bind(to);
+    }
+    qb.push(" ORDER BY p.paid_at, p.tx_hash");
+
+    Ok(stream_rows::<PaymentRow, _>(
+        state.db,
+        qb,
+        format,
+        "payments",
+        "export_payments",
+        PaymentExport::from,
+    ))
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn export_distributions(
+    State(state): State<AppState>,
+    format: ExportFormat,
+    ValidatedQuery(query): ValidatedQuery<ExportQuery>,
+) -> Result<Response, ApiError> {
+    let mut qb = QueryBuilder::new(
+        "SELECT d.tx_hash, d.group_address, g.group_name, d.member_address, d.token_address, \
+         d.token_amount, d.sent_at::text AS sent_at \
+         FROM distributions_history d JOIN groups g ON g.group_address = d.group_address \
+         WHERE TRUE",
+    );
+    if let Some(group_address) = query.group_address {
+        qb.push(" AND d.group_address = ")
+            .push_bind(group_address.to_string());
+    }
+    if let Some(member) = query.member {
+        qb.push(" AND d.member_address = ")
+            .push_bind(member.to_string());
+    }
+    if let Some(token_address) = query.token_address {
+        qb.push(" AND d.token_address = ")
+            .push_bind(token_address.to_string());
+    }
+    if let Some(from) = query.from {
+        qb.push(" AND d.sent_at >= ").push_bind(from);
+    }
+    if let Some(to) = query.to {
+        qb.push(" AND d.sent_at < ").push_bind(to);
+    }
+    qb.push(" ORDER BY d.sent_at, d.id");
+
+    Ok(stream_rows::<DistributionRow, _>(
+        state.db,
+        qb,
+        format,
+        "distributions",
+        "export_distributions",
+        DistributionExport::from,
+    ))
+}
+
+/// Active and past memberships; `token_address` does not apply.
+#[tracing::instrument(skip_all)]
+pub async fn export_memberships(
+    State(state): State<AppState>,
+    format: ExportFormat,
+    ValidatedQuery(query): ValidatedQuery<ExportQuery>,
+) -> Result<Response, ApiError> {
+    let mut qb = QueryBuilder::new(
+        "SELECT gm.group_address, g.group_name, gm.member_address, gm.member_percentage, \
+         gm.is_active, gm.added_at::text AS added_at \
+         FROM group_members gm JOIN groups g ON g.group_address = gm.group_address WHERE TRUE",
+    );
+    if let Some(group_address) = query.group_address {
+        qb.push(" AND gm.group_address = ")
+            .push_bind(group_address.to_string());
+    }
+    if let Some(member) = query.member {
+        qb.push(" AND gm.member_address = ")
+            .push_bind(member.to_string());
+    }
+    if let Some(from) = query.from {
+        qb.push(" AND gm.added_at >= ").push_bind(from);
+    }
+    if let Some(to) = query.to {
+        qb.push(" AND gm.added_at < ").push_bind(to);
+    }
+    qb.push(" ORDER BY gm.group_address, gm.added_at, gm.member_address");
+
+    Ok(stream_rows::<MembershipExport, _>(
+        state.db,
+        qb,
+        format,
+        "memberships",
+        "export_memberships",
+        std::convert::identity,
+    ))
+}
+
+#[derive(FromRow)]
+struct LedgerDistributionRow {
+    tx_hash: String,
+    group_address: String,
+    group_name: String,
+    token_address: String,
+    token_amount: BigDecimal,
+    day: Date,
+}
+
+/// A payment with its distributions, which come as parallel arrays.
+#[derive(FromRow)]
+struct LedgerPaymentRow {
+    tx_hash: String,
+    group_address: String,
+    group_name: String,
+    token_address: String,
+    amount: BigDecimal,
+    day: Date,
+    members: Vec<String>,
+    member_amounts: Vec<BigDecimal>,
+}
+
+/// Beancount or ledger-cli transactions. For a `member`, each distribution
+/// moves income into the assets account. For a group, each payment is
+/// income split into one expense posting per member; anything left
+/// undistributed stays in the assets account.
+#[tracing::instrument(skip_all)]
+pub async fn export_ledger(
+    State(state): State<AppState>,
+    ValidatedQuery(query): ValidatedQuery<LedgerExportQuery>,
+) -> Result<Response, ApiError> {
+    let format = query.format;
+    let assets = query
+        .assets_account
+        .unwrap_or_else(|| DEFAULT_ASSETS_ACCOUNT.to_owned());
+    let income = query
+        .income_account
+        .unwrap_or_else(|| DEFAULT_INCOME_ACCOUNT.to_owned());
+    let expenses = query
+        .expenses_account
+        .unwrap_or_else(|| DEFAULT_EXPENSES_ACCOUNT.to_owned());
+    let mut first = true;
+
+    if let Some(member) = query.member {
+        let filename = format!("distributions-{member}.{}", format.extension());
+        let mut qb = QueryBuilder::new(
+            "SELECT d.tx_hash, d.group_address, g.group_name, d.token_address, d.token_amount, \
+             (d.sent_at AT TIME ZONE 'UTC')::date AS day \
+             FROM distributions_history d JOIN groups g ON g.group_address = d.group_address \
+             WHERE d.member_address = ",
+        );
+        qb.push_bind(member.to_string());
+        if let Some(group_address) = query.group_address {
+            qb.push(" AND d.group_address = ")
+                .push_bind(group_address.to_string());
+        }
+        if let Some(token_address) = query.token_address {
+            qb.push(" AND d.token_address = ")
+                .push_bind(token_address.to_string());
+        }
+        if let Some(from) = query.from {
+            qb.push(" AND d.sent_at >= ").push_bind(from);
+        }
+        if let Some(to) = query.to {
+            qb.push(" AND d.sent_at < ").push_bind(to);
+        }
+        qb.push(" ORDER BY d.sent_at, d.id");
+
+        return Ok(stream_encoded(
+            state.db,
+            qb,
+            format.content_type(),
+            &filename,
+            "export_member_ledger",
+            move |row: LedgerDistributionRow| {
+                let mut out = String::new();
+                if first {
+                    out = format.prelude(row.day, &[&assets, &income]);
+                    first = false;
+                }
+                out.push_str(&format.render(&Entry {
+                    date: row.day,
+                    payee: row.group_name,
+                    narration: "PayMesh distribution".to_owned(),
+                    meta: vec![
+                        ("group_address", row.group_address),
+                        ("tx_hash", row.tx_hash),
+                        ("token_address", row.token_address.clone()),
+                    ],
+                    token_address: row.token_address,
+                    postings: vec![
+                        Posting {
+                            account: assets.clone(),
+                            amount: row.token_amount.clone(),
+                            meta: Vec::new(),
+                        },
+                        Posting {
+                            account: income.clone(),
+                            amount: -row.token_amount,
+                            meta: Vec::new(),
+                        },
+                    ],
+                }));
+                Ok(out.into_bytes())
+            },
+        ));
+    }
+
+    let Some(group_address) = query.group_address else {
+        return Err(ApiError::bad_request("Pass `member` or `group_address`"));
+    };
+    let filename = format!("payments-{group_address}.{}", format.extension());
+    let mut qb = QueryBuilder::new(
+        "SELECT p.tx_hash, p.group_address, g.group_name, p.token_address, p.amount, \
+         (p.paid_at AT TIME ZONE 'UTC')::date AS day, \
+         COALESCE(d.members, '{}') AS members, COALESCE(d.member_amounts, '{}') AS member_amounts \
+         FROM payments p JOIN groups g ON g.group_address = p.group_address \
+         LEFT JOIN LATERAL ( \
+             SELECT array_agg(member_address ORDER BY member_address) AS members, \
+                 array_agg(token_amount ORDER BY member_address) AS member_amounts \
+             FROM distributions_history WHERE tx_hash = p.tx_hash \
+         ) d ON TRUE \
+         WHERE p.group_address = ",
+    );
+    qb.push_bind(group_address.to_string());
+    if let Some(token_address) = query.token_address {
+        qb.push(" AND p.token_address = ")
+            .push_bind(token_address.to_string());
+    }
+    if let Some(from) = query.from {
+        qb.push(" AND p.paid_at >= ").push_bind(from);
+    }
+    if let Some(to) = query.to {
+        qb.push(" AND p.paid_at < ").push_bind(to);
+    }
+    qb.push(" ORDER BY p.paid_at, p.tx_hash");
+
+    Ok(stream_encoded(
+        state.db,
+        qb,
+        format.content_type(),
+        &filename,
+        "export_group_ledger",
+        move |row: LedgerPaymentRow| {
+            let mut out = String::new();
+            if first {
+                out = format.prelude(row.day, &[&assets, &income, &expenses]);
+                first = false;
+            }
+
+            let mut postings = vec![Posting {
+                account: income.clone(),
+                amount: -row.amount.clone(),
+                meta: Vec::new(),
+            }];
+            let mut remainder = row.amount;
+            for (member, amount) in row.members.into_iter().zip(row.member_amounts) {
+                remainder -= &amount;
+                postings.push(Posting {
+                    account: expenses.clone(),
+                    amount,
+                    meta: vec![("member_address", member)],
+                });
+            }
+            if remainder != BigDecimal::from(0) {
+                postings.push(Posting {
+                    account: assets.clone(),
+                    amount: remainder,
+                    meta: Vec::new(),
+                });
+            }
+
+            out.push_str(&format.render(&Entry {
+                date: row.day,
+                payee: row.group_name,
+                narration: "PayMesh payment".to_owned(),
+                meta: vec![
+                    ("group_address", row.group_address),
+                    ("tx_hash", row.tx_hash),
+                    ("token_address", row.token_address.clone()),
+                ],
+                token_address: row.token_address,
+                postings,
+            }));
+            Ok(out.into_bytes())
+        },
+    ))
+}
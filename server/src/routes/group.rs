@@ -1,80 +1,117 @@
 use axum::{Json, extract::State, http::StatusCode, response::IntoResponse};
-use sqlx::types::BigDecimal;
+use sqlx::{PgConnection, PgPool, Postgres, QueryBuilder, types::BigDecimal};
 use std::collections::HashMap;
+use time::OffsetDateTime;
+use tracing::Instrument;
 
 use crate::{
     AppState,
-    libs::error::{ApiError, map_sqlx_error},
+    libs::{
+        db::query_span,
+        error::ApiError,
+        extract::{ValidatedJson, ValidatedPath, ValidatedQuery},
+        forecast,
+        pagination::{KeyColumn, Keyed, Keyset, Page},
+        share_set, usage,
+    },
     routes::types::{
-        GetGroupDetailsRequest, GetGroupDetailsResponse, GroupFullDetailResponse,
-        GroupMemberResponse, GroupMemberWithAddress, GroupRequest, GroupTokenTransfer,
-        GroupsMetricsResponse, GroupsResponse, PaymentsTotalsResponse,
+        CreatorGroupResponse, DEFAULT_PAGE_LIMIT, GetGroupDetailsRequest, GetGroupDetailsResponse,
+        GroupFullDetailResponse, GroupListQuery, GroupMemberResponse, GroupMemberWithAddress,
+        GroupRequest, GroupSort, GroupTokenTransfer, GroupsMetricsResponse, GroupsResponse,
+        MAX_PAGE_LIMIT, PaymentsTotalsResponse, TokenVolume,
+    },
+    util::{
+        address::Address,
+        token::{self, TOKENS},
     },
-    util::connector::is_valid_address,
 };
 
+const GROUP_KEY: KeyColumn = KeyColumn {
+    column: "g.group_address",
+    sql_type: "text",
+};
+
+#[tracing::instrument(skip_all, fields(group_address = %payload.group_address))]
 pub async fn create_group(
     State(state): State<AppState>,
-    Json(payload): Json<GroupRequest>,
+    ValidatedJson(payload): ValidatedJson<GroupRequest>,
 ) -> Result<impl IntoResponse, ApiError> {
     let usage_remaining: BigDecimal = payload.usage_remaining.into();
-    let group_address = &payload.group_address;
-    let created_by = &payload.created_by;
+    let group_address = payload.group_address.to_string();
+    let created_by = payload.created_by.to_string();
 
     tracing::info!("Creating group: {}", group_address);
 
-    let mut tx = state
-        .db
-        .begin()
-        .await
-        .map_err(|_| ApiError::Internal("Failed to begin transaction"))?;
+    let mut tx = state.db.begin().await?;
 
     sqlx::query!(
-        r#"INSERT INTO groups (group_address, group_name, created_by, usage_remaining) VALUES ($1, $2, $3, $4)"#,
+        r#"INSERT INTO groups (group_address, group_name, created_by, usage_remaining, group_id) VALUES ($1, $2, $3, $4, $5)"#,
         group_address,
         payload.group_name,
         created_by,
-        usage_remaining
+        usage_remaining,
+        payload.group_id.map(i64::from)
     )
     .execute(&mut *tx)
-    .await
-    .map_err(|e| map_sqlx_error(&e))?;
+    .instrument(query_span("insert_group"))
+    .await?;
+
+    usage::purchase(&mut tx, &group_address, &usage_remaining).await?;
 
     {
         let mut cache = state.cache.write().await;
         cache.insert(group_address.to_string());
     }
 
-    for group_member in payload.members {
-        let member_percentage: BigDecimal = group_member.percentage.into();
-        sqlx::query!(
-            r#"INSERT INTO group_members (group_address, member_address, member_percentage) VALUES ($1, $2, $3)"#,
-            group_address,
-            group_member.addr,
-            member_percentage
-        )
-        .execute(&mut *tx)
-        .await
-            .map_err(|e| map_sqlx_error(&e))?;
-    }
+    let members: Vec<(String, BigDecimal)> = payload
+        .members
+        .iter()
+        .map(|member| (member.addr.to_string(), member.percentage.into()))
+        .collect();
+    share_set::open(&mut tx, &group_address, &members, OffsetDateTime::now_utc()).await?;
+    forecast::check_thresholds(&mut tx, &group_address).await?;
 
-    tx.commit()
-        .await
-        .map_err(|_| ApiError::Internal("Failed to commit transaction"))?;
+    tx.commit().await?;
 
     tracing::info!("Group created: {}", group_address);
 
     Ok((StatusCode::OK, Json("Group created".to_owned())))
 }
 
+#[tracing::instrument(skip_all, fields(group_address = %params.group_address))]
 pub async fn get_group(
     State(state): State<AppState>,
-    Json(params): Json<GetGroupDetailsRequest>,
+    ValidatedJson(params): ValidatedJson<GetGroupDetailsRequest>,
 ) -> Result<Json<GetGroupDetailsResponse>, ApiError> {
-    let group_address = params.group_address;
+    group_details(&state.db, &params.group_address.to_string())
+        .await
+        .map(Json)
+}
 
-    is_valid_address(&group_address).map_err(|_| ApiError::BadRequest("INVALID GROUP ADDRESS"))?;
+#[tracing::instrument(skip_all, fields(group_address = %group_address))]
+pub async fn get_group_by_address(
+    State(state): State<AppState>,
+    ValidatedPath(group_address): ValidatedPath<Address>,
+) -> Result<Json<GetGroupDetailsResponse>, ApiError> {
+    group_details(&state.db, &group_address.to_string())
+        .await
+        .map(Json)
+}
+
+#[tracing::instrument(skip_all, fields(group_address = %group_address))]
+pub async fn get_group_members(
+    State(state): State<AppState>,
+    ValidatedPath(group_address): ValidatedPath<Address>,
+) -> Result<Json<Vec<GroupMemberResponse>>, ApiError> {
+    let group_address = group_address.to_string();
+    ensure_group_exists(&state.db, &group_address).await?;
+    active_members(&state.db, &group_address).await.map(Json)
+}
 
+async fn group_details(
+    db: &PgPool,
+    group_address: &str,
+) -> Result<GetGroupDetailsResponse, ApiError> {
     let group = sqlx::query_as!(
         GroupsResponse,
         r#"
@@ -85,11 +122,28 @@ pub async fn get_group(
         "#,
         group_address
     )
-    .fetch_optional(&state.db)
-    .await
-    .map_err(|_| ApiError::Internal("Database Error Occurred"))?
-    .ok_or(ApiError::NotFound("Group Not Found"))?;
+    .fetch_optional(db)
+    .instrument(query_span("select_group"))
+    .await?
+    .ok_or_else(ApiError::group_not_found)?;
+
+    let members = active_members(db, group_address).await?;
+
+    Ok(GetGroupDetailsResponse {
+        group_address: group.group_address,
+        group_name: group.group_name,
+        created_by: group.created_by,
+        usage_remaining: group.usage_remaining,
+        created_at: group.created_at,
+        updated_at: group.updated_at,
+        members,
+    })
+}
 
+async fn active_members(
+    db: &PgPool,
+    group_address: &str,
+) -> Result<Vec<GroupMemberResponse>, ApiError> {
     let members = sqlx::query_as!(
         GroupMemberResponse,
         r#"
@@ -100,26 +154,98 @@ pub async fn get_group(
         "#,
         group_address
     )
-    .fetch_all(&state.db)
-    .await
-    .map_err(|_| ApiError::Internal("Database Error Occurred"))?;
+    .fetch_all(db)
+    .instrument(query_span("select_group_members"))
+    .await?;
 
-    Ok(Json(GetGroupDetailsResponse {
-        group_address: group.group_address,
-        group_name: group.group_name,
-        created_by: group.created_by,
-        usage_remaining: group.usage_remaining,
-        created_at: group.created_at,
-        updated_at: group.updated_at,
-        members,
-    }))
+    Ok(members)
 }
 
-// Get all groups metrics with token shares
+/// 404s for unknown groups, so empty sub-resources aren't mistaken for
+/// existing groups without data.
+pub async fn ensure_group_exists(db: &PgPool, group_address: &str) -> Result<(), ApiError> {
+    let exists = sqlx::query_scalar!(
+        r#"SELECT EXISTS(SELECT 1 FROM groups WHERE group_address = $1) as "exists!""#,
+        group_address
+    )
+    .fetch_one(db)
+    .instrument(query_span("select_group_exists"))
+    .await?;
+
+    exists.then_some(()).ok_or_else(ApiError::group_not_found)
+}
+
+/// Takes the group's row lock for the rest of the transaction, so updates of
+/// one group are serialized.
+pub async fn lock_group(conn: &mut PgConnection, group_address: &str) -> Result<(), ApiError> {
+    sqlx::query_scalar!(
+        "SELECT group_address FROM groups WHERE group_address = $1 FOR UPDATE",
+        group_address
+    )
+    .fetch_optional(&mut *conn)
+    .instrument(query_span("lock_group"))
+    .await?
+    .map(|_| ())
+    .ok_or_else(ApiError::group_not_found)
+}
+
+fn group_keyset(query: &GroupListQuery) -> Result<Keyset<GroupSort>, ApiError> {
+    Keyset::new(
+        GROUP_KEY,
+        query.sort,
+        query.order,
+        query.cursor.as_deref(),
+        query.limit.unwrap_or(DEFAULT_PAGE_LIMIT),
+    )
+}
+
+/// Appends the `GroupListQuery` filters to a query over `groups g`.
+fn push_group_filters(qb: &mut QueryBuilder<'_, Postgres>, query: &GroupListQuery) {
+    if let Some(created_by) = query.created_by {
+        qb.push(" AND g.created_by = ")
+            .push_bind(created_by.to_string());
+    }
+    if let Some(member) = query.member {
+        qb.push(
+            " AND EXISTS (SELECT 1 FROM group_members gm \
+             WHERE gm.group_address = g.group_address AND gm.is_active AND gm.member_address = ",
+        )
+        .push_bind(member.to_string())
+        .push(")");
+    }
+    if let Some(token_address) = query.token_address {
+        qb.push(
+            " AND EXISTS (SELECT 1 FROM payments p \
+             WHERE p.group_address = g.group_address AND p.token_address = ",
+        )
+        .push_bind(token_address.to_string())
+        .push(")");
+    }
+    if let Some(from) = query.from {
+        qb.push(" AND g.created_at >= ").push_bind(from);
+    }
+    if let Some(to) = query.to {
+        qb.push(" AND g.created_at < ").push_bind(to);
+    }
+    if let Some(q) = &query.q {
+        let pattern = q
+            .replace('\\', "\\\\")
+            .replace('%', "\\%")
+            .replace('_', "\\_");
+        qb.push(" AND g.group_name ILIKE ")
+            .push_bind(format!("%{pattern}%"));
+    }
+}
+
+// Get groups metrics with token shares, one page at a time
+#[tracing::instrument(skip_all)]
 pub async fn get_groups_metrics(
     State(state): State<AppState>,
-) -> Result<Json<Vec<GroupsMetricsResponse>>, ApiError> {
-    let metrics = sqlx::query!(
+    ValidatedQuery(query): ValidatedQuery<GroupListQuery>,
+) -> Result<Json<Page<GroupsMetricsResponse>>, ApiError> {
+    let keyset = group_keyset(&query)?;
+
+    let mut qb = QueryBuilder::new(
         r#"
         SELECT 
             g.group_address,
@@ -127,62 +253,82 @@ pub async fn get_groups_metrics(
             MAX(CASE WHEN gth.token_symbol = 'USDT' THEN gth.amount::text END) as share_usdt,
             MAX(CASE WHEN gth.token_symbol = 'ETH' THEN gth.amount::text END) as share_eth,
             MAX(CASE WHEN gth.token_symbol = 'STRK' THEN gth.amount::text END) as share_strk
-        FROM groups g
-        LEFT JOIN group_token_history gth ON g.group_address = gth.group_address
-        GROUP BY g.group_address
-        ORDER BY g.created_at DESC
-        "#
-    )
-    .fetch_all(&state.db)
-    .await
-    .map_err(|e| {
-        eprintln!("Database error fetching group metrics: {}", e);
-        ApiError::Internal("Database Error Occurred")
-    })?;
-
-    let response: Vec<GroupsMetricsResponse> = metrics
-        .into_iter()
-        .map(|row| GroupsMetricsResponse {
-            group_address: row.group_address,
-            share_usdc: row.share_usdc,
-            share_usdt: row.share_usdt,
-            share_eth: row.share_eth,
-            share_strk: row.share_strk,
-        })
-        .collect();
+        "#,
+    );
+    keyset.push_select(&mut qb);
+    qb.push(
+        " FROM groups g \
+         LEFT JOIN group_token_history gth ON g.group_address = gth.group_address \
+         WHERE TRUE",
+    );
+    push_group_filters(&mut qb, &query);
+    keyset.push_after(&mut qb);
+    qb.push(" GROUP BY g.group_address");
+    keyset.push_order_limit(&mut qb);
+
+    let rows = qb
+        .build_query_as::<Keyed<GroupsMetricsResponse>>()
+        .fetch_all(&state.db)
+        .instrument(query_span("select_groups_metrics"))
+        .await?;
+
+    Ok(Json(keyset.page(rows)))
+}
 
-    Ok(Json(response))
+/// Unversioned `/history`: every group as a bare array.
+pub async fn get_groups_metrics_unpaged(
+    State(state): State<AppState>,
+    ValidatedQuery(query): ValidatedQuery<GroupListQuery>,
+) -> Result<Json<Vec<GroupsMetricsResponse>>, ApiError> {
+    all_pages(query, |query| {
+        get_groups_metrics(State(state.clone()), ValidatedQuery(query))
+    })
+    .await
+    .map(Json)
 }
 
-// Get all groups with full details including token transfer amount
+// Get groups with full details including token transfer amount
 // for admin usage
+#[tracing::instrument(skip_all)]
 pub async fn get_groups(
     State(state): State<AppState>,
-) -> Result<Json<Vec<GroupFullDetailResponse>>, ApiError> {
-    // Get all groups
-    let groups = sqlx::query_as!(
-        GroupsResponse,
+    ValidatedQuery(query): ValidatedQuery<GroupListQuery>,
+) -> Result<Json<Page<GroupFullDetailResponse>>, ApiError> {
+    let keyset = group_keyset(&query)?;
+
+    // Get one page of groups
+    let mut qb = QueryBuilder::new(
         r#"
         SELECT 
-            group_address, 
-            group_name, 
-            created_by, 
-            usage_remaining, 
-            created_at::text as "created_at!", 
-            updated_at::text as "updated_at"
-        FROM groups 
-        ORDER BY created_at DESC
-        "#
-    )
-    .fetch_all(&state.db)
-    .await
-    .map_err(|e| {
-        eprintln!("Database error fetching groups: {}", e);
-        ApiError::Internal("Database Error Occurred")
-    })?;
+            g.group_address, 
+            g.group_name, 
+            g.created_by, 
+            g.usage_remaining, 
+            g.created_at::text as created_at, 
+            g.updated_at::text as updated_at
+        "#,
+    );
+    keyset.push_select(&mut qb);
+    qb.push(" FROM groups g WHERE TRUE");
+    push_group_filters(&mut qb, &query);
+    keyset.push_after(&mut qb);
+    keyset.push_order_limit(&mut qb);
+
+    let rows = qb
+        .build_query_as::<Keyed<GroupsResponse>>()
+        .fetch_all(&state.db)
+        .instrument(query_span("select_groups"))
+        .await?;
+    let groups = keyset.page(rows);
+
+    let group_addresses: Vec<String> = groups
+        .items
+        .iter()
+        .map(|group| group.group_address.clone())
+        .collect();
 
-    // Get all group members for all groups
-    let all_members = sqlx::query_as!(
+    // Get group members for the groups on this page
+    let page_members = sqlx::query_as!(
         GroupMemberWithAddress,
         r#"
         SELECT 
@@ -192,19 +338,17 @@ pub async fn get_groups(
             is_active, 
             added_at::text as "added_at!"
         FROM group_members 
-        WHERE is_active = true
+        WHERE is_active = true AND group_address = ANY($1)
         ORDER BY group_address, member_percentage DESC
-        "#
+        "#,
+        &group_addresses
     )
     .fetch_all(&state.db)
-    .await
-    .map_err(|e| {
-        eprintln!("Database error fetching members: {}", e);
-        ApiError::Internal("Database Error Occurred")
-    })?;
+    .instrument(query_span("select_page_group_members"))
+    .await?;
 
-    // Get all token transfer for all groups
-    let all_token_tranfer = sqlx::query_as!(
+    // Get token transfer for the groups on this page
+    let page_token_transfer = sqlx::query_as!(
         GroupTokenTransfer,
         r#"
         SELECT 
@@ -212,23 +356,22 @@ pub async fn get_groups(
             token_symbol,
             amount
         FROM group_token_history
+        WHERE group_address = ANY($1)
         ORDER BY group_address
-        "#
+        "#,
+        &group_addresses
     )
     .fetch_all(&state.db)
-    .await
-    .map_err(|e| {
-        eprintln!("Database error fetching token balances: {}", e);
-        ApiError::Internal("Database Error Occurred")
-    })?;
+    .instrument(query_span("select_page_group_token_history"))
+    .await?;
 
     // map group members by group_address
     let mut members_by_group: HashMap<String, Vec<GroupMemberResponse>> = HashMap::new();
-    for member in all_members {
+    for member in page_members {
         let group_address = member.group_address.clone();
         members_by_group
             .entry(group_address)
-            .or_insert_with(Vec::new)
+            .or_default()
             .push(GroupMemberResponse {
                 member_address: member.member_address,
                 member_percentage: member.member_percentage,
@@ -239,10 +382,10 @@ pub async fn get_groups(
 
     // map group token transfer by group_address
     let mut tokens_by_group: HashMap<String, HashMap<String, String>> = HashMap::new();
-    for token_transfer in all_token_tranfer {
+    for token_transfer in page_token_transfer {
         let group_tokens = tokens_by_group
             .entry(token_transfer.group_address.clone())
-            .or_insert_with(HashMap::new);
+            .or_default();
 
         group_tokens.insert(
             token_transfer.token_symbol.to_uppercase(),
@@ -250,25 +393,17 @@ pub async fn get_groups(
         );
     }
 
-    // the funtion response collection / vec
-    let mut response: Vec<GroupFullDetailResponse> = Vec::new();
-
-    for group in groups {
-        let group_address = group.group_address.clone();
-
+    let response = groups.map(|group| {
         // get members for this group
         let members = members_by_group
-            .get(&group_address)
-            .cloned()
+            .remove(&group.group_address)
             .unwrap_or_default();
 
         // get token balances for this group
         let token_balances = tokens_by_group
-            .get(&group_address)
-            .cloned()
+            .remove(&group.group_address)
             .unwrap_or_default();
 
-        // Create GetGroupDetailsResponse
         let group_details = GetGroupDetailsResponse {
             group_address: group.group_address,
             group_name: group.group_name,
@@ -279,62 +414,167 @@ pub async fn get_groups(
             members,
         };
 
-        // Create GroupFullDetailResponse with token balances
-        let full_response = GroupFullDetailResponse {
+        GroupFullDetailResponse {
             group_data: group_details,
             share_usdc: token_balances.get("USDC").cloned(),
             share_usdt: token_balances.get("USDT").cloned(),
             share_eth: token_balances.get("ETH").cloned(),
             share_strk: token_balances.get("STRK").cloned(),
-        };
+        }
+    });
+
+    Ok(Json(response))
+}
+
+/// Unversioned `/all_groups`: every group as a bare array.
+pub async fn get_groups_unpaged(
+    State(state): State<AppState>,
+    ValidatedQuery(query): ValidatedQuery<GroupListQuery>,
+) -> Result<Json<Vec<GroupFullDetailResponse>>, ApiError> {
+    all_pages(query, |query| {
+        get_groups(State(state.clone()), ValidatedQuery(query))
+    })
+    .await
+    .map(Json)
+}
 
-        response.push(full_response);
+/// Follows `next_cursor` to the last page, for the unversioned aliases that
+/// predate pagination and always returned every group.
+async fn all_pages<T, F, Fut>(mut query: GroupListQuery, mut fetch: F) -> Result<Vec<T>, ApiError>
+where
+    F: FnMut(GroupListQuery) -> Fut,
+    Fut: Future<Output = Result<Json<Page<T>>, ApiError>>,
+{
+    query.limit = Some(MAX_PAGE_LIMIT);
+    let mut items = Vec::new();
+    loop {
+        let Json(page) = fetch(query.clone()).await?;
+        items.extend(page.items);
+        match page.next_cursor {
+            Some(cursor) => query.cursor = Some(cursor),
+            None => return Ok(items),
+        }
     }
+}
 
-    Ok(Json(response))
+// Groups created by one address, with what their owner needs to manage them
+#[tracing::instrument(skip_all, fields(created_by = %created_by))]
+pub async fn get_creator_groups(
+    State(state): State<AppState>,
+    ValidatedPath(created_by): ValidatedPath<Address>,
+    ValidatedQuery(mut query): ValidatedQuery<GroupListQuery>,
+) -> Result<Json<Page<CreatorGroupResponse>>, ApiError> {
+    query.created_by = Some(created_by);
+    let keyset = group_keyset(&query)?;
+
+    let mut qb = QueryBuilder::new(
+        r#"
+        SELECT 
+            g.group_address, 
+            g.group_name, 
+            g.usage_remaining, 
+            g.created_at::text as created_at, 
+            g.updated_at::text as updated_at,
+            (SELECT COUNT(*) FROM group_members gm
+                WHERE gm.group_address = g.group_address AND gm.is_active) as member_count,
+            (SELECT COUNT(*) FROM payments p
+                WHERE p.group_address = g.group_address) as payment_count,
+            (SELECT MAX(p.paid_at)::text FROM payments p
+                WHERE p.group_address = g.group_address) as last_payment_at
+        "#,
+    );
+    keyset.push_select(&mut qb);
+    qb.push(" FROM groups g WHERE TRUE");
+    push_group_filters(&mut qb, &query);
+    keyset.push_after(&mut qb);
+    keyset.push_order_limit(&mut qb);
+
+    let rows = qb
+        .build_query_as::<Keyed<CreatorGroupResponse>>()
+        .fetch_all(&state.db)
+        .instrument(query_span("select_creator_groups"))
+        .await?;
+    let mut groups = keyset.page(rows);
+
+    let group_addresses: Vec<String> = groups
+        .items
+        .iter()
+        .map(|group| group.group_address.clone())
+        .collect();
+
+    let volumes = sqlx::query!(
+        r#"
+        SELECT group_address, token_address,
+            SUM(amount) as "total_amount!",
+            COUNT(*) as "payment_count!"
+        FROM payments
+        WHERE group_address = ANY($1)
+        GROUP BY group_address, token_address
+        ORDER BY group_address, token_address
+        "#,
+        &group_addresses
+    )
+    .fetch_all(&state.db)
+    .instrument(query_span("select_creator_group_volumes"))
+    .await?;
+
+    let mut volume_by_group: HashMap<String, Vec<TokenVolume>> = HashMap::new();
+    for row in volumes {
+        volume_by_group
+            .entry(row.group_address)
+            .or_default()
+            .push(TokenVolume {
+                token_address: row.token_address,
+                total_amount: row.total_amount,
+                payment_count: row.payment_count,
+            });
+    }
+
+    for group in &mut groups.items {
+        group.volume = volume_by_group
+            .remove(&group.group_address)
+            .unwrap_or_default();
+    }
+
+    Ok(Json(groups))
 }
 
+#[tracing::instrument(skip_all)]
 pub async fn get_payments_totals(
     State(state): State<AppState>,
 ) -> Result<Json<PaymentsTotalsResponse>, ApiError> {
-    let totals = sqlx::query!(
-        r#"
-        SELECT 
-            COUNT(DISTINCT p.group_address) as total_groups,
-            COUNT(p.tx_hash) as total_payments,
-            COALESCE(SUM(CASE WHEN p.token_address = '0x053c91253bc9682c04929ca02ed00b3e423f6710d2ee7e0d5ebb06f3ecf368a8' THEN p.amount END), 0) as total_usdc_paid,
-            COALESCE(SUM(CASE WHEN p.token_address = '0x068f5c6a61780768455de69077e07e89787839bf8166decfbf92b645209c0fb8' THEN p.amount END), 0) as total_usdt_paid,
-            COALESCE(SUM(CASE WHEN p.token_address = '0x049d36570d4e46f48e99674bd3fcc84644ddd6b96f7c741b1562b82f9e004dc7' THEN p.amount END), 0) as total_eth_paid,
-            COALESCE(SUM(CASE WHEN p.token_address = '0x04718f5a0fc34cc1af16a1cdee98ffb20c31f5cd61d6ab07201858f4287c938d' THEN p.amount END), 0) as total_strk_paid
-        FROM payments p
-        "#
+    // reads the rollups kept by `libs::rollup`, not `payments`
+    let totals = sqlx::query!("SELECT group_count, payment_count FROM payment_rollup_totals")
+        .fetch_optional(&state.db)
+        .instrument(query_span("select_payment_rollup_totals"))
+        .await?;
+
+    let addresses: Vec<&str> = TOKENS.iter().map(|token| token.address).collect();
+    let paid_by_symbol: HashMap<&str, BigDecimal> = sqlx::query!(
+        "SELECT token_address, volume FROM token_rollups WHERE token_address = ANY($1)",
+        &addresses as &[&str]
     )
-    .fetch_one(&state.db)
-    .await
-    .map_err(|e| {
-        eprintln!("Database error fetching payment totals: {}", e);
-        ApiError::Internal("Database Error Occurred")
-    })?;
+    .fetch_all(&state.db)
+    .instrument(query_span("select_token_rollups"))
+    .await?
+    .into_iter()
+    .filter_map(|row| token::lookup(&row.token_address).map(|token| (token.symbol, row.volume)))
+    .collect();
+    let paid = |symbol| {
+        paid_by_symbol
+            .get(symbol)
+            .cloned()
+            .unwrap_or(BigDecimal::from(0))
+            .to_string()
+    };
 
     let response = PaymentsTotalsResponse {
-        total_groups: totals.total_groups.unwrap_or(0),
-        total_payments: totals.total_payments.unwrap_or(0),
-        total_usdc_paid: totals
-            .total_usdc_paid
-            .unwrap_or(BigDecimal::from(0))
-            .to_string(),
-        total_usdt_paid: totals
-            .total_usdt_paid
-            .unwrap_or(BigDecimal::from(0))
-            .to_string(),
-        total_eth_paid: totals
-            .total_eth_paid
-            .unwrap_or(BigDecimal::from(0))
-            .to_string(),
-        total_strk_paid: totals
-            .total_strk_paid
-            .unwrap_or(BigDecimal::from(0))
-            .to_string(),
+        total_groups: totals.as_ref().map_or(0, |totals| totals.group_count),
+        total_payments: totals.as_ref().map_or(0, |totals| totals.payment_count),
+        total_usdc_paid: paid("USDC"),
+        total_usdt_paid: paid("USDT"),
+        total_eth_paid: paid("ETH"),
+        total_strk_paid: paid("STRK"),
     };
 
     Ok(Json(response))
@@ -0,0 +1,265 @@
+use axum::{
+    Json,
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use bigdecimal::BigDecimal;
+use sqlx::{PgPool, Postgres, QueryBuilder};
+use tracing::Instrument;
+
+use crate::{
+    AppState,
+    libs::{
+        db::query_span,
+        error::{ApiError, ErrorCode},
+        extract::{ValidatedJson, ValidatedPath, ValidatedQuery},
+        invoice::{self, STATUS_SQL},
+        pagination::{KeyColumn, Keyed, Keyset, Page},
+    },
+    routes::{
+        group::ensure_group_exists,
+        payment_uri::{PaymentUriFormat, respond},
+        types::{
+            DEFAULT_PAGE_LIMIT, InvoiceDetailResponse, InvoiceListQuery, InvoicePaymentResponse,
+            InvoiceRequest, InvoiceResponse, PaymentUriResponse, QrQuery,
+        },
+    },
+    util::{amount::TokenAmount, payment_uri, token},
+};
+
+const INVOICE_KEY: KeyColumn = KeyColumn {
+    column: "i.id",
+    sql_type: "uuid",
+};
+
+fn select_invoices<'a>() -> QueryBuilder<'a, Postgres> {
+    let mut qb = QueryBuilder::new(
+        "SELECT i.public_id AS id, i.group_address, i.token_address, i.amount, i.paid_amount, \
+         GREATEST(i.amount - i.paid_amount, 0) AS outstanding, i.due_at::text AS due_at, \
+         i.reference, i.payer_address, ",
+    );
+    qb.push(STATUS_SQL);
+    qb.push(
+        " AS status, i.created_at::text AS created_at, i.paid_at::text AS paid_at, \
+         i.cancelled_at::text AS cancelled_at",
+    );
+    qb
+}
+
+#[tracing::instrument(skip_all, fields(group_address = %payload.group_address))]
+pub async fn create_invoice(
+    State(state): State<AppState>,
+    ValidatedJson(payload): ValidatedJson<InvoiceRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    let group_address = payload.group_address.to_string();
+    ensure_group_exists(&state.db, &group_address).await?;
+
+    let public_id = invoice::new_public_id();
+    let amount = BigDecimal::from(payload.amount);
+
+    sqlx::query!(
+        r#"
+        INSERT INTO invoices
+            (public_id, group_address, token_address, amount, due_at, reference, payer_address)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        "#,
+        public_id,
+        group_address,
+        payload.token_address.to_string(),
+        amount,
+        payload.due_at,
+        payload.reference,
+        payload.payer_address.map(|payer| payer.to_string())
+    )
+    .execute(&state.db)
+    .instrument(query_span("insert_invoice"))
+    .await?;
+
+    tracing::info!("Invoice {public_id} created for {group_address}");
+
+    let invoice = invoice_detail(&state.db, &public_id).await?;
+    Ok((StatusCode::CREATED, Json(invoice)))
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn get_invoices(
+    State(state): State<AppState>,
+    ValidatedQuery(query): ValidatedQuery<InvoiceListQuery>,
+) -> Result<Json<Page<InvoiceResponse>>, ApiError> {
+    let keyset = Keyset::new(
+        INVOICE_KEY,
+        query.sort,
+        query.order,
+        query.cursor.as_deref(),
+        query.limit.unwrap_or(DEFAULT_PAGE_LIMIT),
+    )?;
+
+    let mut qb = select_invoices();
+    keyset.push_select(&mut qb);
+    qb.push(" FROM invoices i WHERE TRUE");
+    if let Some(group_address) = query.group_address {
+        qb.push(" AND i.group_address = ")
+            .push_bind(group_address.to_string());
+    }
+    if let Some(payer) = query.payer {
+        qb.push(" AND i.payer_address = ")
+            .push_bind(payer.to_string());
+    }
+    if let Some(token_address) = query.token_address {
+        qb.push(" AND i.token_address = ")
+            .push_bind(token_address.to_string());
+    }
+    if let Some(status) = query.status {
+        qb.push(format_args!(" AND {STATUS_SQL} = "))
+            .push_bind(status.as_str());
+    }
+    if let Some(from) = query.from {
+        qb.push(" AND i.due_at >= ").push_bind(from);
+    }
+    if let Some(to) = query.to {
+        qb.push(" AND i.due_at < ").push_bind(to);
+    }
+    keyset.push_after(&mut qb);
+    keyset.push_order_limit(&mut qb);
+
+    let rows = qb
+        .build_query_as::<Keyed<InvoiceResponse>>()
+        .fetch_all(&state.db)
+        .instrument(query_span("select_invoices"))
+        .await?;
+
+    Ok(Json(keyset.page(rows)))
+}
+
+#[tracing::instrument(skip_all, fields(invoice_id = %invoice_id))]
+pub async fn get_invoice(
+    State(state): State<AppState>,
+    ValidatedPath(invoice_id): ValidatedPath<String>,
+) -> Result<Json<InvoiceDetailResponse>, ApiError> {
+    invoice_detail(&state.db, &invoice_id.to_uppercase())
+        .await
+        .map(Json)
+}
+
+/// Cancels an invoice that is not yet paid. Transfers already applied to it
+/// stay recorded.
+#[tracing::instrument(skip_all, fields(invoice_id = %invoice_id))]
+pub async fn cancel_invoice(
+    State(state): State<AppState>,
+    ValidatedPath(invoice_id): ValidatedPath<String>,
+) -> Result<Json<InvoiceDetailResponse>, ApiError> {
+    let invoice_id = invoice_id.to_uppercase();
+
+    let status = sqlx::query_scalar!(
+        r#"
+        WITH current AS (
+            SELECT id, status FROM invoices WHERE public_id = $1 FOR UPDATE
+        ), cancelled AS (
+            UPDATE invoices i SET status = 'cancelled', cancelled_at = NOW()
+            FROM current c
+            WHERE i.id = c.id AND c.status IN ('open', 'partially_paid')
+            RETURNING i.id
+        )
+        SELECT c.status as "status!" FROM current c
+        "#,
+        invoice_id
+    )
+    .fetch_optional(&state.db)
+    .instrument(query_span("cancel_invoice"))
+    .await?
+    .ok_or_else(invoice_not_found)?;
+
+    if status == "paid" || status == "cancelled" {
+        return Err(ApiError::new(
+            ErrorCode::Conflict,
+            format!("Invoice is already {status}"),
+        ));
+    }
+
+    invoice_detail(&state.db, &invoice_id).await.map(Json)
+}
+
+/// Transfer request for what is still outstanding on an unpaid invoice, for
+/// embedding in the invoice as a QR code.
+#[tracing::instrument(skip_all, fields(invoice_id = %invoice_id))]
+pub async fn get_invoice_payment_uri(
+    State(state): State<AppState>,
+    ValidatedPath(invoice_id): ValidatedPath<String>,
+    format: PaymentUriFormat,
+    ValidatedQuery(query): ValidatedQuery<QrQuery>,
+) -> Result<Response, ApiError> {
+    let invoice_id = invoice_id.to_uppercase();
+
+    let invoice = sqlx::query!(
+        r#"
+        SELECT group_address, token_address, status,
+            GREATEST(amount - paid_amount, 0) as "outstanding!"
+        FROM invoices
+        WHERE public_id = $1
+        "#,
+        invoice_id
+    )
+    .fetch_optional(&state.db)
+    .instrument(query_span("select_invoice_outstanding"))
+    .await?
+    .ok_or_else(invoice_not_found)?;
+
+    if invoice.status == "paid" || invoice.status == "cancelled" {
+        return Err(ApiError::new(
+            ErrorCode::Conflict,
+            format!("Invoice is already {}", invoice.status),
+        ));
+    }
+
+    let amount = TokenAmount::try_from(&invoice.outstanding).map_err(ApiError::internal)?;
+    let token = token::lookup(&invoice.token_address);
+
+    let request = PaymentUriResponse {
+        uri: payment_uri::build(&invoice.token_address, &invoice.group_address, Some(amount)),
+        formatted_amount: token.map(|token| token.format_amount(&invoice.outstanding)),
+        symbol: token.map(|token| token.symbol),
+        group_address: invoice.group_address,
+        token_address: invoice.token_address,
+        amount: Some(amount),
+        invoice_id: Some(invoice_id),
+    };
+
+    respond(format, request, query.scale)
+}
+
+async fn invoice_detail(db: &PgPool, public_id: &str) -> Result<InvoiceDetailResponse, ApiError> {
+    let mut qb = select_invoices();
+    qb.push(" FROM invoices i WHERE i.public_id = ")
+        .push_bind(public_id);
+
+    let invoice = qb
+        .build_query_as::<InvoiceResponse>()
+        .fetch_optional(db)
+        .instrument(query_span("select_invoice"))
+        .await?
+        .ok_or_else(invoice_not_found)?;
+
+    let payments = sqlx::query_as!(
+        InvoicePaymentResponse,
+        r#"
+        SELECT p.tx_hash, t.from_address, p.amount, p.matched_by,
+            p.matched_at::text as "matched_at!"
+        FROM invoice_payments p
+        JOIN invoices i ON i.id = p.invoice_id
+        JOIN group_tx_hashes t ON t.tx_hash = p.tx_hash
+        WHERE i.public_id = $1
+        ORDER BY p.matched_at
+        "#,
+        public_id
+    )
+    .fetch_all(db)
+    .instrument(query_span("select_invoice_payments"))
+    .await?;
+
+    Ok(InvoiceDetailResponse { invoice, payments })
+}
+
+fn invoice_not_found() -> ApiError {
+    ApiError::new(ErrorCode::InvoiceNotFound, "Invoice not found")
+}
@@ -0,0 +1,203 @@
+use axum::{
+    Json,
+    extract::{FromRequestParts, State},
+    http::{HeaderValue, header::CONTENT_TYPE, request::Parts},
+    response::{Html, IntoResponse, Response},
+};
+use std::collections::HashMap;
+use tracing::Instrument;
+
+use crate::{
+    AppState,
+    libs::{
+        db::query_span,
+        error::ApiError,
+        export::negotiate,
+        extract::{ValidatedPath, ValidatedQuery},
+    },
+    routes::types::{
+        DistributionRecord, MemberGroupResponse, MemberPortfolioResponse, MemberTotalsResponse,
+        StatementQuery, TokenEarnings,
+    },
+    util::{address::Address, statement},
+};
+
+/// Older distributions are paged through `/v1/distributions?member=`.
+const RECENT_DISTRIBUTIONS: i64 = 10;
+
+/// Groups, shares and earnings of one address. Like the contract's
+/// `group_address_has_shares_in`, an address without memberships gets an
+/// empty portfolio rather than a 404.
+#[tracing::instrument(skip_all, fields(member_address = %member_address))]
+pub async fn get_member_portfolio(
+    State(state): State<AppState>,
+    ValidatedPath(member_address): ValidatedPath<Address>,
+) -> Result<Json<MemberPortfolioResponse>, ApiError> {
+    let member_address = member_address.to_string();
+
+    let memberships = sqlx::query!(
+        r#"
+        SELECT g.group_address, g.group_name, g.created_by, gm.member_percentage,
+        gm.added_at::text as "added_at!"
+        FROM group_members gm
+        JOIN groups g ON g.group_address = gm.group_address
+        WHERE gm.member_address = $1 AND gm.is_active = true
+        ORDER BY gm.added_at DESC, g.group_address
+        "#,
+        member_address
+    )
+    .fetch_all(&state.db)
+    .instrument(query_span("select_member_groups"))
+    .await?;
+
+    let earnings = sqlx::query!(
+        r#"
+        SELECT group_address, token_address,
+            SUM(token_amount) as "total_amount!",
+            COUNT(*) as "distribution_count!",
+            MAX(sent_at)::text as "last_received_at!"
+        FROM distributions_history
+        WHERE member_address = $1
+        GROUP BY group_address, token_address
+        ORDER BY group_address, token_address
+        "#,
+        member_address
+    )
+    .fetch_all(&state.db)
+    .instrument(query_span("select_member_earnings"))
+    .await?;
+
+    // covers groups the address has since left, so totals stay lifetime
+    let totals = sqlx::query_as!(
+        TokenEarnings,
+        r#"
+        SELECT token_address,
+            SUM(token_amount) as "total_amount!",
+            COUNT(*) as "distribution_count!",
+            MAX(sent_at)::text as "last_received_at!"
+        FROM distributions_history
+        WHERE member_address = $1
+        GROUP BY token_address
+        ORDER BY token_address
+        "#,
+        member_address
+    )
+    .fetch_all(&state.db)
+    .instrument(query_span("select_member_totals"))
+    .await?;
+
+    let recent_distributions = sqlx::query_as!(
+        DistributionRecord,
+        r#"
+        SELECT tx_hash, group_address, member_address, token_address, token_amount,
+        sent_at::text as "sent_at!"
+        FROM distributions_history
+        WHERE member_address = $1
+        ORDER BY sent_at DESC, id DESC
+        LIMIT $2
+        "#,
+        member_address,
+        RECENT_DISTRIBUTIONS
+    )
+    .fetch_all(&state.db)
+    .instrument(query_span("select_member_recent_distributions"))
+    .await?;
+
+    let mut earnings_by_group: HashMap<String, Vec<TokenEarnings>> = HashMap::new();
+    for row in earnings {
+        earnings_by_group
+            .entry(row.group_address)
+            .or_default()
+            .push(TokenEarnings {
+                token_address: row.token_address,
+                total_amount: row.total_amount,
+                distribution_count: row.distribution_count,
+                last_received_at: row.last_received_at,
+            });
+    }
+
+    let groups: Vec<MemberGroupResponse> = memberships
+        .into_iter()
+        .map(|membership| MemberGroupResponse {
+            earnings: earnings_by_group
+                .remove(&membership.group_address)
+                .unwrap_or_default(),
+            group_address: membership.group_address,
+            group_name: membership.group_name,
+            created_by: membership.created_by,
+            member_percentage: membership.member_percentage,
+            added_at: membership.added_at,
+        })
+        .collect();
+
+    Ok(Json(MemberPortfolioResponse {
+        member_address,
+        totals: MemberTotalsResponse {
+            group_count: groups.len(),
+            distribution_count: totals.iter().map(|total| total.distribution_count).sum(),
+            earnings: totals,
+        },
+        groups,
+        recent_distributions,
+    }))
+}
+
+/// Statement format, negotiated from `Accept`; JSON by default.
+#[derive(Debug, Clone, Copy)]
+pub enum StatementFormat {
+    Json,
+    Csv,
+    Html,
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for StatementFormat {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        negotiate(
+            parts,
+            &[
+                ("application/json", StatementFormat::Json),
+                ("text/csv", StatementFormat::Csv),
+                ("text/html", StatementFormat::Html),
+            ],
+        )
+    }
+}
+
+/// Payout statement of one address for a month or range: every distribution
+/// received in the period with opening and closing totals per token.
+#[tracing::instrument(skip_all, fields(member_address = %member_address))]
+pub async fn get_member_statement(
+    State(state): State<AppState>,
+    ValidatedPath(member_address): ValidatedPath<Address>,
+    format: StatementFormat,
+    ValidatedQuery(query): ValidatedQuery<StatementQuery>,
+) -> Result<Response, ApiError> {
+    let (from, to) = query
+        .period()
+        .ok_or_else(|| ApiError::bad_request("Invalid statement period"))?;
+
+    let statement = statement::build(
+        &state.db,
+        &member_address.to_string(),
+        from,
+        to,
+        query.currency,
+    )
+    .await?;
+
+    Ok(match format {
+        StatementFormat::Json => Json(statement).into_response(),
+        StatementFormat::Csv => {
+            let body = statement.to_csv().map_err(ApiError::internal)?;
+            let mut response = body.into_response();
+            response.headers_mut().insert(
+                CONTENT_TYPE,
+                HeaderValue::from_static("text/csv; charset=utf-8"),
+            );
+            response
+        }
+        StatementFormat::Html => Html(statement.to_html()).into_response(),
+    })
+}
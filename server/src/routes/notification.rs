@@ -0,0 +1,90 @@
+use axum::{Json, extract::State};
+use sqlx::QueryBuilder;
+use tracing::Instrument;
+use uuid::Uuid;
+
+use crate::{
+    AppState,
+    libs::{
+        db::query_span,
+        error::{ApiError, ErrorCode},
+        extract::{ValidatedPath, ValidatedQuery},
+        pagination::{KeyColumn, Keyed, Keyset, Page},
+    },
+    routes::types::{DEFAULT_PAGE_LIMIT, NotificationListQuery, NotificationResponse},
+    util::address::Address,
+};
+
+const NOTIFICATION_KEY: KeyColumn = KeyColumn {
+    column: "n.id",
+    sql_type: "uuid",
+};
+
+const NOTIFICATION_COLUMNS: &str = "SELECT n.id, n.group_address, n.kind, n.message, \
+     n.created_at::text AS created_at, n.read_at::text AS read_at";
+
+/// Alerts for the groups an address created, newest first by default.
+#[tracing::instrument(skip_all, fields(creator = %creator))]
+pub async fn get_creator_notifications(
+    State(state): State<AppState>,
+    ValidatedPath(creator): ValidatedPath<Address>,
+    ValidatedQuery(query): ValidatedQuery<NotificationListQuery>,
+) -> Result<Json<Page<NotificationResponse>>, ApiError> {
+    let keyset = Keyset::new(
+        NOTIFICATION_KEY,
+        query.sort,
+        query.order,
+        query.cursor.as_deref(),
+        query.limit.unwrap_or(DEFAULT_PAGE_LIMIT),
+    )?;
+
+    let mut qb = QueryBuilder::new(NOTIFICATION_COLUMNS);
+    keyset.push_select(&mut qb);
+    qb.push(" FROM notifications n WHERE n.recipient = ")
+        .push_bind(creator.to_string());
+    if let Some(group_address) = query.group_address {
+        qb.push(" AND n.group_address = ")
+            .push_bind(group_address.to_string());
+    }
+    if let Some(kind) = query.kind {
+        qb.push(" AND n.kind = ").push_bind(kind);
+    }
+    match query.unread {
+        Some(true) => qb.push(" AND n.read_at IS NULL"),
+        Some(false) => qb.push(" AND n.read_at IS NOT NULL"),
+        None => &mut qb,
+    };
+    keyset.push_after(&mut qb);
+    keyset.push_order_limit(&mut qb);
+
+    let rows = qb
+        .build_query_as::<Keyed<NotificationResponse>>()
+        .fetch_all(&state.db)
+        .instrument(query_span("select_notifications"))
+        .await?;
+
+    Ok(Json(keyset.page(rows)))
+}
+
+/// Marks a notification read; reading it again keeps the first `read_at`.
+#[tracing::instrument(skip_all, fields(notification_id = %id))]
+pub async fn mark_notification_read(
+    State(state): State<AppState>,
+    ValidatedPath(id): ValidatedPath<Uuid>,
+) -> Result<Json<NotificationResponse>, ApiError> {
+    sqlx::query_as!(
+        NotificationResponse,
+        r#"
+        UPDATE notifications SET read_at = COALESCE(read_at, NOW())
+        WHERE id = $1
+        RETURNING id, group_address, kind, message, created_at::text as "created_at!",
+            read_at::text as read_at
+        "#,
+        id
+    )
+    .fetch_optional(&state.db)
+    .instrument(query_span("mark_notification_read"))
+    .await?
+    .map(Json)
+    .ok_or_else(|| ApiError::new(ErrorCode::NotificationNotFound, "Notification not found"))
+}
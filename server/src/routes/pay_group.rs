@@ -1,40 +1,53 @@
-use std::str::FromStr;
-
 use crate::{
     AppState,
-    libs::error::ApiError,
+    libs::{
+        audit,
+        db::query_span,
+        error::{ApiError, ErrorCode},
+        extract::ValidatedJson,
+        forecast, invoice,
+        middleware::RequestId,
+        rollup, usage,
+    },
     routes::types::{CallContractRequest, GetGroupUsageRemaining, PayGroupRequest},
     util::starknet::call_paymesh_contract_function,
 };
 use axum::{Json, extract::State, http::StatusCode, response::IntoResponse};
 use bigdecimal::BigDecimal;
 use serde::Serialize;
-use starknet::core::types::Felt;
+use tracing::{Instrument, field::Empty};
 
 #[derive(Debug, Clone, Serialize, Default)]
 struct Amount {
     amount: BigDecimal,
 }
 
+#[tracing::instrument(
+    skip_all,
+    fields(
+        group_address = %payload.group_address,
+        tx_hash = %payload.tx_hash,
+        request_id = %request_id.0,
+        relayer_tx_hash = Empty,
+    )
+)]
 pub async fn pay_group(
     State(state): State<AppState>,
-    Json(payload): Json<CallContractRequest>,
+    request_id: RequestId,
+    ValidatedJson(payload): ValidatedJson<CallContractRequest>,
 ) -> Result<impl IntoResponse, ApiError> {
-    let group_address = payload.group_address;
-    let from_address = payload.from_address;
-    let tx_hash = payload.tx_hash;
-    let token_amount = BigDecimal::from_str(&payload.token_amount).map_err(|e| {
-        tracing::error!("Failed to parse token amount: {}", e);
-        ApiError::BadRequest("Invalid token amount")
-    })?;
+    let group_address = payload.group_address.to_string();
+    let from_address = payload.from_address.to_string();
+    let tx_hash = payload.tx_hash.to_string();
+    let token_amount = BigDecimal::from(payload.token_amount);
 
     if !state.cache.read().await.contains(&group_address) {
-        return Err(ApiError::BadRequest("Group doesnt exist"));
+        return Err(ApiError::group_not_found());
     }
 
     tracing::info!("Payment hapened for {group_address}");
 
-    let token_address = payload.token_address;
+    let token_address = payload.token_address.to_string();
 
     // Get the users usage remaining
     let record = sqlx::query_as!(
@@ -43,84 +56,77 @@ pub async fn pay_group(
         group_address
     )
     .fetch_optional(&state.db)
-    .await
-    .map_err(|e| {
-        tracing::error!(
-            "Database error when fetching group usage remaining {}",
-            e.to_string()
-        );
-        ApiError::Internal("Database Error Occured")
-    })?
-    .ok_or(ApiError::NotFound("Group Not Found"))?;
+    .instrument(query_span("select_group_usage"))
+    .await?
+    .ok_or_else(ApiError::group_not_found)?;
 
     // Check if the group's usage is exhausted, Return Error if it is
     if record.usage_remaining <= BigDecimal::from(0) {
-        return Err(ApiError::BadRequest(
-            "USAGE COUNT FOR GROUP EXHAUSTED, TOP UP GROUP!",
+        return Err(ApiError::new(
+            ErrorCode::UsageExhausted,
+            "Usage count for group exhausted, top up group",
         ));
     }
 
-    // Convert address to felt
-    let address = Felt::from_hex(group_address.as_str())
-        .map_err(|_| ApiError::BadRequest("TOKEN ADDRESS NOT VALID"))?;
+    let mut tx = state.db.begin().await?;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO group_tx_hashes (group_address, from_address, tx_hash, token_amount, token_address, memo)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        "#,
+        group_address,
+        from_address,
+        tx_hash,
+        token_amount,
+        token_address,
+        payload.memo
+    )
+    .execute(&mut *tx)
+    .instrument(query_span("insert_group_tx_hash"))
+    .await?;
 
-    sqlx::query!(r#"INSERT INTO group_tx_hashes (group_address, from_address, tx_hash, token_amount, token_address) VALUES ($1, $2, $3, $4, $5)"#, 
-    group_address, from_address, tx_hash, token_amount, token_address)
-        .execute(&state.db)
+    if let Some(invoice) = invoice::match_transfer(&mut tx, &tx_hash).await? {
+        tracing::info!("Transfer {tx_hash} applied to invoice {invoice}");
+    }
+
+    tx.commit().await?;
+
+    let relayer_tx_hash = call_paymesh_contract_function(payload.group_address, &request_id.0)
         .await
         .map_err(|e| {
-            tracing::error!("Failed to execute query: {}", e);
-            ApiError::Internal("Database Error Occurred")
+            ApiError::new(
+                ErrorCode::ContractCallFailed,
+                "Failed to call paymesh contract",
+            )
+            .with_cause(e)
         })?;
-
-    call_paymesh_contract_function(address)
-        .await
-        .map_err(|_| ApiError::BadRequest("Failed to call paymesh contract"))?;
+    tracing::Span::current().record("relayer_tx_hash", relayer_tx_hash.to_string());
 
     tracing::info!("Payment hapened");
     Ok((StatusCode::OK, Json("TOKEN SPLIT SUCCESSFULLY")))
 }
 
+#[tracing::instrument(
+    skip_all,
+    fields(group_address = %payload.group_address, tx_hash = %payload.tx_hash)
+)]
 pub async fn store_payment_distribution_history(
     State(state): State<AppState>,
-    Json(payload): Json<PayGroupRequest>,
+    ValidatedJson(payload): ValidatedJson<PayGroupRequest>,
 ) -> Result<impl IntoResponse, ApiError> {
-    let group_address = payload.group_address;
+    let group_address = payload.group_address.to_string();
     let usage_remaining = BigDecimal::from(payload.usage_remaining);
-    let token_address = payload.token_address;
-    let token_amount = BigDecimal::from_str(&payload.token_amount).map_err(|e| {
-        tracing::error!("Failed to parse token amount: {}", e);
-        ApiError::BadRequest("Invalid token amount")
-    })?;
-    let tx_hash = payload.tx_hash;
+    let token_address = payload.token_address.to_string();
+    let token_amount = BigDecimal::from(payload.token_amount);
+    let tx_hash = payload.tx_hash.to_string();
     let group_members = payload.members;
 
     tracing::info!("Update the payment history of group");
-    let mut tx = state.db.begin().await.map_err(|e| {
-        dbg!(e);
-        ApiError::Internal("Failed to begin transaction")
-    })?;
+    let mut tx = state.db.begin().await?;
 
-    let rows_affected = sqlx::query!(
-        r#"
-        UPDATE groups 
-        SET usage_remaining = $1 
-        WHERE group_address = $2
-        "#,
-        usage_remaining,
-        group_address
-    )
-    .execute(&mut *tx)
-    .await
-    .map_err(|e| {
-        tracing::error!("Failed to update group usage {}", e.to_string());
-        ApiError::Internal("Database Error Occured")
-    })?
-    .rows_affected();
-    tracing::info!("calling the pay group function 4");
-
-    if rows_affected == 0 {
-        return Err(ApiError::BadRequest("Failed to update group usage"));
+    if !usage::consume(&mut tx, &group_address, usage_remaining, &tx_hash).await? {
+        return Err(ApiError::group_not_found());
     }
 
     let previous_amount: Amount = sqlx::query_as!(
@@ -132,54 +138,51 @@ pub async fn store_payment_distribution_history(
         token_address
     )
     .fetch_optional(&mut *tx)
-    .await
-    .map_err(|e| {
-        tracing::error!("Failed to fetch previous amount, {}", e.to_string());
-        ApiError::Internal("Database Error Occured")
-    })?
+    .instrument(query_span("select_group_token_history"))
+    .await?
     .unwrap_or_default();
 
     let new_amount = previous_amount.amount + token_amount.clone();
 
     sqlx::query!(r#"UPDATE group_token_history SET amount = $1 WHERE group_address = $2 AND token_address = $3"#, new_amount, group_address, token_address)
         .execute(&mut *tx)
-        .await
-        .map_err(|e|
-            { tracing::error!("Failed to update group token history, {}", e.to_string());
-                ApiError::Internal("Database Error Occured")})?;
+        .instrument(query_span("update_group_token_history"))
+        .await?;
 
-    sqlx::query!(r#"INSERT INTO payments (tx_hash, group_address, token_address, amount) VALUES ($1, $2, $3, $4)"#, 
-    tx_hash, group_address, token_address, token_amount)
-        .execute(&mut *tx)
-        .await
-        .map_err(|e| {
-            tracing::error!("Failed to execute query: {}", e);
-            ApiError::Internal("Database Error Occurred")
-        })?;
+    // split by whatever share set is current when the payment is recorded
+    sqlx::query!(
+        r#"
+        INSERT INTO payments (tx_hash, group_address, token_address, amount, share_set_id)
+        VALUES ($1, $2::text, $3, $4,
+            (SELECT id FROM group_share_sets WHERE group_address = $2 AND valid_to IS NULL))
+        "#,
+        tx_hash,
+        group_address,
+        token_address,
+        token_amount
+    )
+    .execute(&mut *tx)
+    .instrument(query_span("insert_payment"))
+    .await?;
+
+    rollup::record_payment(&mut tx, &group_address, &token_address, &token_amount).await?;
 
     for member in group_members {
-        let member_amount: BigDecimal =
-            BigDecimal::from_str(&member.member_amount).map_err(|e| {
-                tracing::error!("Failed to parse token amount: {}", e);
-                ApiError::BadRequest("Invalid token amount")
-            })?;
+        let member_amount = BigDecimal::from(member.member_amount);
 
         sqlx::query!(r#"INSERT INTO distributions_history (group_address, tx_hash, member_address, token_address, token_amount) VALUES ($1, $2, $3, $4, $5)"#,
-            group_address, tx_hash, member.member_address, token_address, member_amount)
+            group_address, tx_hash, member.member_address.to_string(), token_address, member_amount)
             .execute(&mut *tx)
-            .await
-            .map_err(|e| {
-                tracing::error!("Error occurred while inserting distribution history: {}", e.to_string());
-                ApiError::Internal("Database Error Occurred")
-            })?;
+            .instrument(query_span("insert_distribution"))
+            .await?;
     }
 
-    tx.commit().await.map_err(|e| {
-        dbg!(e);
-        ApiError::Internal("Failed to commit transaction")
-    })?;
+    let findings = audit::audit_payment(&mut tx, &tx_hash).await?;
+    forecast::check_thresholds(&mut tx, &group_address).await?;
+
+    tx.commit().await?;
 
-    tracing::info!("HISTORY ADDED SUCCESSFULLY");
+    tracing::info!(findings, "HISTORY ADDED SUCCESSFULLY");
 
     Ok((StatusCode::OK, Json("HISTORY ADDED SUCCESSFULLY")))
 }
@@ -0,0 +1,206 @@
+use axum::{Json, extract::State};
+use sqlx::{PgPool, Postgres, QueryBuilder};
+use std::collections::HashMap;
+use tracing::Instrument;
+
+use crate::{
+    AppState,
+    libs::{
+        db::query_span,
+        error::ApiError,
+        extract::{ValidatedPath, ValidatedQuery},
+        pagination::{KeyColumn, Keyed, Keyset, Page},
+    },
+    routes::{
+        group::ensure_group_exists,
+        types::{
+            DEFAULT_PAGE_LIMIT, PayerGroupResponse, PayerListQuery, PayerResponse, PayerSort,
+            TokenVolume,
+        },
+    },
+    util::address::Address,
+};
+
+fn payer_keyset(
+    query: &PayerListQuery,
+    column: &'static str,
+) -> Result<Keyset<PayerSort>, ApiError> {
+    Keyset::new(
+        KeyColumn {
+            column,
+            sql_type: "text",
+        },
+        query.sort,
+        query.order,
+        query.cursor.as_deref(),
+        query.limit.unwrap_or(DEFAULT_PAGE_LIMIT),
+    )
+}
+
+/// Appends the `PayerListQuery` filters to a query over `group_tx_hashes gt`.
+fn push_transfer_filters(qb: &mut QueryBuilder<'_, Postgres>, query: &PayerListQuery) {
+    if let Some(token_address) = query.token_address {
+        qb.push(" AND gt.token_address = ")
+            .push_bind(token_address.to_string());
+    }
+    if let Some(from) = query.from {
+        qb.push(" AND gt.created_at >= ").push_bind(from);
+    }
+    if let Some(to) = query.to {
+        qb.push(" AND gt.created_at < ").push_bind(to);
+    }
+}
+
+// Who funded this group
+#[tracing::instrument(skip_all, fields(group_address = %group_address))]
+pub async fn get_group_payers(
+    State(state): State<AppState>,
+    ValidatedPath(group_address): ValidatedPath<Address>,
+    ValidatedQuery(query): ValidatedQuery<PayerListQuery>,
+) -> Result<Json<Page<PayerResponse>>, ApiError> {
+    let group_address = group_address.to_string();
+    ensure_group_exists(&state.db, &group_address).await?;
+    let keyset = payer_keyset(&query, "t.from_address")?;
+
+    // aggregate first so the keyset can compare against the aggregates
+    let mut qb = QueryBuilder::new(
+        "SELECT t.from_address, t.payment_count, \
+         t.first_paid_at::text AS first_paid_at, t.last_paid_at::text AS last_paid_at",
+    );
+    keyset.push_select(&mut qb);
+    qb.push(
+        " FROM (SELECT gt.from_address, COUNT(*) AS payment_count, \
+         MIN(gt.created_at) AS first_paid_at, MAX(gt.created_at) AS last_paid_at \
+         FROM group_tx_hashes gt WHERE gt.group_address = ",
+    )
+    .push_bind(group_address.clone());
+    push_transfer_filters(&mut qb, &query);
+    qb.push(" GROUP BY gt.from_address) t WHERE TRUE");
+    keyset.push_after(&mut qb);
+    keyset.push_order_limit(&mut qb);
+
+    let rows = qb
+        .build_query_as::<Keyed<PayerResponse>>()
+        .fetch_all(&state.db)
+        .instrument(query_span("select_group_payers"))
+        .await?;
+    let mut payers = keyset.page(rows);
+
+    let payer_addresses: Vec<String> = payers
+        .items
+        .iter()
+        .map(|payer| payer.from_address.clone())
+        .collect();
+
+    let mut qb = QueryBuilder::new(
+        "SELECT gt.from_address AS owner, gt.token_address, \
+         SUM(gt.token_amount) AS total_amount, COUNT(*) AS payment_count \
+         FROM group_tx_hashes gt WHERE gt.group_address = ",
+    );
+    qb.push_bind(group_address)
+        .push(" AND gt.from_address = ANY(")
+        .push_bind(payer_addresses)
+        .push(")");
+    push_transfer_filters(&mut qb, &query);
+    qb.push(" GROUP BY gt.from_address, gt.token_address ORDER BY gt.token_address");
+
+    let mut totals = token_totals(&state.db, qb, "select_group_payer_totals").await?;
+    for payer in &mut payers.items {
+        payer.totals = totals.remove(&payer.from_address).unwrap_or_default();
+    }
+
+    Ok(Json(payers))
+}
+
+// Where did this payer's money go
+#[tracing::instrument(skip_all, fields(from_address = %from_address))]
+pub async fn get_payer_groups(
+    State(state): State<AppState>,
+    ValidatedPath(from_address): ValidatedPath<Address>,
+    ValidatedQuery(query): ValidatedQuery<PayerListQuery>,
+) -> Result<Json<Page<PayerGroupResponse>>, ApiError> {
+    let from_address = from_address.to_string();
+    let keyset = payer_keyset(&query, "t.group_address")?;
+
+    let mut qb = QueryBuilder::new(
+        "SELECT t.group_address, g.group_name, t.payment_count, \
+         t.first_paid_at::text AS first_paid_at, t.last_paid_at::text AS last_paid_at",
+    );
+    keyset.push_select(&mut qb);
+    qb.push(
+        " FROM (SELECT gt.group_address, COUNT(*) AS payment_count, \
+         MIN(gt.created_at) AS first_paid_at, MAX(gt.created_at) AS last_paid_at \
+         FROM group_tx_hashes gt WHERE gt.from_address = ",
+    )
+    .push_bind(from_address.clone());
+    push_transfer_filters(&mut qb, &query);
+    qb.push(
+        " GROUP BY gt.group_address) t \
+         JOIN groups g ON g.group_address = t.group_address WHERE TRUE",
+    );
+    keyset.push_after(&mut qb);
+    keyset.push_order_limit(&mut qb);
+
+    let rows = qb
+        .build_query_as::<Keyed<PayerGroupResponse>>()
+        .fetch_all(&state.db)
+        .instrument(query_span("select_payer_groups"))
+        .await?;
+    let mut groups = keyset.page(rows);
+
+    let group_addresses: Vec<String> = groups
+        .items
+        .iter()
+        .map(|group| group.group_address.clone())
+        .collect();
+
+    let mut qb = QueryBuilder::new(
+        "SELECT gt.group_address AS owner, gt.token_address, \
+         SUM(gt.token_amount) AS total_amount, COUNT(*) AS payment_count \
+         FROM group_tx_hashes gt WHERE gt.from_address = ",
+    );
+    qb.push_bind(from_address)
+        .push(" AND gt.group_address = ANY(")
+        .push_bind(group_addresses)
+        .push(")");
+    push_transfer_filters(&mut qb, &query);
+    qb.push(" GROUP BY gt.group_address, gt.token_address ORDER BY gt.token_address");
+
+    let mut totals = token_totals(&state.db, qb, "select_payer_group_totals").await?;
+    for group in &mut groups.items {
+        group.totals = totals.remove(&group.group_address).unwrap_or_default();
+    }
+
+    Ok(Json(groups))
+}
+
+#[derive(sqlx::FromRow)]
+struct OwnedTokenVolume {
+    owner: String,
+    token_address: String,
+    total_amount: bigdecimal::BigDecimal,
+    payment_count: i64,
+}
+
+/// Runs a per-token totals query and groups the rows by their `owner` column.
+async fn token_totals(
+    db: &PgPool,
+    mut qb: QueryBuilder<'_, Postgres>,
+    operation: &'static str,
+) -> Result<HashMap<String, Vec<TokenVolume>>, ApiError> {
+    let rows = qb
+        .build_query_as::<OwnedTokenVolume>()
+        .fetch_all(db)
+        .instrument(query_span(operation))
+        .await?;
+
+    let mut totals: HashMap<String, Vec<TokenVolume>> = HashMap::new();
+    for row in rows {
+        totals.entry(row.owner).or_default().push(TokenVolume {
+            token_address: row.token_address,
+            total_amount: row.total_amount,
+            payment_count: row.payment_count,
+        });
+    }
+    Ok(totals)
+}
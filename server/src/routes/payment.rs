@@ -0,0 +1,220 @@
+use axum::{Json, extract::State};
+use sqlx::{PgPool, QueryBuilder};
+use tracing::Instrument;
+
+use crate::{
+    AppState,
+    libs::{
+        db::query_span,
+        error::{ApiError, ErrorCode},
+        extract::{ValidatedPath, ValidatedQuery},
+        pagination::{KeyColumn, Keyed, Keyset, Page},
+    },
+    routes::{
+        group::ensure_group_exists,
+        types::{
+            DEFAULT_PAGE_LIMIT, DistributionListQuery, DistributionRecord, DistributionResponse,
+            PaymentDetailResponse, PaymentListQuery, PaymentResponse,
+        },
+    },
+    util::address::{Address, TxHash},
+};
+
+const PAYMENT_KEY: KeyColumn = KeyColumn {
+    column: "p.tx_hash",
+    sql_type: "text",
+};
+
+const DISTRIBUTION_KEY: KeyColumn = KeyColumn {
+    column: "d.id",
+    sql_type: "uuid",
+};
+
+#[tracing::instrument(skip_all)]
+pub async fn get_payments(
+    State(state): State<AppState>,
+    ValidatedQuery(query): ValidatedQuery<PaymentListQuery>,
+) -> Result<Json<Page<PaymentResponse>>, ApiError> {
+    list_payments(&state.db, &query).await.map(Json)
+}
+
+#[tracing::instrument(skip_all, fields(group_address = %group_address))]
+pub async fn get_group_payments(
+    State(state): State<AppState>,
+    ValidatedPath(group_address): ValidatedPath<Address>,
+    ValidatedQuery(mut query): ValidatedQuery<PaymentListQuery>,
+) -> Result<Json<Page<PaymentResponse>>, ApiError> {
+    ensure_group_exists(&state.db, &group_address.to_string()).await?;
+    query.group_address = Some(group_address);
+    list_payments(&state.db, &query).await.map(Json)
+}
+
+async fn list_payments(
+    db: &PgPool,
+    query: &PaymentListQuery,
+) -> Result<Page<PaymentResponse>, ApiError> {
+    let keyset = Keyset::new(
+        PAYMENT_KEY,
+        query.sort,
+        query.order,
+        query.cursor.as_deref(),
+        query.limit.unwrap_or(DEFAULT_PAGE_LIMIT),
+    )?;
+
+    let mut qb = QueryBuilder::new(
+        "SELECT p.tx_hash, p.group_address, p.token_address, p.amount, p.paid_at::text AS paid_at",
+    );
+    keyset.push_select(&mut qb);
+    qb.push(" FROM payments p WHERE TRUE");
+    if let Some(group_address) = query.group_address {
+        qb.push(" AND p.group_address = ")
+            .push_bind(group_address.to_string());
+    }
+    if let Some(member) = query.member {
+        qb.push(
+            " AND EXISTS (SELECT 1 FROM distributions_history d \
+             WHERE d.tx_hash = p.tx_hash AND d.member_address = ",
+        )
+        .push_bind(member.to_string())
+        .push(")");
+    }
+    if let Some(token_address) = query.token_address {
+        qb.push(" AND p.token_address = ")
+            .push_bind(token_address.to_string());
+    }
+    if let Some(from) = query.from {
+        qb.push(" AND p.paid_at >= ").push_bind(from);
+    }
+    if let Some(to) = query.to {
+        qb.push(" AND p.paid_at < ").push_bind(to);
+    }
+    keyset.push_after(&mut qb);
+    keyset.push_order_limit(&mut qb);
+
+    let rows = qb
+        .build_query_as::<Keyed<PaymentResponse>>()
+        .fetch_all(db)
+        .instrument(query_span("select_payments"))
+        .await?;
+
+    Ok(keyset.page(rows))
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn get_distributions(
+    State(state): State<AppState>,
+    ValidatedQuery(query): ValidatedQuery<DistributionListQuery>,
+) -> Result<Json<Page<DistributionRecord>>, ApiError> {
+    list_distributions(&state.db, &query).await.map(Json)
+}
+
+#[tracing::instrument(skip_all, fields(group_address = %group_address))]
+pub async fn get_group_distributions(
+    State(state): State<AppState>,
+    ValidatedPath(group_address): ValidatedPath<Address>,
+    ValidatedQuery(mut query): ValidatedQuery<DistributionListQuery>,
+) -> Result<Json<Page<DistributionRecord>>, ApiError> {
+    ensure_group_exists(&state.db, &group_address.to_string()).await?;
+    query.group_address = Some(group_address);
+    list_distributions(&state.db, &query).await.map(Json)
+}
+
+async fn list_distributions(
+    db: &PgPool,
+    query: &DistributionListQuery,
+) -> Result<Page<DistributionRecord>, ApiError> {
+    let keyset = Keyset::new(
+        DISTRIBUTION_KEY,
+        query.sort,
+        query.order,
+        query.cursor.as_deref(),
+        query.limit.unwrap_or(DEFAULT_PAGE_LIMIT),
+    )?;
+
+    let mut qb = QueryBuilder::new(
+        "SELECT d.tx_hash, d.group_address, d.member_address, d.token_address, d.token_amount, \
+         d.sent_at::text AS sent_at",
+    );
+    keyset.push_select(&mut qb);
+    qb.push(" FROM distributions_history d WHERE TRUE");
+    if let Some(group_address) = query.group_address {
+        qb.push(" AND d.group_address = ")
+            .push_bind(group_address.to_string());
+    }
+    if let Some(member) = query.member {
+        qb.push(" AND d.member_address = ")
+            .push_bind(member.to_string());
+    }
+    if let Some(token_address) = query.token_address {
+        qb.push(" AND d.token_address = ")
+            .push_bind(token_address.to_string());
+    }
+    if let Some(tx_hash) = query.tx_hash {
+        qb.push(" AND d.tx_hash = ").push_bind(tx_hash.to_string());
+    }
+    if let Some(from) = query.from {
+        qb.push(" AND d.sent_at >= ").push_bind(from);
+    }
+    if let Some(to) = query.to {
+        qb.push(" AND d.sent_at < ").push_bind(to);
+    }
+    keyset.push_after(&mut qb);
+    keyset.push_order_limit(&mut qb);
+
+    let rows = qb
+        .build_query_as::<Keyed<DistributionRecord>>()
+        .fetch_all(db)
+        .instrument(query_span("select_distributions"))
+        .await?;
+
+    Ok(keyset.page(rows))
+}
+
+#[tracing::instrument(skip_all, fields(tx_hash = %tx_hash))]
+pub async fn get_payment(
+    State(state): State<AppState>,
+    ValidatedPath(tx_hash): ValidatedPath<TxHash>,
+) -> Result<Json<PaymentDetailResponse>, ApiError> {
+    let tx_hash = tx_hash.to_string();
+
+    let row = sqlx::query!(
+        r#"
+        SELECT p.tx_hash, p.group_address, p.token_address, p.amount,
+            p.paid_at::text as "paid_at!", s.version as "share_set_version?"
+        FROM payments p
+        LEFT JOIN group_share_sets s ON s.id = p.share_set_id
+        WHERE p.tx_hash = $1
+        "#,
+        tx_hash
+    )
+    .fetch_optional(&state.db)
+    .instrument(query_span("select_payment"))
+    .await?
+    .ok_or_else(|| ApiError::new(ErrorCode::PaymentNotFound, "Payment not found"))?;
+
+    let distributions = sqlx::query_as!(
+        DistributionResponse,
+        r#"
+        SELECT member_address, token_address, token_amount, sent_at::text as "sent_at!"
+        FROM distributions_history
+        WHERE tx_hash = $1
+        ORDER BY token_amount DESC, member_address
+        "#,
+        tx_hash
+    )
+    .fetch_all(&state.db)
+    .instrument(query_span("select_payment_distributions"))
+    .await?;
+
+    Ok(Json(PaymentDetailResponse {
+        payment: PaymentResponse {
+            tx_hash: row.tx_hash,
+            group_address: row.group_address,
+            token_address: row.token_address,
+            amount: row.amount,
+            paid_at: row.paid_at,
+        },
+        share_set_version: row.share_set_version,
+        distributions,
+    }))
+}
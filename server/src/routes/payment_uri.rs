@@ -0,0 +1,108 @@
+use axum::{
+    Json,
+    extract::{FromRequestParts, State},
+    http::{HeaderValue, header::CONTENT_TYPE, request::Parts},
+    response::{IntoResponse, Response},
+};
+
+use crate::{
+    AppState,
+    libs::{
+        error::ApiError,
+        export::negotiate,
+        extract::{ValidatedPath, ValidatedQuery},
+    },
+    routes::{
+        group::ensure_group_exists,
+        types::{PaymentUriQuery, PaymentUriResponse},
+    },
+    util::{
+        address::Address,
+        payment_uri::{self, DEFAULT_QR_SCALE},
+        token,
+    },
+};
+
+/// Payment requests come as JSON or as a QR code of the URI, so an `<img>`
+/// pointing at the endpoint gets an image.
+#[derive(Debug, Clone, Copy)]
+pub enum PaymentUriFormat {
+    Json,
+    Svg,
+    Png,
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for PaymentUriFormat {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        negotiate(
+            parts,
+            &[
+                ("application/json", PaymentUriFormat::Json),
+                ("image/svg+xml", PaymentUriFormat::Svg),
+                ("image/png", PaymentUriFormat::Png),
+            ],
+        )
+    }
+}
+
+/// Renders `request` in the negotiated format; QR codes are generated here,
+/// `scale` pixels per module.
+pub fn respond(
+    format: PaymentUriFormat,
+    request: PaymentUriResponse,
+    scale: Option<u32>,
+) -> Result<Response, ApiError> {
+    let scale = scale.unwrap_or(DEFAULT_QR_SCALE);
+    let (body, content_type) = match format {
+        PaymentUriFormat::Json => return Ok(Json(request).into_response()),
+        PaymentUriFormat::Svg => (
+            payment_uri::to_svg(&request.uri, scale)
+                .map_err(ApiError::internal)?
+                .into_bytes(),
+            "image/svg+xml",
+        ),
+        PaymentUriFormat::Png => (
+            payment_uri::to_png(&request.uri, scale).map_err(ApiError::internal)?,
+            "image/png",
+        ),
+    };
+
+    let mut response = body.into_response();
+    response
+        .headers_mut()
+        .insert(CONTENT_TYPE, HeaderValue::from_static(content_type));
+    Ok(response)
+}
+
+/// Transfer request paying `token_address`, and optionally `amount`, into the
+/// group.
+#[tracing::instrument(skip_all, fields(group_address = %group_address))]
+pub async fn get_group_payment_uri(
+    State(state): State<AppState>,
+    ValidatedPath(group_address): ValidatedPath<Address>,
+    format: PaymentUriFormat,
+    ValidatedQuery(query): ValidatedQuery<PaymentUriQuery>,
+) -> Result<Response, ApiError> {
+    let group_address = group_address.to_string();
+    ensure_group_exists(&state.db, &group_address).await?;
+
+    let token_address = query.token_address.to_string();
+    // validated as supported
+    let token = token::lookup(&token_address).ok_or_else(|| ApiError::internal("unknown token"))?;
+
+    let request = PaymentUriResponse {
+        uri: payment_uri::build(&token_address, &group_address, query.amount),
+        formatted_amount: query
+            .amount
+            .map(|amount| token.format_amount(&amount.into())),
+        group_address,
+        token_address,
+        symbol: Some(token.symbol),
+        amount: query.amount,
+        invoice_id: None,
+    };
+
+    respond(format, request, query.scale)
+}
@@ -0,0 +1,118 @@
+use axum::{Json, extract::State};
+use bigdecimal::{BigDecimal, ToPrimitive};
+use tracing::Instrument;
+
+use crate::{
+    AppState,
+    libs::{
+        db::query_span,
+        error::{ApiError, ErrorCode},
+        extract::{ValidatedJson, ValidatedPath},
+        middleware::RequestId,
+    },
+    routes::types::{SplitPreviewRequest, SplitPreviewResponse, SplitShareResponse, UsageImpact},
+    util::{
+        address::Address,
+        amount::TokenAmount,
+        split::{self, usage_fee},
+        starknet::token_balance,
+        token,
+    },
+};
+
+/// What each member would receive if `amount` of a token were paid into the
+/// group now, using the contract's integer math. The contract splits the
+/// group's whole balance of the token, so whatever it already holds is split
+/// along with `amount`.
+#[tracing::instrument(skip_all, fields(group_address = %address, request_id = %request_id.0))]
+pub async fn preview_split(
+    State(state): State<AppState>,
+    request_id: RequestId,
+    ValidatedPath(address): ValidatedPath<Address>,
+    ValidatedJson(payload): ValidatedJson<SplitPreviewRequest>,
+) -> Result<Json<SplitPreviewResponse>, ApiError> {
+    let group_address = address.to_string();
+    let token_address = payload.token_address.to_string();
+    // validated as supported
+    let token = token::lookup(&token_address).ok_or_else(|| ApiError::internal("unknown token"))?;
+
+    let usage_remaining = sqlx::query_scalar!(
+        "SELECT usage_remaining FROM groups WHERE group_address = $1",
+        group_address
+    )
+    .fetch_optional(&state.db)
+    .instrument(query_span("select_group_usage"))
+    .await?
+    .ok_or_else(ApiError::group_not_found)?;
+
+    if usage_remaining <= BigDecimal::from(0) {
+        return Err(ApiError::new(
+            ErrorCode::UsageExhausted,
+            "Usage count for group exhausted, top up group",
+        ));
+    }
+
+    let members = sqlx::query!(
+        r#"
+        SELECT member_address, member_percentage
+        FROM group_members
+        WHERE group_address = $1 AND is_active
+        ORDER BY member_percentage DESC, member_address
+        "#,
+        group_address
+    )
+    .fetch_all(&state.db)
+    .instrument(query_span("select_group_members"))
+    .await?
+    .into_iter()
+    .map(|row| {
+        row.member_percentage
+            .is_integer()
+            .then(|| row.member_percentage.to_u8())
+            .flatten()
+            .map(|percentage| (row.member_address, percentage))
+            .ok_or_else(|| ApiError::internal("member percentage is not a whole number"))
+    })
+    .collect::<Result<Vec<_>, ApiError>>()?;
+
+    let group_balance = token_balance(payload.token_address, address, &request_id.0)
+        .await
+        .map_err(|e| {
+            ApiError::new(
+                ErrorCode::ContractCallFailed,
+                "Failed to read the group's token balance",
+            )
+            .with_cause(e)
+        })?;
+
+    let split = TokenAmount::try_from(payload.amount.to_biguint() + group_balance.to_biguint())
+        .and_then(|total| split::split(total, members))
+        .map_err(|e| {
+            ApiError::new(ErrorCode::InvalidAmount, "amount is too large to split").with_cause(e)
+        })?;
+
+    Ok(Json(SplitPreviewResponse {
+        group_address,
+        token_address,
+        symbol: token.symbol,
+        amount: payload.amount,
+        group_balance,
+        members: split
+            .shares
+            .into_iter()
+            .map(|share| SplitShareResponse {
+                formatted_amount: token.format_amount(&share.amount.into()),
+                member_address: share.member_address,
+                percentage: share.percentage,
+                amount: share.amount,
+            })
+            .collect(),
+        distributed: split.distributed,
+        remainder: split.remainder,
+        usage: UsageImpact {
+            usage_remaining_after: &usage_remaining - BigDecimal::from(1),
+            usage_remaining,
+            usage_fee: usage_fee(),
+        },
+    }))
+}
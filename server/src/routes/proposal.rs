@@ -0,0 +1,365 @@
+use std::collections::HashMap;
+
+use axum::{Json, extract::State, http::StatusCode, response::IntoResponse};
+use sqlx::{PgConnection, PgPool, QueryBuilder, types::BigDecimal};
+use time::OffsetDateTime;
+use tracing::Instrument;
+use uuid::Uuid;
+
+use crate::{
+    AppState,
+    libs::{
+        audit,
+        db::query_span,
+        error::{ApiError, ErrorCode},
+        extract::{ValidatedJson, ValidatedPath, ValidatedQuery},
+        pagination::{KeyColumn, Keyed, Keyset, Page},
+        share_set,
+    },
+    routes::{
+        group::{ensure_group_exists, lock_group},
+        types::{
+            DEFAULT_PAGE_LIMIT, GroupUpdatedRequest, ProposalApprovalResponse, ProposalListQuery,
+            ProposalResponse, ShareSetMemberResponse, UpdateApprovedRequest,
+            UpdateRequestedRequest,
+        },
+    },
+    util::address::Address,
+};
+
+const PROPOSAL_KEY: KeyColumn = KeyColumn {
+    column: "p.id",
+    sql_type: "uuid",
+};
+
+const PROPOSAL_COLUMNS: &str = "SELECT p.id, p.group_address, p.request_tx_hash, p.requester, \
+     p.new_name, p.status, p.approval_count, p.total_members, p.requested_at::text AS requested_at, \
+     p.resolved_at::text AS resolved_at, p.update_tx_hash, s.version AS share_set_version";
+
+const PROPOSAL_FROM: &str =
+    " FROM group_update_proposals p LEFT JOIN group_share_sets s ON s.id = p.share_set_id";
+
+/// `GroupUpdateRequested`: stores the proposal as the group's pending one,
+/// superseding any earlier request that was never applied.
+#[tracing::instrument(skip_all, fields(group_address = %group_address, tx_hash = %payload.tx_hash))]
+pub async fn record_update_request(
+    State(state): State<AppState>,
+    ValidatedPath(group_address): ValidatedPath<Address>,
+    ValidatedJson(payload): ValidatedJson<UpdateRequestedRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    let group_address = group_address.to_string();
+
+    let mut tx = state.db.begin().await?;
+    lock_group(&mut tx, &group_address).await?;
+
+    sqlx::query!(
+        r#"
+        UPDATE group_update_proposals SET status = 'superseded', resolved_at = NOW()
+        WHERE group_address = $1 AND status = 'pending'
+        "#,
+        group_address
+    )
+    .execute(&mut *tx)
+    .instrument(query_span("supersede_group_update_proposal"))
+    .await?;
+
+    // approvals are counted against the members at request time until the
+    // contract reports its own total
+    let proposal_id = sqlx::query_scalar!(
+        r#"
+        INSERT INTO group_update_proposals
+            (group_address, request_tx_hash, requester, new_name, total_members)
+        SELECT $1::text, $2, $3, $4, COUNT(*)::int
+        FROM group_members WHERE group_address = $1 AND is_active
+        RETURNING id
+        "#,
+        group_address,
+        payload.tx_hash.to_string(),
+        payload.requester.to_string(),
+        payload.new_name
+    )
+    .fetch_one(&mut *tx)
+    .instrument(query_span("insert_group_update_proposal"))
+    .await?;
+
+    let (addresses, percentages): (Vec<String>, Vec<BigDecimal>) = payload
+        .members
+        .iter()
+        .map(|member| (member.addr.to_string(), BigDecimal::from(member.percentage)))
+        .unzip();
+
+    sqlx::query!(
+        r#"
+        INSERT INTO group_update_proposal_members (proposal_id, member_address, member_percentage)
+        SELECT $1, member_address, member_percentage
+        FROM UNNEST($2::text[], $3::numeric[]) AS m(member_address, member_percentage)
+        "#,
+        proposal_id,
+        &addresses,
+        &percentages
+    )
+    .execute(&mut *tx)
+    .instrument(query_span("insert_group_update_proposal_members"))
+    .await?;
+
+    tx.commit().await?;
+
+    tracing::info!("Group update requested for {}", group_address);
+
+    let proposal = proposal(&state.db, proposal_id).await?;
+    Ok((StatusCode::CREATED, Json(proposal)))
+}
+
+/// `GroupUpdateApproved`: records the approver against the pending proposal
+/// and takes the contract's counts as authoritative.
+#[tracing::instrument(skip_all, fields(group_address = %group_address, tx_hash = %payload.tx_hash))]
+pub async fn record_update_approval(
+    State(state): State<AppState>,
+    ValidatedPath(group_address): ValidatedPath<Address>,
+    ValidatedJson(payload): ValidatedJson<UpdateApprovedRequest>,
+) -> Result<Json<ProposalResponse>, ApiError> {
+    let group_address = group_address.to_string();
+
+    let mut tx = state.db.begin().await?;
+    lock_group(&mut tx, &group_address).await?;
+    let proposal_id = pending_proposal(&mut tx, &group_address).await?;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO group_update_approvals (proposal_id, approver, tx_hash)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (proposal_id, approver) DO NOTHING
+        "#,
+        proposal_id,
+        payload.approver.to_string(),
+        payload.tx_hash.to_string()
+    )
+    .execute(&mut *tx)
+    .instrument(query_span("insert_group_update_approval"))
+    .await?;
+
+    sqlx::query!(
+        r#"
+        UPDATE group_update_proposals SET approval_count = $2, total_members = $3
+        WHERE id = $1
+        "#,
+        proposal_id,
+        i32::from(payload.approval_count),
+        i32::from(payload.total_members)
+    )
+    .execute(&mut *tx)
+    .instrument(query_span("update_group_update_approvals"))
+    .await?;
+
+    tx.commit().await?;
+
+    proposal(&state.db, proposal_id).await.map(Json)
+}
+
+/// `GroupUpdated`: applies the pending proposal, renaming the group and
+/// opening a share set with the proposed members.
+#[tracing::instrument(skip_all, fields(group_address = %group_address, tx_hash = %payload.tx_hash))]
+pub async fn apply_group_update(
+    State(state): State<AppState>,
+    ValidatedPath(group_address): ValidatedPath<Address>,
+    ValidatedJson(payload): ValidatedJson<GroupUpdatedRequest>,
+) -> Result<Json<ProposalResponse>, ApiError> {
+    let group_address = group_address.to_string();
+
+    let mut tx = state.db.begin().await?;
+    lock_group(&mut tx, &group_address).await?;
+    let proposal_id = pending_proposal(&mut tx, &group_address).await?;
+
+    sqlx::query!(
+        "UPDATE groups SET group_name = $2 WHERE group_address = $1",
+        group_address,
+        payload.new_name
+    )
+    .execute(&mut *tx)
+    .instrument(query_span("update_group_name"))
+    .await?;
+
+    let members: Vec<(String, BigDecimal)> = sqlx::query!(
+        r#"
+        SELECT member_address, member_percentage
+        FROM group_update_proposal_members
+        WHERE proposal_id = $1
+        "#,
+        proposal_id
+    )
+    .fetch_all(&mut *tx)
+    .instrument(query_span("select_group_update_proposal_members"))
+    .await?
+    .into_iter()
+    .map(|row| (row.member_address, row.member_percentage))
+    .collect();
+
+    let (version, relinked) =
+        share_set::open(&mut tx, &group_address, &members, OffsetDateTime::now_utc()).await?;
+
+    for tx_hash in &relinked {
+        audit::audit_payment(&mut tx, tx_hash).await?;
+    }
+
+    sqlx::query!(
+        r#"
+        UPDATE group_update_proposals
+        SET status = 'completed', resolved_at = NOW(), update_tx_hash = $2,
+            share_set_id = (SELECT id FROM group_share_sets WHERE group_address = $3 AND version = $4)
+        WHERE id = $1
+        "#,
+        proposal_id,
+        payload.tx_hash.to_string(),
+        group_address,
+        version
+    )
+    .execute(&mut *tx)
+    .instrument(query_span("complete_group_update_proposal"))
+    .await?;
+
+    tx.commit().await?;
+
+    tracing::info!(
+        version,
+        "Group updated: {} renamed from {:?}",
+        group_address,
+        payload.old_name
+    );
+
+    proposal(&state.db, proposal_id).await.map(Json)
+}
+
+#[tracing::instrument(skip_all, fields(group_address = %group_address))]
+pub async fn get_group_proposals(
+    State(state): State<AppState>,
+    ValidatedPath(group_address): ValidatedPath<Address>,
+    ValidatedQuery(query): ValidatedQuery<ProposalListQuery>,
+) -> Result<Json<Page<ProposalResponse>>, ApiError> {
+    let group_address = group_address.to_string();
+    ensure_group_exists(&state.db, &group_address).await?;
+
+    let keyset = Keyset::new(
+        PROPOSAL_KEY,
+        query.sort,
+        query.order,
+        query.cursor.as_deref(),
+        query.limit.unwrap_or(DEFAULT_PAGE_LIMIT),
+    )?;
+
+    let mut qb = QueryBuilder::new(PROPOSAL_COLUMNS);
+    keyset.push_select(&mut qb);
+    qb.push(PROPOSAL_FROM);
+    qb.push(" WHERE p.group_address = ")
+        .push_bind(group_address);
+    if let Some(status) = query.status {
+        qb.push(" AND p.status = ").push_bind(status.as_str());
+    }
+    keyset.push_after(&mut qb);
+    keyset.push_order_limit(&mut qb);
+
+    let rows = qb
+        .build_query_as::<Keyed<ProposalResponse>>()
+        .fetch_all(&state.db)
+        .instrument(query_span("select_group_update_proposals"))
+        .await?;
+
+    let mut page = keyset.page(rows);
+    attach_details(&state.db, &mut page.items).await?;
+    Ok(Json(page))
+}
+
+async fn pending_proposal(conn: &mut PgConnection, group_address: &str) -> Result<Uuid, ApiError> {
+    sqlx::query_scalar!(
+        "SELECT id FROM group_update_proposals WHERE group_address = $1 AND status = 'pending'",
+        group_address
+    )
+    .fetch_optional(&mut *conn)
+    .instrument(query_span("select_pending_group_update_proposal"))
+    .await?
+    .ok_or_else(|| {
+        ApiError::new(
+            ErrorCode::ProposalNotFound,
+            "The group has no pending update proposal",
+        )
+    })
+}
+
+async fn proposal(db: &PgPool, id: Uuid) -> Result<ProposalResponse, ApiError> {
+    let mut qb = QueryBuilder::new(PROPOSAL_COLUMNS);
+    qb.push(PROPOSAL_FROM);
+    qb.push(" WHERE p.id = ").push_bind(id);
+
+    let proposal = qb
+        .build_query_as::<ProposalResponse>()
+        .fetch_one(db)
+        .instrument(query_span("select_group_update_proposal"))
+        .await?;
+
+    let mut proposals = [proposal];
+    attach_details(db, &mut proposals).await?;
+    let [proposal] = proposals;
+    Ok(proposal)
+}
+
+/// Fills in the proposed members and approvals of `proposals`.
+async fn attach_details(db: &PgPool, proposals: &mut [ProposalResponse]) -> Result<(), ApiError> {
+    let ids: Vec<Uuid> = proposals.iter().map(|proposal| proposal.id).collect();
+
+    let members = sqlx::query!(
+        r#"
+        SELECT proposal_id, member_address, member_percentage
+        FROM group_update_proposal_members
+        WHERE proposal_id = ANY($1)
+        ORDER BY proposal_id, member_percentage DESC, member_address
+        "#,
+        &ids
+    )
+    .fetch_all(db)
+    .instrument(query_span("select_proposal_members"))
+    .await?;
+
+    let approvals = sqlx::query!(
+        r#"
+        SELECT proposal_id, approver, tx_hash, approved_at::text as "approved_at!"
+        FROM group_update_approvals
+        WHERE proposal_id = ANY($1)
+        ORDER BY proposal_id, approved_at
+        "#,
+        &ids
+    )
+    .fetch_all(db)
+    .instrument(query_span("select_proposal_approvals"))
+    .await?;
+
+    let mut members_by_proposal: HashMap<Uuid, Vec<ShareSetMemberResponse>> = HashMap::new();
+    for row in members {
+        members_by_proposal
+            .entry(row.proposal_id)
+            .or_default()
+            .push(ShareSetMemberResponse {
+                member_address: row.member_address,
+                member_percentage: row.member_percentage,
+            });
+    }
+
+    let mut approvals_by_proposal: HashMap<Uuid, Vec<ProposalApprovalResponse>> = HashMap::new();
+    for row in approvals {
+        approvals_by_proposal
+            .entry(row.proposal_id)
+            .or_default()
+            .push(ProposalApprovalResponse {
+                approver: row.approver,
+                tx_hash: row.tx_hash,
+                approved_at: row.approved_at,
+            });
+    }
+
+    for proposal in proposals {
+        proposal.members = members_by_proposal.remove(&proposal.id).unwrap_or_default();
+        proposal.approvals = approvals_by_proposal
+            .remove(&proposal.id)
+            .unwrap_or_default();
+    }
+
+    Ok(())
+}
@@ -0,0 +1,203 @@
+use axum::{Json, extract::State, http::StatusCode, response::IntoResponse};
+use bigdecimal::BigDecimal;
+use sqlx::{PgPool, QueryBuilder};
+use tracing::Instrument;
+use uuid::Uuid;
+
+use crate::{
+    AppState,
+    libs::{
+        db::query_span,
+        error::{ApiError, ErrorCode},
+        extract::{ValidatedJson, ValidatedPath},
+    },
+    routes::{
+        group::ensure_group_exists,
+        types::{
+            RecurringExpectationDetailResponse, RecurringExpectationRequest,
+            RecurringExpectationResponse, RecurringOccurrenceResponse,
+        },
+    },
+    util::address::Address,
+};
+
+/// Counts and the next due date from today are derived, never stored.
+const EXPECTATION_SELECT: &str = "SELECT e.id, e.group_address, e.payer_address, \
+     e.token_address, e.min_amount, e.max_amount, e.frequency, e.interval_count, \
+     e.starts_on::text AS starts_on, e.ends_on::text AS ends_on, e.grace_days, e.description, \
+     CASE WHEN e.cancelled_at IS NOT NULL THEN 'cancelled' \
+     WHEN n.due_on IS NULL THEN 'ended' ELSE 'active' END AS status, \
+     n.due_on::text AS next_due_on, c.received_count, c.late_count, c.missed_count, \
+     e.created_at::text AS created_at, e.cancelled_at::text AS cancelled_at \
+     FROM recurring_expectations e \
+     LEFT JOIN LATERAL (SELECT d.due_on \
+         FROM generate_series(0, \
+             GREATEST(recurring_seq_bound(e.starts_on, e.frequency, e.interval_count, CURRENT_DATE), 0) + 1) AS s(seq) \
+         CROSS JOIN LATERAL recurring_due_on(e.starts_on, e.frequency, e.interval_count, s.seq) AS d(due_on) \
+         WHERE e.cancelled_at IS NULL AND d.due_on >= CURRENT_DATE \
+             AND (e.ends_on IS NULL OR d.due_on <= e.ends_on) \
+         ORDER BY d.due_on LIMIT 1) n ON TRUE \
+     CROSS JOIN LATERAL (SELECT \
+         COUNT(*) FILTER (WHERE o.status = 'received') AS received_count, \
+         COUNT(*) FILTER (WHERE o.status = 'late') AS late_count, \
+         COUNT(*) FILTER (WHERE o.status = 'missed') AS missed_count \
+         FROM recurring_occurrences o WHERE o.expectation_id = e.id) c";
+
+/// Declares a recurring inflow for the group. Occurrences are matched to
+/// transfers by the recurring payment check, see `libs::recurring`.
+#[tracing::instrument(skip_all, fields(group_address = %group_address))]
+pub async fn create_expectation(
+    State(state): State<AppState>,
+    ValidatedPath(group_address): ValidatedPath<Address>,
+    ValidatedJson(payload): ValidatedJson<RecurringExpectationRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    let group_address = group_address.to_string();
+    ensure_group_exists(&state.db, &group_address).await?;
+
+    let id = sqlx::query_scalar!(
+        r#"
+        INSERT INTO recurring_expectations
+            (group_address, payer_address, token_address, min_amount, max_amount, frequency,
+             interval_count, starts_on, ends_on, grace_days, description)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+        RETURNING id
+        "#,
+        group_address,
+        payload.payer_address.to_string(),
+        payload.token_address.to_string(),
+        BigDecimal::from(payload.min_amount),
+        BigDecimal::from(payload.max_amount),
+        payload.frequency.as_str(),
+        i32::from(payload.interval_count.unwrap_or(1)),
+        payload.starts_on,
+        payload.ends_on,
+        i32::from(payload.grace_days()),
+        payload.description
+    )
+    .fetch_one(&state.db)
+    .instrument(query_span("insert_recurring_expectation"))
+    .await?;
+
+    tracing::info!("Recurring payment {id} declared for {group_address}");
+
+    let expectation = expectation_detail(&state.db, id).await?;
+    Ok((StatusCode::CREATED, Json(expectation)))
+}
+
+#[tracing::instrument(skip_all, fields(group_address = %group_address))]
+pub async fn get_group_expectations(
+    State(state): State<AppState>,
+    ValidatedPath(group_address): ValidatedPath<Address>,
+) -> Result<Json<Vec<RecurringExpectationResponse>>, ApiError> {
+    let group_address = group_address.to_string();
+    ensure_group_exists(&state.db, &group_address).await?;
+
+    let mut qb = QueryBuilder::new(EXPECTATION_SELECT);
+    qb.push(" WHERE e.group_address = ")
+        .push_bind(group_address)
+        .push(" ORDER BY e.created_at, e.id");
+
+    let expectations = qb
+        .build_query_as::<RecurringExpectationResponse>()
+        .fetch_all(&state.db)
+        .instrument(query_span("select_recurring_expectations"))
+        .await?;
+
+    Ok(Json(expectations))
+}
+
+#[tracing::instrument(skip_all, fields(expectation_id = %id))]
+pub async fn get_expectation(
+    State(state): State<AppState>,
+    ValidatedPath(id): ValidatedPath<Uuid>,
+) -> Result<Json<RecurringExpectationDetailResponse>, ApiError> {
+    expectation_detail(&state.db, id).await.map(Json)
+}
+
+/// Stops expecting further payments. Occurrences already received, late or
+/// missed stay recorded; pending ones are dropped.
+#[tracing::instrument(skip_all, fields(expectation_id = %id))]
+pub async fn cancel_expectation(
+    State(state): State<AppState>,
+    ValidatedPath(id): ValidatedPath<Uuid>,
+) -> Result<Json<RecurringExpectationDetailResponse>, ApiError> {
+    let mut tx = state.db.begin().await?;
+
+    let cancelled_at = sqlx::query_scalar!(
+        "SELECT cancelled_at FROM recurring_expectations WHERE id = $1 FOR UPDATE",
+        id
+    )
+    .fetch_optional(&mut *tx)
+    .instrument(query_span("lock_recurring_expectation"))
+    .await?
+    .ok_or_else(expectation_not_found)?;
+
+    if cancelled_at.is_some() {
+        return Err(ApiError::new(
+            ErrorCode::Conflict,
+            "Recurring payment is already cancelled",
+        ));
+    }
+
+    sqlx::query!(
+        "UPDATE recurring_expectations SET cancelled_at = NOW() WHERE id = $1",
+        id
+    )
+    .execute(&mut *tx)
+    .instrument(query_span("cancel_recurring_expectation"))
+    .await?;
+
+    sqlx::query!(
+        "DELETE FROM recurring_occurrences WHERE expectation_id = $1 AND status = 'pending'",
+        id
+    )
+    .execute(&mut *tx)
+    .instrument(query_span("delete_pending_recurring_occurrences"))
+    .await?;
+
+    tx.commit().await?;
+
+    expectation_detail(&state.db, id).await.map(Json)
+}
+
+async fn expectation_detail(
+    db: &PgPool,
+    id: Uuid,
+) -> Result<RecurringExpectationDetailResponse, ApiError> {
+    let mut qb = QueryBuilder::new(EXPECTATION_SELECT);
+    qb.push(" WHERE e.id = ").push_bind(id);
+
+    let expectation = qb
+        .build_query_as::<RecurringExpectationResponse>()
+        .fetch_optional(db)
+        .instrument(query_span("select_recurring_expectation"))
+        .await?
+        .ok_or_else(expectation_not_found)?;
+
+    let occurrences = sqlx::query_as!(
+        RecurringOccurrenceResponse,
+        r#"
+        SELECT due_on::text as "due_on!", status, tx_hash, amount,
+            received_at::text as received_at, missed_at::text as missed_at
+        FROM recurring_occurrences
+        WHERE expectation_id = $1
+        ORDER BY seq DESC
+        "#,
+        id
+    )
+    .fetch_all(db)
+    .instrument(query_span("select_recurring_occurrences"))
+    .await?;
+
+    Ok(RecurringExpectationDetailResponse {
+        expectation,
+        occurrences,
+    })
+}
+
+fn expectation_not_found() -> ApiError {
+    ApiError::new(
+        ErrorCode::RecurringPaymentNotFound,
+        "Recurring payment not found",
+    )
+}
@@ -0,0 +1,224 @@
+use std::collections::HashMap;
+
+use axum::{Json, extract::State, http::StatusCode, response::IntoResponse};
+use sqlx::{PgPool, types::BigDecimal};
+use time::OffsetDateTime;
+use tracing::Instrument;
+use uuid::Uuid;
+
+use crate::{
+    AppState,
+    libs::{
+        audit,
+        db::query_span,
+        error::{ApiError, ErrorCode},
+        extract::{ValidatedJson, ValidatedPath, ValidatedQuery},
+        share_set,
+    },
+    routes::{
+        group::{ensure_group_exists, lock_group},
+        types::{CompositionQuery, ShareSetMemberResponse, ShareSetRequest, ShareSetResponse},
+    },
+    util::address::Address,
+};
+
+/// Every version of a group's member shares, oldest first.
+#[tracing::instrument(skip_all, fields(group_address = %group_address))]
+pub async fn get_share_sets(
+    State(state): State<AppState>,
+    ValidatedPath(group_address): ValidatedPath<Address>,
+) -> Result<Json<Vec<ShareSetResponse>>, ApiError> {
+    let group_address = group_address.to_string();
+    ensure_group_exists(&state.db, &group_address).await?;
+
+    let sets = sqlx::query!(
+        r#"
+        SELECT id, version, valid_from::text as "valid_from!", valid_to::text
+        FROM group_share_sets
+        WHERE group_address = $1
+        ORDER BY version
+        "#,
+        group_address
+    )
+    .fetch_all(&state.db)
+    .instrument(query_span("select_share_sets"))
+    .await?;
+
+    let ids: Vec<Uuid> = sets.iter().map(|set| set.id).collect();
+    let mut members = share_set_members(&state.db, &ids).await?;
+
+    let response = sets
+        .into_iter()
+        .map(|set| ShareSetResponse {
+            version: set.version,
+            valid_from: set.valid_from,
+            valid_to: set.valid_to,
+            members: members.remove(&set.id).unwrap_or_default(),
+        })
+        .collect();
+
+    Ok(Json(response))
+}
+
+/// The share set in effect at `as_of`.
+#[tracing::instrument(skip_all, fields(group_address = %group_address))]
+pub async fn get_composition(
+    State(state): State<AppState>,
+    ValidatedPath(group_address): ValidatedPath<Address>,
+    ValidatedQuery(query): ValidatedQuery<CompositionQuery>,
+) -> Result<Json<ShareSetResponse>, ApiError> {
+    let group_address = group_address.to_string();
+    ensure_group_exists(&state.db, &group_address).await?;
+    let as_of = query.as_of.unwrap_or_else(OffsetDateTime::now_utc);
+
+    let set = sqlx::query!(
+        r#"
+        SELECT id, version, valid_from::text as "valid_from!", valid_to::text
+        FROM group_share_sets
+        WHERE group_address = $1 AND valid_from <= $2 AND (valid_to IS NULL OR valid_to > $2)
+        "#,
+        group_address,
+        as_of
+    )
+    .fetch_optional(&state.db)
+    .instrument(query_span("select_share_set_as_of"))
+    .await?
+    .ok_or_else(|| {
+        ApiError::new(
+            ErrorCode::ShareSetNotFound,
+            "The group had no share set at that time",
+        )
+    })?;
+
+    let members = share_set_members(&state.db, &[set.id])
+        .await?
+        .remove(&set.id)
+        .unwrap_or_default();
+
+    Ok(Json(ShareSetResponse {
+        version: set.version,
+        valid_from: set.valid_from,
+        valid_to: set.valid_to,
+        members,
+    }))
+}
+
+/// Records a member update, e.g. one approved through the contract's
+/// `request_group_update`. Payments made since `valid_from` move to the new
+/// version and are re-audited against it.
+#[tracing::instrument(skip_all, fields(group_address = %group_address))]
+pub async fn create_share_set(
+    State(state): State<AppState>,
+    ValidatedPath(group_address): ValidatedPath<Address>,
+    ValidatedJson(payload): ValidatedJson<ShareSetRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    let group_address = group_address.to_string();
+    let valid_from = payload.valid_from.unwrap_or_else(OffsetDateTime::now_utc);
+
+    let mut tx = state.db.begin().await?;
+
+    lock_group(&mut tx, &group_address).await?;
+
+    let current = sqlx::query_scalar!(
+        "SELECT valid_from FROM group_share_sets WHERE group_address = $1 AND valid_to IS NULL",
+        group_address
+    )
+    .fetch_optional(&mut *tx)
+    .instrument(query_span("select_current_share_set"))
+    .await?;
+
+    if current.is_some_and(|current| valid_from <= current) {
+        return Err(ApiError::new(
+            ErrorCode::Conflict,
+            "`valid_from` must be after the start of the current share set",
+        ));
+    }
+
+    if let Some(group_name) = &payload.group_name {
+        sqlx::query!(
+            "UPDATE groups SET group_name = $2 WHERE group_address = $1",
+            group_address,
+            group_name
+        )
+        .execute(&mut *tx)
+        .instrument(query_span("update_group_name"))
+        .await?;
+    }
+
+    let members: Vec<(String, BigDecimal)> = payload
+        .members
+        .iter()
+        .map(|member| (member.addr.to_string(), member.percentage.into()))
+        .collect();
+    let (version, relinked) =
+        share_set::open(&mut tx, &group_address, &members, valid_from).await?;
+
+    for tx_hash in &relinked {
+        audit::audit_payment(&mut tx, tx_hash).await?;
+    }
+
+    tx.commit().await?;
+
+    tracing::info!(
+        version,
+        relinked = relinked.len(),
+        "Share set recorded for {}",
+        group_address
+    );
+
+    let set = sqlx::query!(
+        r#"
+        SELECT id, valid_from::text as "valid_from!", valid_to::text
+        FROM group_share_sets
+        WHERE group_address = $1 AND version = $2
+        "#,
+        group_address,
+        version
+    )
+    .fetch_one(&state.db)
+    .instrument(query_span("select_share_set"))
+    .await?;
+
+    let response = ShareSetResponse {
+        version,
+        valid_from: set.valid_from,
+        valid_to: set.valid_to,
+        members: share_set_members(&state.db, &[set.id])
+            .await?
+            .remove(&set.id)
+            .unwrap_or_default(),
+    };
+
+    Ok((StatusCode::CREATED, Json(response)))
+}
+
+async fn share_set_members(
+    db: &PgPool,
+    ids: &[Uuid],
+) -> Result<HashMap<Uuid, Vec<ShareSetMemberResponse>>, ApiError> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT share_set_id, member_address, member_percentage
+        FROM group_share_set_members
+        WHERE share_set_id = ANY($1)
+        ORDER BY share_set_id, member_percentage DESC, member_address
+        "#,
+        ids
+    )
+    .fetch_all(db)
+    .instrument(query_span("select_share_set_members"))
+    .await?;
+
+    let mut members: HashMap<Uuid, Vec<ShareSetMemberResponse>> = HashMap::new();
+    for row in rows {
+        members
+            .entry(row.share_set_id)
+            .or_default()
+            .push(ShareSetMemberResponse {
+                member_address: row.member_address,
+                member_percentage: row.member_percentage,
+            });
+    }
+
+    Ok(members)
+}
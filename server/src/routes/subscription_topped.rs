@@ -1,43 +1,69 @@
 use crate::{
-    AppState, libs::error::ApiError, routes::types::SubscriptionToppedReq,
+    AppState,
+    libs::{
+        error::{ApiError, ErrorCode},
+        extract::ValidatedJson,
+        forecast,
+        middleware::RequestId,
+        usage::{self, ToppedUp},
+    },
+    routes::types::SubscriptionToppedReq,
     util::starknet::call_paymesh_contract_function,
 };
 use axum::{Json, extract::State, http::StatusCode, response::IntoResponse};
 use bigdecimal::BigDecimal;
-use starknet::core::types::Felt;
+use tracing::field::Empty;
 
+#[tracing::instrument(
+    skip_all,
+    fields(
+        group_address = %payload.group_address,
+        request_id = %request_id.0,
+        relayer_tx_hash = Empty,
+    )
+)]
 pub async fn subscription_topped(
     State(state): State<AppState>,
-    Json(payload): Json<SubscriptionToppedReq>,
+    request_id: RequestId,
+    ValidatedJson(payload): ValidatedJson<SubscriptionToppedReq>,
 ) -> Result<impl IntoResponse, ApiError> {
-    let group_address = payload.group_address;
+    let group_address = payload.group_address.to_string();
     let usage_count = BigDecimal::from(payload.usage_count);
 
-    sqlx::query!(
-        r#"UPDATE groups 
-        SET usage_remaining = $1 
-        WHERE group_address = $2"#,
+    let mut tx = state.db.begin().await?;
+    let bought = match usage::top_up(
+        &mut tx,
+        &group_address,
         usage_count,
-        group_address
+        payload
+            .tx_hash
+            .map(|tx_hash| tx_hash.to_string())
+            .as_deref(),
     )
-    .fetch_optional(&state.db)
-    .await
-    .map_err(|e| {
-        tracing::error!(
-            "Database error when updating group usage remaining {}",
-            e.to_string()
-        );
-        ApiError::Internal("Database Error Occured")
-    })?;
-
-    tracing::info!("Group topped up {}", group_address);
+    .await?
+    {
+        ToppedUp::Booked(bought) => bought,
+        ToppedUp::GroupNotFound => return Err(ApiError::group_not_found()),
+        ToppedUp::AlreadyBooked => {
+            tracing::info!("Top-up already recorded for {}", group_address);
+            return Ok((StatusCode::OK, Json("USAGE COUNT UPDATED SUCCESSFULLY")));
+        }
+    };
+    forecast::check_thresholds(&mut tx, &group_address).await?;
+    tx.commit().await?;
 
-    let address = Felt::from_hex(group_address.as_str())
-        .map_err(|_| ApiError::BadRequest("TOKEN ADDRESS NOT VALID"))?;
+    tracing::info!(%bought, "Group topped up {}", group_address);
 
-    call_paymesh_contract_function(address)
+    let relayer_tx_hash = call_paymesh_contract_function(payload.group_address, &request_id.0)
         .await
-        .map_err(|_| ApiError::BadRequest("Failed to call paymesh contract"))?;
+        .map_err(|e| {
+            ApiError::new(
+                ErrorCode::ContractCallFailed,
+                "Failed to call paymesh contract",
+            )
+            .with_cause(e)
+        })?;
+    tracing::Span::current().record("relayer_tx_hash", relayer_tx_hash.to_string());
 
     Ok((StatusCode::OK, Json("USAGE COUNT UPDATED SUCCESSFULLY")))
 }
@@ -1,31 +1,708 @@
+use std::collections::HashSet;
+
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
+use time::{Date, Duration, OffsetDateTime, macros::format_description};
 use validator::{Validate, ValidationError};
 
+use crate::{
+    libs::{
+        pagination::{SortField, SortOrder},
+        price::Currency,
+    },
+    util::{
+        address::{Address, TxHash},
+        amount::TokenAmount,
+        ledger::LedgerFormat,
+        token,
+    },
+};
+
+/// The contract rejects groups with fewer than two members; shares are whole
+/// percentages of at least 1, so a group can never exceed 100 members.
+pub const MIN_GROUP_MEMBERS: usize = 2;
+pub const MAX_GROUP_MEMBERS: usize = 100;
+
+pub const DEFAULT_PAGE_LIMIT: i64 = 100;
+pub const MAX_PAGE_LIMIT: i64 = 500;
+
+time::serde::format_description!(iso_date, Date, "[year]-[month]-[day]");
+
+/// Upper bound on buckets per series, so an hourly query over years can't
+/// build an unbounded result.
+pub const MAX_ANALYTICS_BUCKETS: i64 = 1000;
+
+/// Low-usage thresholds are usage counts, kept well inside `INTEGER`.
+pub const MAX_USAGE_THRESHOLD: u32 = 100_000;
+
 #[derive(Debug, Deserialize, Validate)]
+#[validate(schema(function = "validate_group_members", skip_on_field_errors = false))]
 pub struct GroupRequest {
-    #[validate(custom(function = "validate_address"))]
-    pub group_address: String,
-    #[validate(length(min = 2, max = 100))]
+    pub group_address: Address,
+    /// The contract's id from `GroupCreated`, used to read its usage views.
+    pub group_id: Option<u32>,
+    #[validate(length(min = 2, max = 100, message = "must be between 2 and 100 characters"))]
     pub group_name: String,
-    #[validate(custom(function = "validate_address"))]
-    pub created_by: String,
+    pub created_by: Address,
     pub usage_remaining: u32,
+    #[validate(nested)]
     pub members: Vec<GroupMembersRequest>,
 }
 
 #[derive(Debug, Deserialize, Validate)]
 pub struct GroupMembersRequest {
-    #[validate(custom(function = "validate_address"))]
-    pub addr: String,
-    #[validate(range(min = 1, max = 100))]
+    pub addr: Address,
+    #[validate(range(min = 1, max = 100, message = "must be between 1 and 100"))]
     pub percentage: u8,
 }
 
+/// A new version of a group's member shares, effective from `valid_from`
+/// (default now). `group_name` optionally renames the group with it.
 #[derive(Debug, Deserialize, Validate)]
-pub struct GetGroupDetailsRequest {
-    #[validate(custom(function = "validate_address"))]
+#[validate(schema(function = "validate_share_set", skip_on_field_errors = false))]
+pub struct ShareSetRequest {
+    #[validate(length(min = 2, max = 100, message = "must be between 2 and 100 characters"))]
+    pub group_name: Option<String>,
+    #[validate(nested)]
+    pub members: Vec<GroupMembersRequest>,
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub valid_from: Option<OffsetDateTime>,
+}
+
+/// `as_of` defaults to now.
+#[derive(Debug, Deserialize, Validate)]
+pub struct CompositionQuery {
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub as_of: Option<OffsetDateTime>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ShareSetResponse {
+    pub version: i32,
+    pub valid_from: String,
+    pub valid_to: Option<String>,
+    pub members: Vec<ShareSetMemberResponse>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ShareSetMemberResponse {
+    pub member_address: String,
+    pub member_percentage: bigdecimal::BigDecimal,
+}
+
+/// `GroupUpdateRequested`, with the proposed members read from the contract.
+#[derive(Debug, Deserialize, Validate)]
+#[validate(schema(function = "validate_update_request", skip_on_field_errors = false))]
+pub struct UpdateRequestedRequest {
+    pub tx_hash: TxHash,
+    pub requester: Address,
+    #[validate(length(min = 2, max = 100, message = "must be between 2 and 100 characters"))]
+    pub new_name: String,
+    #[validate(nested)]
+    pub members: Vec<GroupMembersRequest>,
+}
+
+/// `GroupUpdateApproved`.
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdateApprovedRequest {
+    pub tx_hash: TxHash,
+    pub approver: Address,
+    pub approval_count: u8,
+    pub total_members: u8,
+}
+
+/// `GroupUpdated`.
+#[derive(Debug, Deserialize, Validate)]
+pub struct GroupUpdatedRequest {
+    pub tx_hash: TxHash,
+    pub old_name: String,
+    #[validate(length(min = 2, max = 100, message = "must be between 2 and 100 characters"))]
+    pub new_name: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProposalStatus {
+    Pending,
+    Completed,
+    Superseded,
+}
+
+impl ProposalStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ProposalStatus::Pending => "pending",
+            ProposalStatus::Completed => "completed",
+            ProposalStatus::Superseded => "superseded",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProposalSort {
+    #[default]
+    RequestedAt,
+}
+
+impl SortField for ProposalSort {
+    fn column(self) -> &'static str {
+        match self {
+            ProposalSort::RequestedAt => "p.requested_at",
+        }
+    }
+
+    fn sql_type(self) -> &'static str {
+        match self {
+            ProposalSort::RequestedAt => "timestamptz",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct ProposalListQuery {
+    pub status: Option<ProposalStatus>,
+    pub sort: Option<ProposalSort>,
+    pub order: Option<SortOrder>,
+    pub cursor: Option<String>,
+    #[validate(range(min = 1, max = MAX_PAGE_LIMIT, message = "must be between 1 and 500"))]
+    pub limit: Option<i64>,
+}
+
+/// A group update proposal with its members and the approvals so far.
+/// `share_set_version` is set once the update has been applied.
+#[derive(Debug, Serialize, FromRow)]
+pub struct ProposalResponse {
+    pub id: uuid::Uuid,
+    pub group_address: String,
+    pub request_tx_hash: String,
+    pub requester: String,
+    pub new_name: String,
+    pub status: String,
+    pub approval_count: i32,
+    pub total_members: i32,
+    pub requested_at: String,
+    pub resolved_at: Option<String>,
+    pub update_tx_hash: Option<String>,
+    pub share_set_version: Option<i32>,
+    #[sqlx(skip)]
+    pub members: Vec<ShareSetMemberResponse>,
+    #[sqlx(skip)]
+    pub approvals: Vec<ProposalApprovalResponse>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProposalApprovalResponse {
+    pub approver: String,
+    pub tx_hash: String,
+    pub approved_at: String,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct SplitPreviewRequest {
+    #[validate(custom(function = "validate_supported_token"))]
+    pub token_address: Address,
+    #[validate(custom(function = "validate_token_amount"))]
+    pub amount: TokenAmount,
+}
+
+/// What paying `amount` into the group would distribute, in base units.
+/// `remainder` is the dust flooring leaves in the group's contract.
+#[derive(Debug, Serialize)]
+pub struct SplitPreviewResponse {
+    pub group_address: String,
+    pub token_address: String,
+    pub symbol: &'static str,
+    pub amount: TokenAmount,
+    /// What the group already holds of the token; the payout splits it too.
+    pub group_balance: TokenAmount,
+    pub members: Vec<SplitShareResponse>,
+    pub distributed: TokenAmount,
+    pub remainder: TokenAmount,
+    pub usage: UsageImpact,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SplitShareResponse {
+    pub member_address: String,
+    pub percentage: u8,
+    pub amount: TokenAmount,
+    pub formatted_amount: String,
+}
+
+/// Each payout uses one prepaid usage, worth `usage_fee` STRK base units.
+#[derive(Debug, Serialize)]
+pub struct UsageImpact {
+    pub usage_remaining: bigdecimal::BigDecimal,
+    pub usage_remaining_after: bigdecimal::BigDecimal,
+    pub usage_fee: TokenAmount,
+}
+
+/// A bill payable into a group. Without `payer_address` anyone may pay it.
+#[derive(Debug, Deserialize, Validate)]
+pub struct InvoiceRequest {
+    pub group_address: Address,
+    #[validate(custom(function = "validate_supported_token"))]
+    pub token_address: Address,
+    #[validate(custom(function = "validate_token_amount"))]
+    pub amount: TokenAmount,
+    #[serde(with = "time::serde::rfc3339")]
+    pub due_at: OffsetDateTime,
+    #[validate(length(min = 1, max = 140, message = "must be between 1 and 140 characters"))]
+    pub reference: String,
+    pub payer_address: Option<Address>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InvoiceStatus {
+    Open,
+    PartiallyPaid,
+    Paid,
+    Overdue,
+    Cancelled,
+}
+
+impl InvoiceStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            InvoiceStatus::Open => "open",
+            InvoiceStatus::PartiallyPaid => "partially_paid",
+            InvoiceStatus::Paid => "paid",
+            InvoiceStatus::Overdue => "overdue",
+            InvoiceStatus::Cancelled => "cancelled",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InvoiceSort {
+    #[default]
+    DueAt,
+    CreatedAt,
+}
+
+impl SortField for InvoiceSort {
+    fn column(self) -> &'static str {
+        match self {
+            InvoiceSort::DueAt => "i.due_at",
+            InvoiceSort::CreatedAt => "i.created_at",
+        }
+    }
+
+    fn sql_type(self) -> &'static str {
+        match self {
+            InvoiceSort::DueAt | InvoiceSort::CreatedAt => "timestamptz",
+        }
+    }
+}
+
+/// Invoice filters; `from`/`to` bound `due_at`.
+#[derive(Debug, Deserialize, Validate)]
+#[validate(schema(function = "validate_invoice_list_range", skip_on_field_errors = false))]
+pub struct InvoiceListQuery {
+    pub group_address: Option<Address>,
+    pub payer: Option<Address>,
+    pub token_address: Option<Address>,
+    pub status: Option<InvoiceStatus>,
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub from: Option<OffsetDateTime>,
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub to: Option<OffsetDateTime>,
+    pub sort: Option<InvoiceSort>,
+    pub order: Option<SortOrder>,
+    pub cursor: Option<String>,
+    #[validate(range(min = 1, max = MAX_PAGE_LIMIT, message = "must be between 1 and 500"))]
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct InvoiceResponse {
+    pub id: String,
+    pub group_address: String,
+    pub token_address: String,
+    pub amount: bigdecimal::BigDecimal,
+    pub paid_amount: bigdecimal::BigDecimal,
+    pub outstanding: bigdecimal::BigDecimal,
+    pub due_at: String,
+    pub reference: String,
+    pub payer_address: Option<String>,
+    pub status: String,
+    pub created_at: String,
+    pub paid_at: Option<String>,
+    pub cancelled_at: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct InvoiceDetailResponse {
+    #[serde(flatten)]
+    pub invoice: InvoiceResponse,
+    pub payments: Vec<InvoicePaymentResponse>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct InvoicePaymentResponse {
+    pub tx_hash: String,
+    pub from_address: String,
+    pub amount: bigdecimal::BigDecimal,
+    pub matched_by: String,
+    pub matched_at: String,
+}
+
+/// `scale` is pixels per QR module, used when an image is requested.
+#[derive(Debug, Deserialize, Validate)]
+pub struct PaymentUriQuery {
+    #[validate(custom(function = "validate_supported_token"))]
+    pub token_address: Address,
+    #[validate(custom(function = "validate_token_amount"))]
+    pub amount: Option<TokenAmount>,
+    #[validate(range(min = 1, max = 16, message = "must be between 1 and 16"))]
+    pub scale: Option<u32>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct QrQuery {
+    #[validate(range(min = 1, max = 16, message = "must be between 1 and 16"))]
+    pub scale: Option<u32>,
+}
+
+/// A transfer request wallets can open. Without `amount` the payer chooses.
+#[derive(Debug, Serialize)]
+pub struct PaymentUriResponse {
+    pub uri: String,
+    pub group_address: String,
+    pub token_address: String,
+    pub symbol: Option<&'static str>,
+    pub amount: Option<TokenAmount>,
+    pub formatted_amount: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub invoice_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RecurringFrequency {
+    Daily,
+    Weekly,
+    Monthly,
+}
+
+impl RecurringFrequency {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RecurringFrequency::Daily => "daily",
+            RecurringFrequency::Weekly => "weekly",
+            RecurringFrequency::Monthly => "monthly",
+        }
+    }
+
+    /// Shortest period in days; months count as 28.
+    fn min_days(self) -> u32 {
+        match self {
+            RecurringFrequency::Daily => 1,
+            RecurringFrequency::Weekly => 7,
+            RecurringFrequency::Monthly => 28,
+        }
+    }
+}
+
+/// A transfer a group expects from `payer_address` every `interval_count`
+/// days, weeks or months from `starts_on`, for an amount between
+/// `min_amount` and `max_amount`. It counts as received up to `grace_days`
+/// either side of the due date (see [`Self::grace_days`] for the default).
+#[derive(Debug, Deserialize, Validate)]
+#[validate(schema(
+    function = "validate_recurring_expectation",
+    skip_on_field_errors = false
+))]
+pub struct RecurringExpectationRequest {
+    pub payer_address: Address,
+    #[validate(custom(function = "validate_supported_token"))]
+    pub token_address: Address,
+    #[validate(custom(function = "validate_token_amount"))]
+    pub min_amount: TokenAmount,
+    #[validate(custom(function = "validate_token_amount"))]
+    pub max_amount: TokenAmount,
+    pub frequency: RecurringFrequency,
+    #[validate(range(min = 1, max = 365, message = "must be between 1 and 365"))]
+    pub interval_count: Option<u16>,
+    #[serde(with = "iso_date")]
+    pub starts_on: Date,
+    #[serde(default, with = "iso_date::option")]
+    pub ends_on: Option<Date>,
+    pub grace_days: Option<u16>,
+    #[validate(length(min = 1, max = 140, message = "must be between 1 and 140 characters"))]
+    pub description: Option<String>,
+}
+
+impl RecurringExpectationRequest {
+    /// Shortest interval between due dates, in days.
+    fn period_days(&self) -> u32 {
+        self.frequency.min_days() * u32::from(self.interval_count.unwrap_or(1))
+    }
+
+    /// The grace period sent, or 3 days capped under half the interval so
+    /// short intervals get one too.
+    pub fn grace_days(&self) -> u16 {
+        self.grace_days.unwrap_or_else(|| {
+            u16::try_from(self.period_days().saturating_sub(1) / 2).map_or(3, |half| half.min(3))
+        })
+    }
+}
+
+/// `status` is `active`, `ended` (past `ends_on`) or `cancelled`.
+/// `next_due_on` is the next due date from today, if any.
+#[derive(Debug, Serialize, FromRow)]
+pub struct RecurringExpectationResponse {
+    pub id: uuid::Uuid,
+    pub group_address: String,
+    pub payer_address: String,
+    pub token_address: String,
+    pub min_amount: bigdecimal::BigDecimal,
+    pub max_amount: bigdecimal::BigDecimal,
+    pub frequency: String,
+    pub interval_count: i32,
+    pub starts_on: String,
+    pub ends_on: Option<String>,
+    pub grace_days: i32,
+    pub description: Option<String>,
+    pub status: String,
+    pub next_due_on: Option<String>,
+    pub received_count: i64,
+    pub late_count: i64,
+    pub missed_count: i64,
+    pub created_at: String,
+    pub cancelled_at: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RecurringExpectationDetailResponse {
+    #[serde(flatten)]
+    pub expectation: RecurringExpectationResponse,
+    pub occurrences: Vec<RecurringOccurrenceResponse>,
+}
+
+/// `status` is `pending`, `received`, `late` or `missed`.
+#[derive(Debug, Serialize)]
+pub struct RecurringOccurrenceResponse {
+    pub due_on: String,
+    pub status: String,
+    pub tx_hash: Option<String>,
+    pub amount: Option<bigdecimal::BigDecimal>,
+    pub received_at: Option<String>,
+    pub missed_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationSort {
+    #[default]
+    CreatedAt,
+}
+
+impl SortField for NotificationSort {
+    fn column(self) -> &'static str {
+        match self {
+            NotificationSort::CreatedAt => "n.created_at",
+        }
+    }
+
+    fn sql_type(self) -> &'static str {
+        match self {
+            NotificationSort::CreatedAt => "timestamptz",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct NotificationListQuery {
+    pub group_address: Option<Address>,
+    pub kind: Option<String>,
+    pub unread: Option<bool>,
+    pub sort: Option<NotificationSort>,
+    pub order: Option<SortOrder>,
+    pub cursor: Option<String>,
+    #[validate(range(min = 1, max = MAX_PAGE_LIMIT, message = "must be between 1 and 500"))]
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UsageEntryKind {
+    Opening,
+    Purchase,
+    TopUp,
+    Consumption,
+    Adjustment,
+}
+
+impl UsageEntryKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            UsageEntryKind::Opening => "opening",
+            UsageEntryKind::Purchase => "purchase",
+            UsageEntryKind::TopUp => "top_up",
+            UsageEntryKind::Consumption => "consumption",
+            UsageEntryKind::Adjustment => "adjustment",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UsageLedgerSort {
+    #[default]
+    RecordedAt,
+}
+
+impl SortField for UsageLedgerSort {
+    fn column(self) -> &'static str {
+        match self {
+            UsageLedgerSort::RecordedAt => "l.recorded_at",
+        }
+    }
+
+    fn sql_type(self) -> &'static str {
+        match self {
+            UsageLedgerSort::RecordedAt => "timestamptz",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Validate)]
+#[validate(schema(function = "validate_usage_ledger_range", skip_on_field_errors = false))]
+pub struct UsageLedgerQuery {
+    pub kind: Option<UsageEntryKind>,
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub from: Option<OffsetDateTime>,
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub to: Option<OffsetDateTime>,
+    pub sort: Option<UsageLedgerSort>,
+    pub order: Option<SortOrder>,
+    pub cursor: Option<String>,
+    #[validate(range(min = 1, max = MAX_PAGE_LIMIT, message = "must be between 1 and 500"))]
+    pub limit: Option<i64>,
+}
+
+/// `delta` is signed; `usage_after` is the group's usage remaining once the
+/// entry was booked.
+#[derive(Debug, Serialize, FromRow)]
+pub struct UsageLedgerEntryResponse {
+    pub id: i64,
+    pub kind: String,
+    pub delta: bigdecimal::BigDecimal,
+    pub usage_after: bigdecimal::BigDecimal,
+    pub tx_hash: Option<String>,
+    pub reason: Option<String>,
+    pub recorded_by: Option<String>,
+    pub recorded_at: String,
+}
+
+/// A manual correction of a group's usage by `delta`.
+#[derive(Debug, Deserialize, Validate)]
+pub struct UsageAdjustmentRequest {
+    #[validate(custom(function = "validate_usage_delta"))]
+    pub delta: i32,
+    #[validate(length(min = 1, max = 140, message = "must be between 1 and 140 characters"))]
+    pub reason: String,
+}
+
+/// `onchain` (default true) compares against the contract as well.
+#[derive(Debug, Deserialize, Validate)]
+pub struct UsageReconciliationQuery {
+    pub onchain: Option<bool>,
+}
+
+/// The ledger checked against `usage_remaining` and, when `onchain`, the
+/// contract's usage count and paid history. `purchases` are the usage
+/// bought at creation and by each top-up, oldest first; for groups older
+/// than the ledger only those since its opening balance.
+#[derive(Debug, Serialize)]
+pub struct UsageReconciliationResponse {
+    pub group_address: String,
+    pub group_id: Option<i64>,
+    pub consistent: bool,
+    pub usage_remaining: bigdecimal::BigDecimal,
+    pub ledger_balance: bigdecimal::BigDecimal,
+    pub ledger_purchases: Vec<bigdecimal::BigDecimal>,
+    pub contract_usage_count: Option<bigdecimal::BigDecimal>,
+    pub contract_purchases: Option<Vec<bigdecimal::BigDecimal>>,
+    pub discrepancies: Vec<UsageDiscrepancy>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UsageDiscrepancy {
+    pub kind: &'static str,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub entry_id: Option<i64>,
+}
+
+/// `window_days` (default 30) is how far back payouts count towards the
+/// burn rate.
+#[derive(Debug, Deserialize, Validate)]
+pub struct UsageForecastQuery {
+    #[validate(range(min = 1, max = 365, message = "must be between 1 and 365"))]
+    pub window_days: Option<i32>,
+}
+
+/// `status` is `exhausted`, `low` (at or below a threshold), `idle` (no
+/// payouts in the window) or `ok`.
+#[derive(Debug, Serialize)]
+pub struct UsageForecastResponse {
+    pub group_address: String,
+    pub status: &'static str,
+    pub usage_remaining: bigdecimal::BigDecimal,
+    pub window_days: i32,
+    pub payouts_in_window: i64,
+    pub burn_rate_per_day: bigdecimal::BigDecimal,
+    pub last_payout_at: Option<String>,
+    pub days_remaining: Option<bigdecimal::BigDecimal>,
+    pub projected_exhaustion_at: Option<String>,
+    pub thresholds: Vec<i32>,
+}
+
+/// Replaces the group's low-usage thresholds; `null` goes back to the
+/// server's defaults and `[]` turns the alerts off.
+#[derive(Debug, Deserialize, Validate)]
+pub struct UsageAlertSettingsRequest {
+    #[validate(
+        length(max = 10, message = "must have at most 10 thresholds"),
+        custom(function = "validate_usage_thresholds")
+    )]
+    pub thresholds: Option<Vec<u32>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UsageAlertSettingsResponse {
     pub group_address: String,
+    pub thresholds: Vec<i32>,
+    pub uses_defaults: bool,
+    /// Thresholds already warned about, until usage rises above them again.
+    pub alerted: Vec<UsageAlertResponse>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UsageAlertResponse {
+    pub threshold: i32,
+    pub usage_remaining: bigdecimal::BigDecimal,
+    pub alerted_at: String,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct NotificationResponse {
+    pub id: uuid::Uuid,
+    pub group_address: String,
+    pub kind: String,
+    pub message: String,
+    pub created_at: String,
+    pub read_at: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct GetGroupDetailsRequest {
+    pub group_address: Address,
 }
 #[derive(Debug, Serialize)]
 pub struct GetGroupDetailsResponse {
@@ -40,44 +717,43 @@ pub struct GetGroupDetailsResponse {
 
 #[derive(Debug, Deserialize, Validate)]
 pub struct PayGroupRequest {
-    #[validate(custom(function = "validate_address"))]
-    pub group_address: String,
-    #[validate(custom(function = "validate_address"))]
-    pub token_address: String,
-    #[validate(custom(function = "validate_address"))]
-    pub tx_hash: String,
+    pub group_address: Address,
+    pub token_address: Address,
+    pub tx_hash: TxHash,
     pub usage_remaining: u32,
-    pub token_amount: String,
+    #[validate(custom(function = "validate_token_amount"))]
+    pub token_amount: TokenAmount,
+    #[validate(nested)]
     pub members: Vec<PayGroupMembersRequest>,
 }
 
 #[derive(Debug, Deserialize, Validate)]
 pub struct CallContractRequest {
-    #[validate(custom(function = "validate_address"))]
-    pub group_address: String,
-    #[validate(custom(function = "validate_address"))]
-    pub from_address: String,
-    #[validate(custom(function = "validate_address"))]
-    pub tx_hash: String,
-    pub token_amount: String,
-    pub token_address: String,
+    pub group_address: Address,
+    pub from_address: Address,
+    pub tx_hash: TxHash,
+    #[validate(custom(function = "validate_token_amount"))]
+    pub token_amount: TokenAmount,
+    pub token_address: Address,
+    /// Invoice ID or reference the payer quoted, see `libs::invoice`.
+    #[validate(length(max = 140, message = "must be at most 140 characters"))]
+    pub memo: Option<String>,
 }
 
 #[derive(Debug, Deserialize, Validate)]
 pub struct SubscriptionToppedReq {
-    #[validate(custom(function = "validate_address"))]
-    pub group_address: String,
+    pub group_address: Address,
     pub usage_count: u32,
+    pub tx_hash: Option<TxHash>,
 }
 
 #[derive(Debug, Deserialize, Validate)]
 pub struct PayGroupMembersRequest {
-    #[validate(custom(function = "validate_address"))]
-    pub member_address: String,
-    pub member_amount: String,
+    pub member_address: Address,
+    pub member_amount: TokenAmount,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, FromRow)]
 pub struct GroupsResponse {
     pub group_address: String,
     pub group_name: String,
@@ -96,7 +772,7 @@ pub struct GroupFullDetailResponse {
     pub share_strk: Option<String>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, FromRow)]
 pub struct GroupsMetricsResponse {
     pub group_address: String,
     pub share_usdc: Option<String>,
@@ -141,10 +817,805 @@ pub struct GetGroupUsageRemaining {
     pub usage_remaining: bigdecimal::BigDecimal,
 }
 
-pub fn validate_address(address: &str) -> Result<(), ValidationError> {
-    (address.starts_with("0x")
-        && address.len() == 66
-        && address[2..].chars().all(|c| c.is_ascii_hexdigit()))
-    .then_some(())
-    .ok_or(ValidationError::new("invalid address format"))
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GroupSort {
+    #[default]
+    CreatedAt,
+    Name,
+    UsageRemaining,
+}
+
+impl SortField for GroupSort {
+    fn column(self) -> &'static str {
+        match self {
+            GroupSort::CreatedAt => "g.created_at",
+            GroupSort::Name => "g.group_name",
+            GroupSort::UsageRemaining => "g.usage_remaining",
+        }
+    }
+
+    fn sql_type(self) -> &'static str {
+        match self {
+            GroupSort::CreatedAt => "timestamptz",
+            GroupSort::Name => "text",
+            GroupSort::UsageRemaining => "numeric",
+        }
+    }
+}
+
+/// Filters for group lists; `from`/`to` bound `created_at` and `q` matches
+/// the group name case-insensitively.
+#[derive(Debug, Clone, Deserialize, Validate)]
+#[validate(schema(function = "validate_group_list_range", skip_on_field_errors = false))]
+pub struct GroupListQuery {
+    pub created_by: Option<Address>,
+    pub member: Option<Address>,
+    pub token_address: Option<Address>,
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub from: Option<OffsetDateTime>,
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub to: Option<OffsetDateTime>,
+    #[validate(length(min = 1, max = 100, message = "must be between 1 and 100 characters"))]
+    pub q: Option<String>,
+    pub sort: Option<GroupSort>,
+    pub order: Option<SortOrder>,
+    pub cursor: Option<String>,
+    #[validate(range(min = 1, max = MAX_PAGE_LIMIT, message = "must be between 1 and 500"))]
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PaymentSort {
+    #[default]
+    PaidAt,
+    Amount,
+}
+
+impl SortField for PaymentSort {
+    fn column(self) -> &'static str {
+        match self {
+            PaymentSort::PaidAt => "p.paid_at",
+            PaymentSort::Amount => "p.amount",
+        }
+    }
+
+    fn sql_type(self) -> &'static str {
+        match self {
+            PaymentSort::PaidAt => "timestamptz",
+            PaymentSort::Amount => "numeric",
+        }
+    }
+}
+
+/// Filters for payment lists; `member` keeps payments that distributed to
+/// that address and `from`/`to` bound `paid_at`.
+#[derive(Debug, Deserialize, Validate)]
+#[validate(schema(function = "validate_payment_list_range", skip_on_field_errors = false))]
+pub struct PaymentListQuery {
+    pub group_address: Option<Address>,
+    pub member: Option<Address>,
+    pub token_address: Option<Address>,
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub from: Option<OffsetDateTime>,
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub to: Option<OffsetDateTime>,
+    pub sort: Option<PaymentSort>,
+    pub order: Option<SortOrder>,
+    pub cursor: Option<String>,
+    #[validate(range(min = 1, max = MAX_PAGE_LIMIT, message = "must be between 1 and 500"))]
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DistributionSort {
+    #[default]
+    SentAt,
+    Amount,
+}
+
+impl SortField for DistributionSort {
+    fn column(self) -> &'static str {
+        match self {
+            DistributionSort::SentAt => "d.sent_at",
+            DistributionSort::Amount => "d.token_amount",
+        }
+    }
+
+    fn sql_type(self) -> &'static str {
+        match self {
+            DistributionSort::SentAt => "timestamptz",
+            DistributionSort::Amount => "numeric",
+        }
+    }
+}
+
+/// Filters for distribution lists; `from`/`to` bound `sent_at`.
+#[derive(Debug, Deserialize, Validate)]
+#[validate(schema(
+    function = "validate_distribution_list_range",
+    skip_on_field_errors = false
+))]
+pub struct DistributionListQuery {
+    pub group_address: Option<Address>,
+    pub member: Option<Address>,
+    pub token_address: Option<Address>,
+    pub tx_hash: Option<TxHash>,
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub from: Option<OffsetDateTime>,
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub to: Option<OffsetDateTime>,
+    pub sort: Option<DistributionSort>,
+    pub order: Option<SortOrder>,
+    pub cursor: Option<String>,
+    #[validate(range(min = 1, max = MAX_PAGE_LIMIT, message = "must be between 1 and 500"))]
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PayerSort {
+    #[default]
+    LastPaidAt,
+    PaymentCount,
+}
+
+impl SortField for PayerSort {
+    fn column(self) -> &'static str {
+        match self {
+            PayerSort::LastPaidAt => "t.last_paid_at",
+            PayerSort::PaymentCount => "t.payment_count",
+        }
+    }
+
+    fn sql_type(self) -> &'static str {
+        match self {
+            PayerSort::LastPaidAt => "timestamptz",
+            PayerSort::PaymentCount => "bigint",
+        }
+    }
+}
+
+/// Filters for payer lists, applied to the transfers before aggregating;
+/// `from`/`to` bound when each transfer was submitted.
+#[derive(Debug, Deserialize, Validate)]
+#[validate(schema(function = "validate_payer_list_range", skip_on_field_errors = false))]
+pub struct PayerListQuery {
+    pub token_address: Option<Address>,
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub from: Option<OffsetDateTime>,
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub to: Option<OffsetDateTime>,
+    pub sort: Option<PayerSort>,
+    pub order: Option<SortOrder>,
+    pub cursor: Option<String>,
+    #[validate(range(min = 1, max = MAX_PAGE_LIMIT, message = "must be between 1 and 500"))]
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Granularity {
+    Hour,
+    Day,
+    Week,
+    Month,
+}
+
+impl Granularity {
+    /// `date_trunc` field; also the unit of the `generate_series` step.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Granularity::Hour => "hour",
+            Granularity::Day => "day",
+            Granularity::Week => "week",
+            Granularity::Month => "month",
+        }
+    }
+
+    /// Shortest possible bucket, for bounding the bucket count.
+    fn min_duration(self) -> time::Duration {
+        match self {
+            Granularity::Hour => time::Duration::HOUR,
+            Granularity::Day => time::Duration::DAY,
+            Granularity::Week => time::Duration::WEEK,
+            Granularity::Month => time::Duration::DAY * 28,
+        }
+    }
+}
+
+/// Volume over `[from, to)`; `to` defaults to now. With `member` set the
+/// volume is what that address received, otherwise what was paid in.
+#[derive(Debug, Deserialize, Validate)]
+#[validate(schema(function = "validate_volume_range", skip_on_field_errors = false))]
+pub struct VolumeQuery {
+    pub granularity: Granularity,
+    #[serde(with = "time::serde::rfc3339")]
+    pub from: OffsetDateTime,
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub to: Option<OffsetDateTime>,
+    pub group_address: Option<Address>,
+    pub member: Option<Address>,
+    pub created_by: Option<Address>,
+    pub token_address: Option<Address>,
+    pub currency: Option<Currency>,
+}
+
+/// Scope of an export; `from`/`to` bound the payment, distribution or
+/// membership time.
+#[derive(Debug, Deserialize, Validate)]
+#[validate(schema(function = "validate_export_range", skip_on_field_errors = false))]
+pub struct ExportQuery {
+    pub group_address: Option<Address>,
+    pub member: Option<Address>,
+    pub token_address: Option<Address>,
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub from: Option<OffsetDateTime>,
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub to: Option<OffsetDateTime>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FindingKind {
+    MissingMember,
+    UnexpectedMember,
+    AmountMismatch,
+    OverDistributed,
+    RoundingDust,
+}
+
+impl FindingKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            FindingKind::MissingMember => "missing_member",
+            FindingKind::UnexpectedMember => "unexpected_member",
+            FindingKind::AmountMismatch => "amount_mismatch",
+            FindingKind::OverDistributed => "over_distributed",
+            FindingKind::RoundingDust => "rounding_dust",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FindingSeverity {
+    Error,
+    Info,
+}
+
+impl FindingSeverity {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            FindingSeverity::Error => "error",
+            FindingSeverity::Info => "info",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FindingSort {
+    #[default]
+    DetectedAt,
+}
+
+impl SortField for FindingSort {
+    fn column(self) -> &'static str {
+        match self {
+            FindingSort::DetectedAt => "f.detected_at",
+        }
+    }
+
+    fn sql_type(self) -> &'static str {
+        match self {
+            FindingSort::DetectedAt => "timestamptz",
+        }
+    }
+}
+
+/// Filters for audit findings; `from`/`to` bound the payment time.
+#[derive(Debug, Deserialize, Validate)]
+#[validate(schema(function = "validate_finding_list_range", skip_on_field_errors = false))]
+pub struct FindingListQuery {
+    pub group_address: Option<Address>,
+    pub member: Option<Address>,
+    pub tx_hash: Option<TxHash>,
+    pub kind: Option<FindingKind>,
+    pub severity: Option<FindingSeverity>,
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub from: Option<OffsetDateTime>,
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub to: Option<OffsetDateTime>,
+    pub sort: Option<FindingSort>,
+    pub order: Option<SortOrder>,
+    pub cursor: Option<String>,
+    #[validate(range(min = 1, max = MAX_PAGE_LIMIT, message = "must be between 1 and 500"))]
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct AuditSummaryQuery {
+    pub group_address: Option<Address>,
+}
+
+/// Scope of a ledger export: a `member`'s distributions (optionally within
+/// one group) or, without `member`, a group's payments and their splits.
+#[derive(Debug, Deserialize, Validate)]
+#[validate(schema(function = "validate_ledger_export", skip_on_field_errors = false))]
+pub struct LedgerExportQuery {
+    #[serde(default)]
+    pub format: LedgerFormat,
+    pub group_address: Option<Address>,
+    pub member: Option<Address>,
+    pub token_address: Option<Address>,
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub from: Option<OffsetDateTime>,
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub to: Option<OffsetDateTime>,
+    #[validate(custom(function = "validate_ledger_account"))]
+    pub assets_account: Option<String>,
+    #[validate(custom(function = "validate_ledger_account"))]
+    pub income_account: Option<String>,
+    #[validate(custom(function = "validate_ledger_account"))]
+    pub expenses_account: Option<String>,
+}
+
+/// Statement period: a calendar `month` (`YYYY-MM`, UTC) or an explicit
+/// `from`/`to` range, end exclusive.
+#[derive(Debug, Deserialize, Validate)]
+#[validate(schema(function = "validate_statement_period", skip_on_field_errors = false))]
+pub struct StatementQuery {
+    pub month: Option<String>,
+    pub currency: Option<Currency>,
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub from: Option<OffsetDateTime>,
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub to: Option<OffsetDateTime>,
+}
+
+impl StatementQuery {
+    /// `None` unless exactly one of `month` or `from` and `to` is given.
+    pub fn period(&self) -> Option<(OffsetDateTime, OffsetDateTime)> {
+        match (&self.month, self.from, self.to) {
+            (Some(month), None, None) => {
+                let start = Date::parse(
+                    &format!("{month}-01"),
+                    format_description!("[year]-[month]-[day]"),
+                )
+                .ok()?;
+                let end = (start + Duration::days(31)).replace_day(1).ok()?;
+                Some((start.midnight().assume_utc(), end.midnight().assume_utc()))
+            }
+            (None, Some(from), Some(to)) => Some((from, to)),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct PaymentResponse {
+    pub tx_hash: String,
+    pub group_address: String,
+    pub token_address: String,
+    pub amount: bigdecimal::BigDecimal,
+    pub paid_at: String,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct DistributionResponse {
+    pub member_address: String,
+    pub token_address: String,
+    pub token_amount: bigdecimal::BigDecimal,
+    pub sent_at: String,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct DistributionRecord {
+    pub tx_hash: String,
+    pub group_address: String,
+    pub member_address: String,
+    pub token_address: String,
+    pub token_amount: bigdecimal::BigDecimal,
+    pub sent_at: String,
+}
+
+/// A discrepancy between a payment's stored distributions and the split its
+/// share set implies. Member findings compare one member's `expected` and
+/// `actual` amounts (absent when there is none); payment findings compare the
+/// paid `amount` (`expected`) with the total distributed (`over_distributed`)
+/// or the total the shares account for (`rounding_dust`).
+#[derive(Debug, Serialize, FromRow)]
+pub struct AuditFindingResponse {
+    pub tx_hash: String,
+    pub group_address: String,
+    pub token_address: String,
+    pub member_address: Option<String>,
+    pub kind: String,
+    pub severity: String,
+    pub expected: Option<bigdecimal::BigDecimal>,
+    pub actual: Option<bigdecimal::BigDecimal>,
+    pub paid_at: String,
+    pub detected_at: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FindingCount {
+    pub kind: String,
+    pub severity: String,
+    pub finding_count: i64,
+    pub payment_count: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AuditSummaryResponse {
+    pub payment_count: i64,
+    pub audited_count: i64,
+    /// Audited payments with at least one `error` finding.
+    pub flagged_count: i64,
+    pub findings: Vec<FindingCount>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PaymentDetailResponse {
+    #[serde(flatten)]
+    pub payment: PaymentResponse,
+    /// Version of the share set the payment was split by.
+    pub share_set_version: Option<i32>,
+    pub distributions: Vec<DistributionResponse>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TokenVolume {
+    pub token_address: String,
+    pub total_amount: bigdecimal::BigDecimal,
+    pub payment_count: i64,
+}
+
+/// A group as seen by its creator on the dashboard.
+#[derive(Debug, Serialize, FromRow)]
+pub struct CreatorGroupResponse {
+    pub group_address: String,
+    pub group_name: String,
+    pub usage_remaining: bigdecimal::BigDecimal,
+    pub created_at: String,
+    pub updated_at: String,
+    pub member_count: i64,
+    pub payment_count: i64,
+    pub last_payment_at: Option<String>,
+    #[sqlx(skip)]
+    pub volume: Vec<TokenVolume>,
+}
+
+/// One address that paid a group.
+#[derive(Debug, Serialize, FromRow)]
+pub struct PayerResponse {
+    pub from_address: String,
+    pub payment_count: i64,
+    pub first_paid_at: String,
+    pub last_paid_at: String,
+    #[sqlx(skip)]
+    pub totals: Vec<TokenVolume>,
+}
+
+/// One group a payer has paid.
+#[derive(Debug, Serialize, FromRow)]
+pub struct PayerGroupResponse {
+    pub group_address: String,
+    pub group_name: String,
+    pub payment_count: i64,
+    pub first_paid_at: String,
+    pub last_paid_at: String,
+    #[sqlx(skip)]
+    pub totals: Vec<TokenVolume>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct VolumePoint {
+    #[serde(with = "time::serde::rfc3339")]
+    pub bucket: OffsetDateTime,
+    pub volume: bigdecimal::BigDecimal,
+    pub payment_count: i64,
+    /// Fiat value at payment time; only with `currency`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<bigdecimal::BigDecimal>,
+    /// Payments left out of `value` for lack of a price.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unpriced_count: Option<i64>,
+}
+
+/// One token's buckets, contiguous over the range with empty buckets zeroed.
+#[derive(Debug, Serialize)]
+pub struct VolumeSeries {
+    pub token_address: String,
+    pub points: Vec<VolumePoint>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct VolumeAnalyticsResponse {
+    pub granularity: Granularity,
+    #[serde(with = "time::serde::rfc3339")]
+    pub from: OffsetDateTime,
+    #[serde(with = "time::serde::rfc3339")]
+    pub to: OffsetDateTime,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub currency: Option<Currency>,
+    pub series: Vec<VolumeSeries>,
+}
+
+/// Lifetime earnings of one member in one token.
+#[derive(Debug, Serialize, Clone)]
+pub struct TokenEarnings {
+    pub token_address: String,
+    pub total_amount: bigdecimal::BigDecimal,
+    pub distribution_count: i64,
+    pub last_received_at: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MemberGroupResponse {
+    pub group_address: String,
+    pub group_name: String,
+    pub created_by: String,
+    pub member_percentage: bigdecimal::BigDecimal,
+    pub added_at: String,
+    pub earnings: Vec<TokenEarnings>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MemberTotalsResponse {
+    pub group_count: usize,
+    pub distribution_count: i64,
+    pub earnings: Vec<TokenEarnings>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MemberPortfolioResponse {
+    pub member_address: String,
+    pub groups: Vec<MemberGroupResponse>,
+    pub recent_distributions: Vec<DistributionRecord>,
+    pub totals: MemberTotalsResponse,
+}
+
+fn validation_error(code: &'static str, message: &'static str) -> ValidationError {
+    ValidationError::new(code).with_message(message.into())
+}
+
+/// A transferred amount must be non-zero.
+pub fn validate_token_amount(amount: &TokenAmount) -> Result<(), ValidationError> {
+    (!amount.is_zero()).then_some(()).ok_or(validation_error(
+        "not_positive",
+        "must be greater than zero",
+    ))
+}
+
+fn validate_supported_token(token_address: &Address) -> Result<(), ValidationError> {
+    token::lookup(&token_address.to_string())
+        .map(|_| ())
+        .ok_or(validation_error(
+            "unsupported_token",
+            "the contract does not split this token",
+        ))
+}
+
+/// Cross-member rules mirrored from the contract's `create_group`. Reported
+/// against `members` via the `field` param, see `libs::extract`.
+fn validate_group_members(request: &GroupRequest) -> Result<(), ValidationError> {
+    validate_members(&request.members)
+}
+
+fn validate_share_set(request: &ShareSetRequest) -> Result<(), ValidationError> {
+    validate_members(&request.members)?;
+    match request.valid_from {
+        Some(valid_from) if valid_from > OffsetDateTime::now_utc() => {
+            let mut error = validation_error(
+                "future_valid_from",
+                "`valid_from` must not be in the future",
+            );
+            error.add_param("field".into(), &"valid_from");
+            Err(error)
+        }
+        _ => Ok(()),
+    }
+}
+
+fn validate_update_request(request: &UpdateRequestedRequest) -> Result<(), ValidationError> {
+    validate_members(&request.members)
+}
+
+fn validate_members(members: &[GroupMembersRequest]) -> Result<(), ValidationError> {
+    let mut seen = HashSet::new();
+    let sum: u32 = members
+        .iter()
+        .map(|member| u32::from(member.percentage))
+        .sum();
+
+    let mut error = if !(MIN_GROUP_MEMBERS..=MAX_GROUP_MEMBERS).contains(&members.len()) {
+        validation_error(
+            "member_count",
+            "a group must have between 2 and 100 members",
+        )
+    } else if !members.iter().all(|member| seen.insert(member.addr)) {
+        validation_error("duplicate_member", "member addresses must be unique")
+    } else if sum != 100 {
+        validation_error("percentage_sum", "member percentages must add up to 100")
+    } else {
+        return Ok(());
+    };
+
+    error.add_param("field".into(), &"members");
+    Err(error)
+}
+
+fn validate_time_range(
+    from: Option<OffsetDateTime>,
+    to: Option<OffsetDateTime>,
+) -> Result<(), ValidationError> {
+    match (from, to) {
+        (Some(from), Some(to)) if from > to => {
+            let mut error = validation_error("invalid_range", "`from` must not be after `to`");
+            error.add_param("field".into(), &"from");
+            Err(error)
+        }
+        _ => Ok(()),
+    }
+}
+
+fn validate_group_list_range(query: &GroupListQuery) -> Result<(), ValidationError> {
+    validate_time_range(query.from, query.to)
+}
+
+fn validate_payment_list_range(query: &PaymentListQuery) -> Result<(), ValidationError> {
+    validate_time_range(query.from, query.to)
+}
+
+fn validate_distribution_list_range(query: &DistributionListQuery) -> Result<(), ValidationError> {
+    validate_time_range(query.from, query.to)
+}
+
+/// Amount range and dates in order, and a grace period short enough that
+/// one occurrence's window closes before the next one's opens.
+fn validate_recurring_expectation(
+    request: &RecurringExpectationRequest,
+) -> Result<(), ValidationError> {
+    let (field, code, message) = if request.max_amount < request.min_amount {
+        (
+            "max_amount",
+            "invalid_range",
+            "`max_amount` must not be below `min_amount`",
+        )
+    } else if request
+        .ends_on
+        .is_some_and(|ends_on| ends_on < request.starts_on)
+    {
+        (
+            "ends_on",
+            "invalid_range",
+            "`ends_on` must not be before `starts_on`",
+        )
+    } else if request
+        .grace_days
+        .is_some_and(|grace_days| 2 * u32::from(grace_days) >= request.period_days())
+    {
+        (
+            "grace_days",
+            "grace_too_long",
+            "grace period must be shorter than half the interval",
+        )
+    } else {
+        return Ok(());
+    };
+
+    let mut error = validation_error(code, message);
+    error.add_param("field".into(), &field);
+    Err(error)
+}
+
+fn validate_usage_delta(delta: i32) -> Result<(), ValidationError> {
+    (delta != 0)
+        .then_some(())
+        .ok_or(validation_error("zero_delta", "must not be zero"))
+}
+
+fn validate_usage_thresholds(thresholds: &[u32]) -> Result<(), ValidationError> {
+    if let Some(threshold) = thresholds.iter().find(|t| **t > MAX_USAGE_THRESHOLD) {
+        let mut error = validation_error("threshold_too_high", "must be at most 100000");
+        error.add_param("threshold".into(), threshold);
+        return Err(error);
+    }
+    let mut seen = std::collections::HashSet::new();
+    match thresholds.iter().find(|t| !seen.insert(**t)) {
+        Some(threshold) => {
+            let mut error = validation_error("duplicate_threshold", "must not repeat a threshold");
+            error.add_param("threshold".into(), threshold);
+            Err(error)
+        }
+        None => Ok(()),
+    }
+}
+
+fn validate_usage_ledger_range(query: &UsageLedgerQuery) -> Result<(), ValidationError> {
+    validate_time_range(query.from, query.to)
+}
+
+fn validate_invoice_list_range(query: &InvoiceListQuery) -> Result<(), ValidationError> {
+    validate_time_range(query.from, query.to)
+}
+
+fn validate_payer_list_range(query: &PayerListQuery) -> Result<(), ValidationError> {
+    validate_time_range(query.from, query.to)
+}
+
+fn validate_volume_range(query: &VolumeQuery) -> Result<(), ValidationError> {
+    let to = query.to.unwrap_or_else(OffsetDateTime::now_utc);
+    let (code, message) = if query.from >= to {
+        ("invalid_range", "`from` must be before `to`")
+    } else if (to - query.from) / query.granularity.min_duration() > MAX_ANALYTICS_BUCKETS as f64 {
+        (
+            "too_many_buckets",
+            "range spans more than 1000 buckets at this granularity",
+        )
+    } else {
+        return Ok(());
+    };
+
+    let mut error = validation_error(code, message);
+    error.add_param("field".into(), &"from");
+    Err(error)
+}
+
+fn validate_export_range(query: &ExportQuery) -> Result<(), ValidationError> {
+    validate_time_range(query.from, query.to)
+}
+
+fn validate_statement_period(query: &StatementQuery) -> Result<(), ValidationError> {
+    let (code, message) = match query.period() {
+        None => (
+            "invalid_period",
+            "pass either `month` as YYYY-MM or both `from` and `to`",
+        ),
+        Some((from, to)) if from >= to => ("invalid_range", "`from` must be before `to`"),
+        Some(_) => return Ok(()),
+    };
+
+    let mut error = validation_error(code, message);
+    error.add_param("field".into(), &"month");
+    Err(error)
+}
+
+fn validate_ledger_export(query: &LedgerExportQuery) -> Result<(), ValidationError> {
+    if query.member.is_none() && query.group_address.is_none() {
+        let mut error = validation_error("missing_scope", "pass `member` or `group_address`");
+        error.add_param("field".into(), &"member");
+        return Err(error);
+    }
+    validate_time_range(query.from, query.to)
+}
+
+/// Account names both Beancount and ledger-cli accept: a root type followed
+/// by capitalized `:`-separated components, e.g. `Assets:Crypto:Starknet`.
+fn validate_ledger_account(account: &str) -> Result<(), ValidationError> {
+    let mut components = account.split(':');
+    let root = components.next().unwrap_or_default();
+    let valid = ["Assets", "Liabilities", "Equity", "Income", "Expenses"].contains(&root)
+        && components.all(|component| {
+            component
+                .chars()
+                .next()
+                .is_some_and(|c| c.is_ascii_uppercase() || c.is_ascii_digit())
+                && component
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-')
+        });
+
+    valid.then_some(()).ok_or(validation_error(
+        "invalid_account",
+        "must look like `Assets:Crypto:Starknet`",
+    ))
+}
+
+fn validate_finding_list_range(query: &FindingListQuery) -> Result<(), ValidationError> {
+    validate_time_range(query.from, query.to)
 }
@@ -0,0 +1,425 @@
+use axum::{Json, extract::State, http::StatusCode, response::IntoResponse};
+use bigdecimal::BigDecimal;
+use sqlx::{PgConnection, QueryBuilder};
+use tracing::Instrument;
+
+use crate::{
+    AppState,
+    libs::{
+        auth::Admin,
+        db::query_span,
+        error::{ApiError, ErrorCode},
+        extract::{ValidatedJson, ValidatedPath, ValidatedQuery},
+        forecast::{self, DEFAULT_WINDOW_DAYS},
+        middleware::RequestId,
+        pagination::{KeyColumn, Keyed, Keyset, Page},
+        usage::{self, Adjusted},
+    },
+    routes::{
+        group::ensure_group_exists,
+        types::{
+            DEFAULT_PAGE_LIMIT, UsageAdjustmentRequest, UsageAlertResponse,
+            UsageAlertSettingsRequest, UsageAlertSettingsResponse, UsageDiscrepancy,
+            UsageForecastQuery, UsageForecastResponse, UsageLedgerEntryResponse, UsageLedgerQuery,
+            UsageReconciliationQuery, UsageReconciliationResponse,
+        },
+    },
+    util::{address::Address, starknet},
+};
+
+const LEDGER_KEY: KeyColumn = KeyColumn {
+    column: "l.id",
+    sql_type: "bigint",
+};
+
+const LEDGER_COLUMNS: &str = "SELECT l.id, l.kind, l.delta, l.usage_after, l.tx_hash, l.reason, \
+     l.recorded_by, l.recorded_at::text AS recorded_at";
+
+#[tracing::instrument(skip_all, fields(group_address = %group_address))]
+pub async fn get_usage_ledger(
+    State(state): State<AppState>,
+    ValidatedPath(group_address): ValidatedPath<Address>,
+    ValidatedQuery(query): ValidatedQuery<UsageLedgerQuery>,
+) -> Result<Json<Page<UsageLedgerEntryResponse>>, ApiError> {
+    let group_address = group_address.to_string();
+    ensure_group_exists(&state.db, &group_address).await?;
+
+    let keyset = Keyset::new(
+        LEDGER_KEY,
+        query.sort,
+        query.order,
+        query.cursor.as_deref(),
+        query.limit.unwrap_or(DEFAULT_PAGE_LIMIT),
+    )?;
+
+    let mut qb = QueryBuilder::new(LEDGER_COLUMNS);
+    keyset.push_select(&mut qb);
+    qb.push(" FROM group_usage_ledger l WHERE l.group_address = ")
+        .push_bind(group_address);
+    if let Some(kind) = query.kind {
+        qb.push(" AND l.kind = ").push_bind(kind.as_str());
+    }
+    if let Some(from) = query.from {
+        qb.push(" AND l.recorded_at >= ").push_bind(from);
+    }
+    if let Some(to) = query.to {
+        qb.push(" AND l.recorded_at < ").push_bind(to);
+    }
+    keyset.push_after(&mut qb);
+    keyset.push_order_limit(&mut qb);
+
+    let rows = qb
+        .build_query_as::<Keyed<UsageLedgerEntryResponse>>()
+        .fetch_all(&state.db)
+        .instrument(query_span("select_usage_ledger"))
+        .await?;
+
+    Ok(Json(keyset.page(rows)))
+}
+
+/// Manually corrects a group's usage, e.g. after a missed event. Admin only.
+#[tracing::instrument(skip_all, fields(group_address = %group_address, recorded_by = admin.identity))]
+pub async fn adjust_usage(
+    State(state): State<AppState>,
+    admin: Admin,
+    ValidatedPath(group_address): ValidatedPath<Address>,
+    ValidatedJson(payload): ValidatedJson<UsageAdjustmentRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    let group_address = group_address.to_string();
+
+    let mut tx = state.db.begin().await?;
+    let adjusted = usage::adjust(
+        &mut tx,
+        &group_address,
+        BigDecimal::from(payload.delta),
+        &payload.reason,
+        admin.identity,
+    )
+    .await?;
+
+    match adjusted {
+        Adjusted::Applied(usage_after) => {
+            tracing::info!(delta = payload.delta, %usage_after, "Usage adjusted");
+            forecast::check_thresholds(&mut tx, &group_address).await?;
+        }
+        Adjusted::GroupNotFound => return Err(ApiError::group_not_found()),
+        Adjusted::Negative(usage_remaining) => {
+            return Err(ApiError::new(
+                ErrorCode::Conflict,
+                format!("Group has only {usage_remaining} usages remaining"),
+            ));
+        }
+    }
+
+    let entry = sqlx::query_as!(
+        UsageLedgerEntryResponse,
+        r#"
+        SELECT id, kind, delta, usage_after, tx_hash, reason, recorded_by,
+            recorded_at::text as "recorded_at!"
+        FROM group_usage_ledger
+        WHERE group_address = $1
+        ORDER BY id DESC
+        LIMIT 1
+        "#,
+        group_address
+    )
+    .fetch_one(&mut *tx)
+    .instrument(query_span("select_usage_ledger_entry"))
+    .await?;
+
+    tx.commit().await?;
+
+    Ok((StatusCode::CREATED, Json(entry)))
+}
+
+/// Checks the ledger against itself, `usage_remaining` and, unless
+/// `onchain=false`, the contract's `get_group_usage_count` and
+/// `get_group_usage_paid_history`.
+#[tracing::instrument(skip_all, fields(group_address = %group_address, request_id = %request_id.0))]
+pub async fn reconcile_usage(
+    State(state): State<AppState>,
+    request_id: RequestId,
+    ValidatedPath(group_address): ValidatedPath<Address>,
+    ValidatedQuery(query): ValidatedQuery<UsageReconciliationQuery>,
+) -> Result<Json<UsageReconciliationResponse>, ApiError> {
+    let address = group_address.to_string();
+
+    let group = sqlx::query!(
+        "SELECT created_by, usage_remaining, group_id FROM groups WHERE group_address = $1",
+        address
+    )
+    .fetch_optional(&state.db)
+    .instrument(query_span("select_group_usage"))
+    .await?
+    .ok_or_else(ApiError::group_not_found)?;
+
+    let entries = sqlx::query!(
+        r#"
+        SELECT id, kind, delta, usage_after
+        FROM group_usage_ledger
+        WHERE group_address = $1
+        ORDER BY id
+        "#,
+        address
+    )
+    .fetch_all(&state.db)
+    .instrument(query_span("select_usage_ledger"))
+    .await?;
+
+    let mut discrepancies = Vec::new();
+    let mut ledger_balance = BigDecimal::from(0);
+    let mut ledger_purchases = Vec::new();
+    let mut opened = false;
+    for entry in &entries {
+        let expected = &ledger_balance + &entry.delta;
+        if entry.kind != "opening" && expected != entry.usage_after {
+            discrepancies.push(UsageDiscrepancy {
+                kind: "broken_sequence",
+                message: format!(
+                    "{} left {} usages where the ledger expected {expected}",
+                    entry.kind, entry.usage_after
+                ),
+                entry_id: Some(entry.id),
+            });
+        }
+        match entry.kind.as_str() {
+            "opening" => opened = true,
+            "purchase" | "top_up" => ledger_purchases.push(entry.delta.clone()),
+            _ => {}
+        }
+        ledger_balance = entry.usage_after.clone();
+    }
+
+    let ledger_sum: BigDecimal = entries.iter().map(|entry| &entry.delta).sum();
+    if ledger_sum != group.usage_remaining {
+        discrepancies.push(UsageDiscrepancy {
+            kind: "balance_drift",
+            message: format!(
+                "ledger entries add up to {ledger_sum} but the group has {} usages remaining",
+                group.usage_remaining
+            ),
+            entry_id: None,
+        });
+    }
+
+    let mut group_id = group.group_id;
+    let mut contract_usage_count = None;
+    let mut contract_purchases = None;
+    if query.onchain.unwrap_or(true) {
+        if group_id.is_none() {
+            let creator = group
+                .created_by
+                .parse::<Address>()
+                .map_err(ApiError::internal)?;
+            group_id = starknet::find_group_id(creator, group_address, &request_id.0)
+                .await
+                .map_err(contract_call_failed)?;
+            if let Some(id) = group_id {
+                sqlx::query!(
+                    "UPDATE groups SET group_id = $2 WHERE group_address = $1",
+                    address,
+                    id
+                )
+                .execute(&state.db)
+                .instrument(query_span("update_group_id"))
+                .await?;
+            }
+        }
+
+        match group_id {
+            Some(id) => {
+                let usage_count = starknet::get_group_usage_count(id, &request_id.0)
+                    .await
+                    .map_err(contract_call_failed)?;
+                let paid_history = starknet::get_group_usage_paid_history(id, &request_id.0)
+                    .await
+                    .map_err(contract_call_failed)?;
+
+                if usage_count != group.usage_remaining {
+                    discrepancies.push(UsageDiscrepancy {
+                        kind: "contract_usage",
+                        message: format!(
+                            "contract has {usage_count} usages remaining, the group {}",
+                            group.usage_remaining
+                        ),
+                        entry_id: None,
+                    });
+                }
+
+                // before the ledger opened only the latest purchases are known
+                let compared = if opened {
+                    &paid_history[paid_history.len().saturating_sub(ledger_purchases.len())..]
+                } else {
+                    &paid_history[..]
+                };
+                if compared != ledger_purchases.as_slice() {
+                    discrepancies.push(UsageDiscrepancy {
+                        kind: "purchase_history",
+                        message: "contract's paid usage history differs from the ledger's \
+                                  purchases and top-ups"
+                            .to_owned(),
+                        entry_id: None,
+                    });
+                }
+
+                contract_usage_count = Some(usage_count);
+                contract_purchases = Some(paid_history);
+            }
+            None => discrepancies.push(UsageDiscrepancy {
+                kind: "unknown_on_chain",
+                message: "no group with this address among its creator's groups on-chain"
+                    .to_owned(),
+                entry_id: None,
+            }),
+        }
+    }
+
+    Ok(Json(UsageReconciliationResponse {
+        group_address: address,
+        group_id,
+        consistent: discrepancies.is_empty(),
+        usage_remaining: group.usage_remaining,
+        ledger_balance: ledger_sum,
+        ledger_purchases,
+        contract_usage_count,
+        contract_purchases,
+        discrepancies,
+    }))
+}
+
+/// Burn rate from recent payouts and when, at that rate, usage runs out.
+#[tracing::instrument(skip_all, fields(group_address = %group_address))]
+pub async fn get_usage_forecast(
+    State(state): State<AppState>,
+    ValidatedPath(group_address): ValidatedPath<Address>,
+    ValidatedQuery(query): ValidatedQuery<UsageForecastQuery>,
+) -> Result<Json<UsageForecastResponse>, ApiError> {
+    let group_address = group_address.to_string();
+    let mut conn = state.db.acquire().await?;
+
+    let forecast = forecast::forecast(
+        &mut conn,
+        &group_address,
+        query.window_days.unwrap_or(DEFAULT_WINDOW_DAYS),
+    )
+    .await?
+    .ok_or_else(ApiError::group_not_found)?;
+
+    let low = forecast
+        .thresholds
+        .iter()
+        .any(|threshold| forecast.usage_remaining <= BigDecimal::from(*threshold));
+    let status = if forecast.usage_remaining <= BigDecimal::from(0) {
+        "exhausted"
+    } else if low {
+        "low"
+    } else if forecast.payouts_in_window == 0 {
+        "idle"
+    } else {
+        "ok"
+    };
+
+    Ok(Json(UsageForecastResponse {
+        group_address,
+        status,
+        usage_remaining: forecast.usage_remaining,
+        window_days: forecast.window_days,
+        payouts_in_window: forecast.payouts_in_window,
+        burn_rate_per_day: forecast.burn_rate,
+        last_payout_at: forecast.last_payout_at,
+        days_remaining: forecast.days_remaining,
+        projected_exhaustion_at: forecast.projected_exhaustion_at,
+        thresholds: forecast.thresholds,
+    }))
+}
+
+#[tracing::instrument(skip_all, fields(group_address = %group_address))]
+pub async fn get_usage_alerts(
+    State(state): State<AppState>,
+    ValidatedPath(group_address): ValidatedPath<Address>,
+) -> Result<Json<UsageAlertSettingsResponse>, ApiError> {
+    let mut conn = state.db.acquire().await?;
+    alert_settings(&mut conn, group_address.to_string())
+        .await
+        .map(Json)
+}
+
+/// Replaces the group's thresholds. A threshold usage is already at warns
+/// right away.
+#[tracing::instrument(skip_all, fields(group_address = %group_address))]
+pub async fn set_usage_alerts(
+    State(state): State<AppState>,
+    ValidatedPath(group_address): ValidatedPath<Address>,
+    ValidatedJson(payload): ValidatedJson<UsageAlertSettingsRequest>,
+) -> Result<Json<UsageAlertSettingsResponse>, ApiError> {
+    let group_address = group_address.to_string();
+    let thresholds = payload.thresholds.map(|thresholds| {
+        let mut thresholds: Vec<i32> = thresholds.into_iter().map(|t| t as i32).collect();
+        thresholds.sort_unstable_by(|a, b| b.cmp(a));
+        thresholds
+    });
+
+    let mut tx = state.db.begin().await?;
+    sqlx::query!(
+        "UPDATE groups SET low_usage_thresholds = $2 WHERE group_address = $1",
+        group_address,
+        thresholds.as_deref()
+    )
+    .execute(&mut *tx)
+    .instrument(query_span("update_low_usage_thresholds"))
+    .await?
+    .rows_affected()
+    .eq(&1)
+    .then_some(())
+    .ok_or_else(ApiError::group_not_found)?;
+
+    forecast::check_thresholds(&mut tx, &group_address).await?;
+
+    let settings = alert_settings(&mut tx, group_address).await?;
+    tx.commit().await?;
+
+    Ok(Json(settings))
+}
+
+async fn alert_settings(
+    conn: &mut PgConnection,
+    group_address: String,
+) -> Result<UsageAlertSettingsResponse, ApiError> {
+    let thresholds = sqlx::query_scalar!(
+        "SELECT low_usage_thresholds FROM groups WHERE group_address = $1",
+        group_address
+    )
+    .fetch_optional(&mut *conn)
+    .instrument(query_span("select_low_usage_thresholds"))
+    .await?
+    .ok_or_else(ApiError::group_not_found)?;
+
+    let alerted = sqlx::query_as!(
+        UsageAlertResponse,
+        r#"
+        SELECT threshold, usage_remaining, alerted_at::text as "alerted_at!"
+        FROM group_usage_alerts
+        WHERE group_address = $1
+        ORDER BY threshold DESC
+        "#,
+        group_address
+    )
+    .fetch_all(&mut *conn)
+    .instrument(query_span("select_usage_alerts"))
+    .await?;
+
+    Ok(UsageAlertSettingsResponse {
+        group_address,
+        uses_defaults: thresholds.is_none(),
+        thresholds: thresholds.unwrap_or_else(|| forecast::default_thresholds().to_vec()),
+        alerted,
+    })
+}
+
+fn contract_call_failed(cause: String) -> ApiError {
+    ApiError::new(
+        ErrorCode::ContractCallFailed,
+        "Failed to call paymesh contract",
+    )
+    .with_cause(cause)
+}
@@ -0,0 +1,162 @@
+use std::{fmt, str::FromStr};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de};
+use starknet::core::types::Felt;
+
+pub const ADDRESS_PREFIX: &str = "0x";
+pub const ADDRESS_HEX_DIGITS: usize = 64;
+
+/// A Starknet address (or any other felt, e.g. a transaction hash).
+///
+/// Accepts `0x` followed by 1 to 64 hex digits in any case, so the indexer's
+/// stripped `FieldElement` output parses, and always renders as `0x` plus 64
+/// lowercase digits. Store and compare the rendered form only.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Address(Felt);
+
+/// Transaction hashes are felts and normalize exactly like addresses.
+pub type TxHash = Address;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidAddress;
+
+impl fmt::Display for InvalidAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("must be 0x followed by 1 to 64 hex digits, below the field prime")
+    }
+}
+
+impl std::error::Error for InvalidAddress {}
+
+impl Address {
+    pub fn felt(self) -> Felt {
+        self.0
+    }
+}
+
+impl From<Felt> for Address {
+    fn from(felt: Felt) -> Self {
+        Address(felt)
+    }
+}
+
+impl From<Address> for Felt {
+    fn from(address: Address) -> Self {
+        address.0
+    }
+}
+
+impl FromStr for Address {
+    type Err = InvalidAddress;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let digits = s
+            .strip_prefix(ADDRESS_PREFIX)
+            .or_else(|| s.strip_prefix("0X"))
+            .ok_or(InvalidAddress)?;
+
+        if digits.is_empty()
+            || digits.len() > ADDRESS_HEX_DIGITS
+            || !digits.bytes().all(|b| b.is_ascii_hexdigit())
+        {
+            return Err(InvalidAddress);
+        }
+
+        // `Felt::from_hex` only strips a lowercase prefix
+        let felt =
+            Felt::from_hex(&format!("{ADDRESS_PREFIX}{digits}")).map_err(|_| InvalidAddress)?;
+
+        // values at or above the field prime would silently wrap around
+        let canonical = felt.to_fixed_hex_string();
+        let expected = format!("{:0>64}", digits.to_ascii_lowercase());
+        if canonical[2..] != expected {
+            return Err(InvalidAddress);
+        }
+
+        Ok(Address(felt))
+    }
+}
+
+impl fmt::Display for Address {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0.to_fixed_hex_string())
+    }
+}
+
+impl Serialize for Address {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Address {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = <std::borrow::Cow<'de, str>>::deserialize(deserializer)?;
+        s.parse()
+            .map_err(|e| de::Error::custom(format!("invalid address `{s}`: {e}")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PRIME: &str = "0x800000000000011000000000000000000000000000000000000000000000001";
+
+    fn parse(s: &str) -> Result<String, InvalidAddress> {
+        s.parse::<Address>().map(|address| address.to_string())
+    }
+
+    #[test]
+    fn pads_to_64_digits() {
+        assert_eq!(
+            parse("0x4600").unwrap(),
+            "0x0000000000000000000000000000000000000000000000000000000000004600"
+        );
+        assert_eq!(parse("0x0").unwrap(), format!("0x{}", "0".repeat(64)));
+        assert_eq!(
+            parse("0x0000000000000000000000000000000000000000000000000000000000004600"),
+            parse("0x4600")
+        );
+    }
+
+    #[test]
+    fn lowercases_digits() {
+        assert_eq!(
+            parse("0x049D36570D4E46F48E99674BD3FCC84644DDD6B96F7C741B1562B82F9E004DC7").unwrap(),
+            "0x049d36570d4e46f48e99674bd3fcc84644ddd6b96f7c741b1562b82f9e004dc7"
+        );
+    }
+
+    #[test]
+    fn accepts_uppercase_prefix() {
+        assert_eq!(parse("0XaBc"), parse("0xabc"));
+    }
+
+    #[test]
+    fn rejects_malformed_input() {
+        for input in [
+            "",
+            "0x",
+            "4600",
+            "0x12g4",
+            "0x 12",
+            "x12",
+            &format!("0x{}", "1".repeat(65)),
+        ] {
+            assert_eq!(parse(input), Err(InvalidAddress), "{input:?}");
+        }
+    }
+
+    #[test]
+    fn rejects_values_at_or_above_the_prime() {
+        let below = "0x800000000000011000000000000000000000000000000000000000000000000";
+        assert_eq!(parse(below).unwrap(), format!("0x0{}", &below[2..]));
+        assert_eq!(parse(PRIME), Err(InvalidAddress));
+        assert_eq!(
+            parse("0x800000000000011000000000000000000000000000000000000000000000002"),
+            Err(InvalidAddress)
+        );
+        assert_eq!(parse(&format!("0x{}", "f".repeat(64))), Err(InvalidAddress));
+    }
+}
@@ -0,0 +1,352 @@
+use std::{fmt, str::FromStr};
+
+use bigdecimal::{
+    BigDecimal,
+    num_bigint::{BigInt, BigUint, Sign},
+};
+use serde::{
+    Deserialize, Deserializer, Serialize, Serializer,
+    de::{self, MapAccess, Visitor},
+};
+use starknet::core::types::U256;
+
+/// A token amount in base units, covering the contract's full `u256` range.
+///
+/// Deserializes from a decimal string, a `0x` hex string, a JSON integer or a
+/// Cairo-style `{ "low": .., "high": .. }` pair of 128-bit words. Negative and
+/// fractional values are rejected. Serializes as a decimal string so no
+/// precision is lost in JavaScript clients.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TokenAmount(U256);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InvalidAmount {
+    Empty,
+    Negative,
+    Fractional,
+    Malformed,
+    Overflow,
+}
+
+impl fmt::Display for InvalidAmount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            InvalidAmount::Empty => "amount is empty",
+            InvalidAmount::Negative => "amount must not be negative",
+            InvalidAmount::Fractional => "amount must be a whole number of base units",
+            InvalidAmount::Malformed => "amount must be a decimal or 0x-prefixed hex integer",
+            InvalidAmount::Overflow => "amount does not fit in a u256",
+        })
+    }
+}
+
+impl std::error::Error for InvalidAmount {}
+
+impl TokenAmount {
+    pub const ZERO: TokenAmount = TokenAmount(U256::from_words(0, 0));
+
+    pub fn from_words(low: u128, high: u128) -> Self {
+        TokenAmount(U256::from_words(low, high))
+    }
+
+    pub fn low(&self) -> u128 {
+        self.0.low()
+    }
+
+    pub fn high(&self) -> u128 {
+        self.0.high()
+    }
+
+    pub fn is_zero(&self) -> bool {
+        *self == Self::ZERO
+    }
+
+    pub fn to_biguint(&self) -> BigUint {
+        (BigUint::from(self.high()) << 128u32) | BigUint::from(self.low())
+    }
+}
+
+impl From<U256> for TokenAmount {
+    fn from(value: U256) -> Self {
+        TokenAmount(value)
+    }
+}
+
+impl From<TokenAmount> for U256 {
+    fn from(value: TokenAmount) -> Self {
+        value.0
+    }
+}
+
+impl From<u128> for TokenAmount {
+    fn from(value: u128) -> Self {
+        TokenAmount::from_words(value, 0)
+    }
+}
+
+impl TryFrom<BigUint> for TokenAmount {
+    type Error = InvalidAmount;
+
+    fn try_from(value: BigUint) -> Result<Self, Self::Error> {
+        if value.bits() > 256 {
+            return Err(InvalidAmount::Overflow);
+        }
+        let mask = (BigUint::from(1u8) << 128u32) - 1u8;
+        let low: u128 = (&value & &mask).try_into().expect("masked to 128 bits");
+        let high: u128 = (value >> 128u32)
+            .try_into()
+            .expect("checked to be 256 bits");
+        Ok(TokenAmount::from_words(low, high))
+    }
+}
+
+impl TryFrom<BigInt> for TokenAmount {
+    type Error = InvalidAmount;
+
+    fn try_from(value: BigInt) -> Result<Self, Self::Error> {
+        match value.sign() {
+            Sign::Minus => Err(InvalidAmount::Negative),
+            _ => TokenAmount::try_from(value.magnitude().clone()),
+        }
+    }
+}
+
+/// For reading `NUMERIC(78,0)` columns back.
+impl TryFrom<&BigDecimal> for TokenAmount {
+    type Error = InvalidAmount;
+
+    fn try_from(value: &BigDecimal) -> Result<Self, Self::Error> {
+        if !value.is_integer() {
+            return Err(InvalidAmount::Fractional);
+        }
+        let (int, _) = value.with_scale(0).into_bigint_and_exponent();
+        TokenAmount::try_from(int)
+    }
+}
+
+/// For binding to `NUMERIC(78,0)` columns.
+impl From<TokenAmount> for BigDecimal {
+    fn from(value: TokenAmount) -> Self {
+        BigDecimal::from(BigInt::from(value.to_biguint()))
+    }
+}
+
+impl FromStr for TokenAmount {
+    type Err = InvalidAmount;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() {
+            return Err(InvalidAmount::Empty);
+        }
+        if s.starts_with('-') {
+            return Err(InvalidAmount::Negative);
+        }
+
+        if let Some(hex) = s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+            if hex.is_empty() || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+                return Err(InvalidAmount::Malformed);
+            }
+            let value = BigUint::parse_bytes(hex.as_bytes(), 16).ok_or(InvalidAmount::Malformed)?;
+            return TokenAmount::try_from(value);
+        }
+
+        if s.contains(['.', 'e', 'E']) {
+            return Err(InvalidAmount::Fractional);
+        }
+        if !s.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(InvalidAmount::Malformed);
+        }
+        let value = BigUint::parse_bytes(s.as_bytes(), 10).ok_or(InvalidAmount::Malformed)?;
+        TokenAmount::try_from(value)
+    }
+}
+
+impl fmt::Display for TokenAmount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+impl Serialize for TokenAmount {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for TokenAmount {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(TokenAmountVisitor)
+    }
+}
+
+struct TokenAmountVisitor;
+
+impl<'de> Visitor<'de> for TokenAmountVisitor {
+    type Value = TokenAmount;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a u256 as a decimal string, 0x hex string, integer or {low, high} pair")
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        v.parse().map_err(E::custom)
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E> {
+        Ok(TokenAmount::from(u128::from(v)))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Self::Value, E> {
+        u64::try_from(v)
+            .map(|v| TokenAmount::from(u128::from(v)))
+            .map_err(|_| E::custom(InvalidAmount::Negative))
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<Self::Value, E> {
+        if v.is_sign_negative() {
+            return Err(E::custom(InvalidAmount::Negative));
+        }
+        if v.fract() != 0.0 {
+            return Err(E::custom(InvalidAmount::Fractional));
+        }
+        Err(E::custom("amounts above 2^64 must be sent as strings"))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let (mut low, mut high) = (None, None);
+        while let Some(key) = map.next_key::<String>()? {
+            let word = match key.as_str() {
+                "low" => &mut low,
+                "high" => &mut high,
+                other => return Err(de::Error::unknown_field(other, &["low", "high"])),
+            };
+            if word.is_some() {
+                return Err(de::Error::custom(format!("duplicate field `{key}`")));
+            }
+            *word = Some(map.next_value::<TokenAmount>()?);
+        }
+
+        let low = low.ok_or_else(|| de::Error::missing_field("low"))?;
+        let high = high.ok_or_else(|| de::Error::missing_field("high"))?;
+        if low.high() != 0 || high.high() != 0 {
+            return Err(de::Error::custom("u256 words must each fit in 128 bits"));
+        }
+        Ok(TokenAmount::from_words(low.low(), high.low()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const U256_MAX_DEC: &str =
+        "115792089237316195423570985008687907853269984665640564039457584007913129639935";
+    const U256_MAX_HEX: &str = "0xffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff";
+    const TWO_POW_256_DEC: &str =
+        "115792089237316195423570985008687907853269984665640564039457584007913129639936";
+
+    fn parse_json(json: &str) -> Result<TokenAmount, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
+    #[test]
+    fn parses_zero() {
+        assert_eq!("0".parse(), Ok(TokenAmount::ZERO));
+        assert_eq!("0x0".parse(), Ok(TokenAmount::ZERO));
+        assert_eq!(parse_json("0").unwrap(), TokenAmount::ZERO);
+    }
+
+    #[test]
+    fn parses_u256_max_as_decimal_and_hex() {
+        let max = TokenAmount::from_words(u128::MAX, u128::MAX);
+        assert_eq!(U256_MAX_DEC.parse(), Ok(max));
+        assert_eq!(U256_MAX_HEX.parse(), Ok(max));
+        assert_eq!(max.to_string(), U256_MAX_DEC);
+    }
+
+    #[test]
+    fn round_trips_through_json() {
+        for value in ["0", U256_MAX_DEC] {
+            let amount: TokenAmount = value.parse().unwrap();
+            let json = serde_json::to_string(&amount).unwrap();
+            assert_eq!(json, format!("\"{value}\""));
+            assert_eq!(parse_json(&json).unwrap(), amount);
+        }
+    }
+
+    #[test]
+    fn parses_low_high_pair() {
+        let amount = parse_json(r#"{ "low": "5", "high": "0x2" }"#).unwrap();
+        assert_eq!(amount, TokenAmount::from_words(5, 2));
+        assert_eq!(
+            amount.to_string(),
+            "680564733841876926926749214863536422917"
+        );
+    }
+
+    #[test]
+    fn rejects_low_high_word_above_128_bits() {
+        let two_pow_128 = "0x100000000000000000000000000000000";
+        let json = format!(r#"{{ "low": "{two_pow_128}", "high": "0" }}"#);
+        assert!(parse_json(&json).is_err());
+        let json = format!(r#"{{ "low": "0", "high": "{two_pow_128}" }}"#);
+        assert!(parse_json(&json).is_err());
+    }
+
+    #[test]
+    fn rejects_overflow() {
+        assert_eq!(
+            TWO_POW_256_DEC.parse::<TokenAmount>(),
+            Err(InvalidAmount::Overflow)
+        );
+        assert_eq!(
+            "0x10000000000000000000000000000000000000000000000000000000000000000"
+                .parse::<TokenAmount>(),
+            Err(InvalidAmount::Overflow)
+        );
+    }
+
+    #[test]
+    fn rejects_negative_and_fractional() {
+        assert_eq!("-1".parse::<TokenAmount>(), Err(InvalidAmount::Negative));
+        assert_eq!("1.5".parse::<TokenAmount>(), Err(InvalidAmount::Fractional));
+        assert_eq!("1e3".parse::<TokenAmount>(), Err(InvalidAmount::Fractional));
+        assert!(parse_json("-1").is_err());
+        assert!(parse_json("1.5").is_err());
+        assert!(parse_json("1e3").is_err());
+    }
+
+    #[test]
+    fn rejects_negative_floats_as_negative() {
+        let negative = InvalidAmount::Negative.to_string();
+        for json in ["-5", "-5.0", "-1.5", "-1e3"] {
+            let error = parse_json(json).unwrap_err().to_string();
+            assert!(error.starts_with(&negative), "{json}: {error}");
+        }
+    }
+
+    #[test]
+    fn round_trips_numeric_column_limit() {
+        let max: BigDecimal = U256_MAX_DEC.parse().unwrap();
+        let amount = TokenAmount::try_from(&max).unwrap();
+        assert_eq!(amount, TokenAmount::from_words(u128::MAX, u128::MAX));
+        assert_eq!(BigDecimal::from(amount), max);
+
+        // NUMERIC(78,0) holds up to 10^78 - 1, past u256
+        let numeric_max: BigDecimal = "9".repeat(78).parse().unwrap();
+        assert_eq!(
+            TokenAmount::try_from(&numeric_max),
+            Err(InvalidAmount::Overflow)
+        );
+        let negative: BigDecimal = "-1".parse().unwrap();
+        assert_eq!(
+            TokenAmount::try_from(&negative),
+            Err(InvalidAmount::Negative)
+        );
+        let fractional: BigDecimal = "1.5".parse().unwrap();
+        assert_eq!(
+            TokenAmount::try_from(&fractional),
+            Err(InvalidAmount::Fractional)
+        );
+    }
+}
@@ -1,5 +1,6 @@
 use std::{env::var, sync::LazyLock};
 
+use crate::libs::middleware::REQUEST_ID_HEADER;
 use starknet::{
     accounts::{ExecutionEncoding, SingleOwnerAccount},
     core::{chain_id, types::Felt},
@@ -10,32 +11,28 @@ use starknet::{
     signers::{LocalWallet, SigningKey},
 };
 
-pub const ADDRESS_PREFIX: &str = "0x";
-pub const ADDRESS_LENGTH: usize = 66;
-
-/// Returns true if the wallet address is valid.
-pub fn is_valid_address(address: &str) -> Result<(), String> {
-    (address.starts_with(ADDRESS_PREFIX)
-        && address.len() == ADDRESS_LENGTH
-        && address[2..].chars().all(|c| c.is_ascii_hexdigit()))
-    .then_some(())
-    .ok_or("invalid address format".to_owned())
-}
-
 static RPC_URL: LazyLock<String> = LazyLock::new(|| var("RPC_URL").expect("RPC URL NOT PROVIDED"));
 static CONTRACT_ADDRESS: LazyLock<String> =
     LazyLock::new(|| var("CONTRACT_ADDRESS").expect("CONTRACT ADDRESS NOT PROVIDED"));
 
-fn rpc_provider() -> JsonRpcClient<HttpTransport> {
-    JsonRpcClient::new(HttpTransport::new(Url::parse(&RPC_URL).unwrap()))
+/// RPC client that forwards the caller's request id so node-side logs can be
+/// correlated with ours.
+pub fn rpc_provider(request_id: &str) -> JsonRpcClient<HttpTransport> {
+    let mut transport = HttpTransport::new(Url::parse(&RPC_URL).unwrap());
+    if !request_id.is_empty() {
+        transport.add_header(REQUEST_ID_HEADER.to_string(), request_id.to_owned());
+    }
+    JsonRpcClient::new(transport)
 }
 
 pub fn contract_address_felt() -> Felt {
     Felt::from_hex(&CONTRACT_ADDRESS).unwrap()
 }
 
-pub fn signer_account() -> SingleOwnerAccount<JsonRpcClient<HttpTransport>, LocalWallet> {
-    let provider = rpc_provider();
+pub fn signer_account(
+    request_id: &str,
+) -> SingleOwnerAccount<JsonRpcClient<HttpTransport>, LocalWallet> {
+    let provider = rpc_provider(request_id);
     let private_key = var("PRIVATE_KEY").unwrap();
     let public_key = var("PUBLIC_KEY").unwrap();
 
@@ -0,0 +1,155 @@
+use std::fmt::Write;
+
+use bigdecimal::BigDecimal;
+use serde::Deserialize;
+use time::{Date, macros::format_description};
+
+use crate::util::token;
+
+/// Plain-text accounting syntax of a ledger export.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LedgerFormat {
+    #[default]
+    Beancount,
+    Ledger,
+}
+
+impl LedgerFormat {
+    pub fn content_type(self) -> &'static str {
+        match self {
+            LedgerFormat::Beancount => "text/x-beancount; charset=utf-8",
+            LedgerFormat::Ledger => "text/x-ledger; charset=utf-8",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            LedgerFormat::Beancount => "beancount",
+            LedgerFormat::Ledger => "ledger",
+        }
+    }
+
+    /// Beancount refuses postings to accounts that were never opened, so the
+    /// file starts by opening every account on the date of the first entry.
+    pub fn prelude(self, date: Date, accounts: &[&str]) -> String {
+        let mut out = String::new();
+        if self == LedgerFormat::Beancount {
+            for account in accounts {
+                let _ = writeln!(out, "{} open {account}", beancount_date(date));
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    pub fn render(self, entry: &Entry) -> String {
+        match self {
+            LedgerFormat::Beancount => render_beancount(entry),
+            LedgerFormat::Ledger => render_ledger(entry),
+        }
+    }
+}
+
+/// One transaction. Posting amounts are in base units and must sum to zero.
+#[derive(Debug)]
+pub struct Entry {
+    pub date: Date,
+    pub payee: String,
+    pub narration: String,
+    pub token_address: String,
+    pub meta: Vec<(&'static str, String)>,
+    pub postings: Vec<Posting>,
+}
+
+#[derive(Debug)]
+pub struct Posting {
+    pub account: String,
+    pub amount: BigDecimal,
+    pub meta: Vec<(&'static str, String)>,
+}
+
+/// Commodity and whole-token amount for a posting. Unknown tokens are booked
+/// in base units under a commodity derived from the token address.
+fn quantity(token_address: &str, amount: &BigDecimal) -> (String, String) {
+    match token::lookup(token_address) {
+        Some(token) => {
+            let formatted = token.format_amount(&amount.abs());
+            let sign = if amount < &BigDecimal::from(0) {
+                "-"
+            } else {
+                ""
+            };
+            (token.symbol.to_owned(), format!("{sign}{formatted}"))
+        }
+        None => {
+            let hex = token_address.trim_start_matches("0x");
+            let suffix = &hex[hex.len().saturating_sub(10)..];
+            (
+                format!("T{}", suffix.to_uppercase()),
+                amount.to_plain_string(),
+            )
+        }
+    }
+}
+
+fn beancount_date(date: Date) -> String {
+    date.format(format_description!("[year]-[month]-[day]"))
+        .unwrap_or_else(|_| date.to_string())
+}
+
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn render_beancount(entry: &Entry) -> String {
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "{} * {} {}",
+        beancount_date(entry.date),
+        quote(&entry.payee),
+        quote(&entry.narration)
+    );
+    for (key, value) in &entry.meta {
+        let _ = writeln!(out, "  {key}: {}", quote(value));
+    }
+    for posting in &entry.postings {
+        let (commodity, amount) = quantity(&entry.token_address, &posting.amount);
+        let _ = writeln!(out, "  {}  {amount} {commodity}", posting.account);
+        for (key, value) in &posting.meta {
+            let _ = writeln!(out, "    {key}: {}", quote(value));
+        }
+    }
+    out.push('\n');
+    out
+}
+
+fn render_ledger(entry: &Entry) -> String {
+    let mut out = String::new();
+    let date = entry
+        .date
+        .format(format_description!("[year]/[month]/[day]"))
+        .unwrap_or_else(|_| entry.date.to_string());
+    // the payee runs to the end of the line; the narration becomes a note
+    let _ = writeln!(out, "{date} * {}", entry.payee.replace('\n', " "));
+    let _ = writeln!(out, "    ; {}", entry.narration);
+    for (key, value) in &entry.meta {
+        let _ = writeln!(out, "    ; {key}: {value}");
+    }
+    for posting in &entry.postings {
+        let (commodity, amount) = quantity(&entry.token_address, &posting.amount);
+        // commodities containing digits must be quoted
+        let commodity = if commodity.chars().all(|c| c.is_ascii_alphabetic()) {
+            commodity
+        } else {
+            quote(&commodity)
+        };
+        let _ = writeln!(out, "    {}  {amount} {commodity}", posting.account);
+        for (key, value) in &posting.meta {
+            let _ = writeln!(out, "        ; {key}: {value}");
+        }
+    }
+    out.push('\n');
+    out
+}
@@ -0,0 +1,68 @@
+use std::error::Error;
+
+use png::{BitDepth, ColorType, Encoder};
+use qrcode::{Color, EcLevel, QrCode, QrResult, render::svg};
+use starknet::core::chain_id;
+
+use crate::util::amount::TokenAmount;
+
+/// Pixels per QR module when the caller does not ask for a size.
+pub const DEFAULT_QR_SCALE: u32 = 8;
+
+/// Light modules around the code, as the QR spec requires.
+const QUIET_ZONE: usize = 4;
+
+/// ERC-681 style transfer request, as Starknet wallets read it:
+/// `starknet:<token>@<chain id>/transfer?address=<recipient>[&uint256=<amount>]`.
+/// Without an amount the payer's wallet asks for one.
+pub fn build(token_address: &str, recipient: &str, amount: Option<TokenAmount>) -> String {
+    let mut uri = format!(
+        "starknet:{token_address}@{:#x}/transfer?address={recipient}",
+        chain_id::MAINNET
+    );
+    if let Some(amount) = amount {
+        uri.push_str(&format!("&uint256={amount}"));
+    }
+    uri
+}
+
+/// Medium error correction keeps the code small enough for a phone camera at
+/// arm's length while surviving a crease in a printed invoice.
+fn encode(uri: &str) -> QrResult<QrCode> {
+    QrCode::with_error_correction_level(uri, EcLevel::M)
+}
+
+/// `uri` as a standalone SVG document, `scale` pixels per module.
+pub fn to_svg(uri: &str, scale: u32) -> QrResult<String> {
+    Ok(encode(uri)?
+        .render::<svg::Color>()
+        .module_dimensions(scale, scale)
+        .build())
+}
+
+/// `uri` as an 8-bit grayscale PNG, `scale` pixels per module.
+pub fn to_png(uri: &str, scale: u32) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+    let code = encode(uri)?;
+    let modules = code.width();
+    let colors = code.to_colors();
+    let scale = scale as usize;
+    let side = (modules + 2 * QUIET_ZONE) * scale;
+
+    let mut pixels = vec![u8::MAX; side * side];
+    for (i, color) in colors.iter().enumerate() {
+        if *color == Color::Light {
+            continue;
+        }
+        let (x, y) = (i % modules + QUIET_ZONE, i / modules + QUIET_ZONE);
+        for row in y * scale..(y + 1) * scale {
+            pixels[row * side + x * scale..row * side + (x + 1) * scale].fill(0);
+        }
+    }
+
+    let mut png = Vec::new();
+    let mut encoder = Encoder::new(&mut png, side as u32, side as u32);
+    encoder.set_color(ColorType::Grayscale);
+    encoder.set_depth(BitDepth::Eight);
+    encoder.write_header()?.write_image_data(&pixels)?;
+    Ok(png)
+}
@@ -0,0 +1,160 @@
+use std::{env::var, sync::LazyLock};
+
+use bigdecimal::{num_bigint::BigUint, num_traits::CheckedSub};
+
+use crate::util::amount::{InvalidAmount, TokenAmount};
+
+/// The contract's `group_usage_fee` as set by its constructor: 1 STRK.
+const DEFAULT_USAGE_FEE: u128 = 1_000_000_000_000_000_000;
+
+/// STRK (base units) prepaid per usage. Set `USAGE_FEE` when the admin has
+/// changed the fee on-chain; a value that doesn't parse panics rather than
+/// quoting the default.
+static USAGE_FEE: LazyLock<TokenAmount> = LazyLock::new(|| match var("USAGE_FEE") {
+    Ok(fee) => fee
+        .parse()
+        .unwrap_or_else(|e| panic!("Invalid USAGE_FEE {fee:?}: {e}")),
+    Err(_) => TokenAmount::from(DEFAULT_USAGE_FEE),
+});
+
+pub fn usage_fee() -> TokenAmount {
+    *USAGE_FEE
+}
+
+#[derive(Debug)]
+pub struct Share {
+    pub member_address: String,
+    pub percentage: u8,
+    pub amount: TokenAmount,
+}
+
+#[derive(Debug)]
+pub struct Split {
+    pub shares: Vec<Share>,
+    pub distributed: TokenAmount,
+    /// Dust lost to flooring. It stays in the group's contract and is split
+    /// with the next payout of the token.
+    pub remainder: TokenAmount,
+}
+
+/// Splits `amount` the way `paymesh` does: each member gets
+/// `amount * percentage / 100` in `u256` arithmetic, rounded down. Fails with
+/// `Overflow` where the contract's multiplication would. Percentages are
+/// expected to add up to 100.
+pub fn split(amount: TokenAmount, members: Vec<(String, u8)>) -> Result<Split, InvalidAmount> {
+    let total = amount.to_biguint();
+    let mut distributed = BigUint::ZERO;
+
+    let shares = members
+        .into_iter()
+        .map(|(member_address, percentage)| {
+            let product = TokenAmount::try_from(&total * percentage)?.to_biguint();
+            let share = product / 100u32;
+            distributed += &share;
+            Ok(Share {
+                member_address,
+                percentage,
+                amount: TokenAmount::try_from(share)?,
+            })
+        })
+        .collect::<Result<Vec<_>, InvalidAmount>>()?;
+
+    Ok(Split {
+        shares,
+        remainder: TokenAmount::try_from(total.checked_sub(&distributed).unwrap_or_default())?,
+        distributed: TokenAmount::try_from(distributed)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn members(percentages: &[u8]) -> Vec<(String, u8)> {
+        percentages
+            .iter()
+            .enumerate()
+            .map(|(i, percentage)| (format!("0x{i}"), *percentage))
+            .collect()
+    }
+
+    fn amounts(split: &Split) -> Vec<TokenAmount> {
+        split.shares.iter().map(|share| share.amount).collect()
+    }
+
+    fn max() -> BigUint {
+        (BigUint::from(1u32) << 256u32) - 1u32
+    }
+
+    #[test]
+    fn splits_33_33_34() {
+        let split = split(TokenAmount::from(1001), members(&[33, 33, 34])).unwrap();
+        assert_eq!(
+            amounts(&split),
+            [330, 330, 340].map(TokenAmount::from).to_vec()
+        );
+        assert_eq!(split.distributed, TokenAmount::from(1000));
+        assert_eq!(split.remainder, TokenAmount::from(1));
+    }
+
+    #[test]
+    fn floors_amounts_below_100() {
+        let split = split(TokenAmount::from(99), members(&[33, 33, 34])).unwrap();
+        assert_eq!(
+            amounts(&split),
+            [32, 32, 33].map(TokenAmount::from).to_vec()
+        );
+        assert_eq!(split.remainder, TokenAmount::from(2));
+
+        let split = super::split(TokenAmount::from(1), members(&[50, 50])).unwrap();
+        assert_eq!(amounts(&split), [0, 0].map(TokenAmount::from).to_vec());
+        assert_eq!(split.distributed, TokenAmount::from(0));
+        assert_eq!(split.remainder, TokenAmount::from(1));
+    }
+
+    #[test]
+    fn overflows_where_the_contract_would() {
+        let largest = max() / 100u32;
+        let split = split(
+            TokenAmount::try_from(largest.clone()).unwrap(),
+            members(&[100]),
+        )
+        .unwrap();
+        assert_eq!(split.shares[0].amount.to_biguint(), largest);
+        assert!(split.remainder.is_zero());
+
+        let too_large = TokenAmount::try_from(largest + 1u32).unwrap();
+        assert_eq!(
+            super::split(too_large, members(&[100])).unwrap_err(),
+            InvalidAmount::Overflow
+        );
+        assert_eq!(
+            super::split(TokenAmount::try_from(max()).unwrap(), members(&[1, 99])).unwrap_err(),
+            InvalidAmount::Overflow
+        );
+    }
+
+    #[test]
+    fn distributes_all_but_the_dust() {
+        for percentages in [
+            &[100][..],
+            &[50, 50],
+            &[33, 33, 34],
+            &[1, 2, 3, 94],
+            &[10; 10],
+        ] {
+            for amount in [0u128, 7, 100, 12_345, 1_000_000_000_000_000_001] {
+                let split = split(TokenAmount::from(amount), members(percentages)).unwrap();
+                for (share, percentage) in split.shares.iter().zip(percentages) {
+                    assert_eq!(
+                        share.amount,
+                        TokenAmount::from(amount * u128::from(*percentage) / 100)
+                    );
+                }
+                let total = split.distributed.to_biguint() + split.remainder.to_biguint();
+                assert_eq!(total, BigUint::from(amount));
+                assert!(split.remainder.to_biguint() < BigUint::from(percentages.len()));
+            }
+        }
+    }
+}
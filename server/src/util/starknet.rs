@@ -1,37 +1,221 @@
+use bigdecimal::BigDecimal;
 use starknet::{
     accounts::Account,
     core::{
-        types::{Call, Felt},
+        types::{BlockId, BlockTag, Call, Felt, FunctionCall},
         utils::get_selector_from_name,
     },
+    providers::Provider,
 };
+use tracing::field::Empty;
 
-use crate::util::connector::{contract_address_felt, signer_account};
+use crate::util::{
+    address::{Address, TxHash},
+    amount::TokenAmount,
+    connector::{contract_address_felt, rpc_provider, signer_account},
+};
 
-pub async fn call_paymesh_contract_function(group_address: Felt) -> Result<(), String> {
+/// Submits `paymesh(group_address)` through the relayer account and returns
+/// the transaction hash.
+#[tracing::instrument(
+    name = "contract.paymesh",
+    skip_all,
+    fields(
+        otel.kind = "client",
+        group_address = %group_address,
+        request_id = %request_id,
+        relayer_tx_hash = Empty,
+    )
+)]
+pub async fn call_paymesh_contract_function(
+    group_address: Address,
+    request_id: &str,
+) -> Result<TxHash, String> {
     let contract_address = contract_address_felt();
-    let account = signer_account();
+    let account = signer_account(request_id);
 
     let pay_call = Call {
         to: contract_address,
         selector: get_selector_from_name("paymesh").unwrap(),
-        calldata: vec![group_address],
+        calldata: vec![group_address.felt()],
     };
 
     let execute = account.execute_v3(vec![pay_call]).send().await;
 
     match execute {
         Ok(data) => {
-            tracing::info!(
-                "Transaction successful with hash: {}",
-                data.transaction_hash
-            );
-            Ok(())
+            let tx_hash = TxHash::from(data.transaction_hash);
+            tracing::Span::current().record("relayer_tx_hash", tx_hash.to_string());
+            tracing::info!("Transaction successful with hash: {}", tx_hash);
+            Ok(tx_hash)
         }
         Err(data) => {
             let message = format!("Error calling paymesh contract function: {:?}", data);
             tracing::error!(message);
-            return Err(message);
+            Err(message)
         }
     }
 }
+
+/// Calls a view function of the paymesh contract at the latest block.
+async fn call_view(
+    function: &str,
+    calldata: Vec<Felt>,
+    request_id: &str,
+) -> Result<Vec<Felt>, String> {
+    call_contract_view(contract_address_felt(), function, calldata, request_id).await
+}
+
+/// Calls a view function of any contract at the latest block.
+#[tracing::instrument(
+    name = "contract.call",
+    skip_all,
+    fields(
+        otel.kind = "client",
+        contract_address = %contract_address,
+        function = %function,
+        request_id = %request_id,
+    )
+)]
+async fn call_contract_view(
+    contract_address: Felt,
+    function: &str,
+    calldata: Vec<Felt>,
+    request_id: &str,
+) -> Result<Vec<Felt>, String> {
+    let call = FunctionCall {
+        contract_address,
+        entry_point_selector: get_selector_from_name(function).unwrap(),
+        calldata,
+    };
+
+    rpc_provider(request_id)
+        .call(call, BlockId::Tag(BlockTag::Latest))
+        .await
+        .map_err(|e| {
+            let message = format!("Error calling contract view {function}: {e:?}");
+            tracing::error!(message);
+            message
+        })
+}
+
+/// Reads a Cairo return value felt by felt.
+struct Felts(std::vec::IntoIter<Felt>);
+
+impl Felts {
+    fn next(&mut self) -> Result<Felt, String> {
+        self.0
+            .next()
+            .ok_or_else(|| "contract returned too few values".to_owned())
+    }
+
+    fn u128(&mut self) -> Result<u128, String> {
+        u128::try_from(self.next()?.to_biguint())
+            .map_err(|_| "contract returned a value wider than 128 bits".to_owned())
+    }
+
+    fn token_amount(&mut self) -> Result<TokenAmount, String> {
+        let low = self.u128()?;
+        let high = self.u128()?;
+        Ok(TokenAmount::from_words(low, high))
+    }
+
+    fn u256(&mut self) -> Result<BigDecimal, String> {
+        self.token_amount().map(BigDecimal::from)
+    }
+
+    fn array_len(&mut self) -> Result<usize, String> {
+        usize::try_from(self.u128()?).map_err(|_| "contract returned an invalid length".to_owned())
+    }
+
+    fn skip(&mut self, count: usize) -> Result<(), String> {
+        for _ in 0..count {
+            self.next()?;
+        }
+        Ok(())
+    }
+}
+
+fn u256_calldata(value: i64) -> Vec<Felt> {
+    vec![Felt::from(value), Felt::ZERO]
+}
+
+/// `get_group_usage_paid_history`: the usage bought at creation and by each
+/// top-up, oldest first.
+pub async fn get_group_usage_paid_history(
+    group_id: i64,
+    request_id: &str,
+) -> Result<Vec<BigDecimal>, String> {
+    let mut felts = Felts(
+        call_view(
+            "get_group_usage_paid_history",
+            u256_calldata(group_id),
+            request_id,
+        )
+        .await?
+        .into_iter(),
+    );
+    let len = felts.array_len()?;
+    (0..len).map(|_| felts.u256()).collect()
+}
+
+/// `get_group_usage_count`: usage remaining on-chain.
+pub async fn get_group_usage_count(group_id: i64, request_id: &str) -> Result<BigDecimal, String> {
+    Felts(
+        call_view("get_group_usage_count", u256_calldata(group_id), request_id)
+            .await?
+            .into_iter(),
+    )
+    .u256()
+}
+
+/// ERC-20 `balance_of(holder)` for `token`.
+pub async fn token_balance(
+    token: Address,
+    holder: Address,
+    request_id: &str,
+) -> Result<TokenAmount, String> {
+    Felts(
+        call_contract_view(token.felt(), "balance_of", vec![holder.felt()], request_id)
+            .await?
+            .into_iter(),
+    )
+    .token_amount()
+}
+
+/// The contract's id for `group_address`, looked up among the groups its
+/// creator made since the contract has no reverse mapping.
+pub async fn find_group_id(
+    creator: Address,
+    group_address: Address,
+    request_id: &str,
+) -> Result<Option<i64>, String> {
+    let mut felts = Felts(
+        call_view(
+            "get_groups_created_by_address",
+            vec![creator.felt()],
+            request_id,
+        )
+        .await?
+        .into_iter(),
+    );
+
+    // Group { id: u256, name: ByteArray, usage_limit_reached, creator,
+    // group_address, date: u64, total_amount: u256 }
+    for _ in 0..felts.array_len()? {
+        let id = felts.u128()?;
+        felts.skip(1)?;
+        let words = felts.array_len()?;
+        felts.skip(words + 2)?;
+        felts.skip(2)?;
+        let address = felts.next()?;
+        felts.skip(3)?;
+        if address == group_address.felt() {
+            return i64::try_from(id)
+                .map(Some)
+                .map_err(|_| "contract returned an invalid group id".to_owned());
+        }
+    }
+
+    Ok(None)
+}
@@ -0,0 +1,408 @@
+use std::{collections::BTreeMap, fmt::Write};
+
+use bigdecimal::BigDecimal;
+use serde::Serialize;
+use sqlx::PgPool;
+use time::OffsetDateTime;
+use tracing::Instrument;
+
+use crate::{
+    libs::{
+        db::query_span,
+        price::{Currency, FIAT_SCALE},
+    },
+    util::token,
+};
+
+#[derive(Debug, Serialize)]
+pub struct TokenBalance {
+    pub token_address: String,
+    pub token_symbol: Option<&'static str>,
+    pub amount: BigDecimal,
+    pub amount_formatted: Option<String>,
+    /// Fiat value of the priced distributions it sums; only with a currency.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<BigDecimal>,
+}
+
+/// Running total of one token; `value` only counts priced distributions.
+#[derive(Debug, Clone, Default)]
+struct Total {
+    amount: BigDecimal,
+    value: BigDecimal,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StatementLine {
+    pub sent_at: String,
+    pub group_address: String,
+    pub group_name: String,
+    pub token_address: String,
+    pub token_symbol: Option<&'static str>,
+    pub amount: BigDecimal,
+    pub amount_formatted: Option<String>,
+    pub tx_hash: String,
+    /// Fiat value at `sent_at`; absent without a currency or a price.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<BigDecimal>,
+}
+
+/// What a member received over `[period_start, period_end)`. `opening` is
+/// everything received before the period, `closing` is `opening` plus
+/// `received`; all three are per token.
+#[derive(Debug, Serialize)]
+pub struct Statement {
+    pub member_address: String,
+    #[serde(with = "time::serde::rfc3339")]
+    pub period_start: OffsetDateTime,
+    #[serde(with = "time::serde::rfc3339")]
+    pub period_end: OffsetDateTime,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub currency: Option<Currency>,
+    pub opening: Vec<TokenBalance>,
+    pub lines: Vec<StatementLine>,
+    pub received: Vec<TokenBalance>,
+    pub closing: Vec<TokenBalance>,
+}
+
+/// With a `currency`, each distribution is also valued at its `sent_at`
+/// from `token_prices`.
+pub async fn build(
+    db: &PgPool,
+    member_address: &str,
+    period_start: OffsetDateTime,
+    period_end: OffsetDateTime,
+    currency: Option<Currency>,
+) -> Result<Statement, sqlx::Error> {
+    let currency_code = currency.as_ref().map(|currency| currency.as_str());
+
+    let opening = sqlx::query!(
+        r#"
+        SELECT d.token_address, SUM(d.token_amount) as "amount!",
+            COALESCE(SUM(d.token_amount * px.price / power(10::numeric, px.decimals)), 0) as "value!"
+        FROM distributions_history d
+        LEFT JOIN LATERAL (
+            SELECT tp.price, tp.decimals FROM token_prices tp
+            WHERE tp.token_address = d.token_address AND tp.currency = $3
+                AND tp.priced_at <= d.sent_at
+            ORDER BY tp.priced_at DESC LIMIT 1
+        ) px ON TRUE
+        WHERE d.member_address = $1 AND d.sent_at < $2
+        GROUP BY d.token_address
+        "#,
+        member_address,
+        period_start,
+        currency_code
+    )
+    .fetch_all(db)
+    .instrument(query_span("select_statement_opening"))
+    .await?;
+
+    let rows = sqlx::query!(
+        r#"
+        SELECT d.sent_at::text as "sent_at!", d.group_address, g.group_name, d.token_address,
+            d.token_amount, d.tx_hash,
+            d.token_amount * px.price / power(10::numeric, px.decimals) as value
+        FROM distributions_history d
+        JOIN groups g ON g.group_address = d.group_address
+        LEFT JOIN LATERAL (
+            SELECT tp.price, tp.decimals FROM token_prices tp
+            WHERE tp.token_address = d.token_address AND tp.currency = $4
+                AND tp.priced_at <= d.sent_at
+            ORDER BY tp.priced_at DESC LIMIT 1
+        ) px ON TRUE
+        WHERE d.member_address = $1 AND d.sent_at >= $2 AND d.sent_at < $3
+        ORDER BY d.sent_at, d.id
+        "#,
+        member_address,
+        period_start,
+        period_end,
+        currency_code
+    )
+    .fetch_all(db)
+    .instrument(query_span("select_statement_lines"))
+    .await?;
+
+    let opening: BTreeMap<String, Total> = opening
+        .into_iter()
+        .map(|row| {
+            let total = Total {
+                amount: row.amount,
+                value: row.value,
+            };
+            (row.token_address, total)
+        })
+        .collect();
+
+    let mut received: BTreeMap<String, Total> = BTreeMap::new();
+    let lines: Vec<StatementLine> = rows
+        .into_iter()
+        .map(|row| {
+            let total = received.entry(row.token_address.clone()).or_default();
+            total.amount += &row.token_amount;
+            if let Some(value) = &row.value {
+                total.value += value;
+            }
+
+            let token = token::lookup(&row.token_address);
+            StatementLine {
+                sent_at: row.sent_at,
+                group_address: row.group_address,
+                group_name: row.group_name,
+                token_symbol: token.map(|token| token.symbol),
+                amount_formatted: token.map(|token| token.format_amount(&row.token_amount)),
+                token_address: row.token_address,
+                amount: row.token_amount,
+                tx_hash: row.tx_hash,
+                value: row.value.map(|value| value.round(FIAT_SCALE)),
+            }
+        })
+        .collect();
+
+    let mut closing = opening.clone();
+    for (token_address, received) in &received {
+        let total = closing.entry(token_address.clone()).or_default();
+        total.amount += &received.amount;
+        total.value += &received.value;
+    }
+
+    let valued = currency.is_some();
+    let balances = |totals: BTreeMap<String, Total>| {
+        totals
+            .into_iter()
+            .map(|(token_address, total)| {
+                let token = token::lookup(&token_address);
+                TokenBalance {
+                    token_symbol: token.map(|token| token.symbol),
+                    amount_formatted: token.map(|token| token.format_amount(&total.amount)),
+                    value: valued.then(|| total.value.round(FIAT_SCALE)),
+                    token_address,
+                    amount: total.amount,
+                }
+            })
+            .collect()
+    };
+
+    Ok(Statement {
+        member_address: member_address.to_owned(),
+        period_start,
+        period_end,
+        currency,
+        opening: balances(opening),
+        lines,
+        received: balances(received),
+        closing: balances(closing),
+    })
+}
+
+#[derive(Serialize)]
+struct CsvRow<'a> {
+    kind: &'static str,
+    at: String,
+    group_address: &'a str,
+    group_name: &'a str,
+    token_address: &'a str,
+    token_symbol: Option<&'static str>,
+    amount: String,
+    amount_formatted: Option<&'a str>,
+    value: Option<String>,
+    tx_hash: &'a str,
+}
+
+impl<'a> CsvRow<'a> {
+    fn balance(kind: &'static str, at: OffsetDateTime, balance: &'a TokenBalance) -> Self {
+        CsvRow {
+            kind,
+            at: format_time(at),
+            group_address: "",
+            group_name: "",
+            token_address: &balance.token_address,
+            token_symbol: balance.token_symbol,
+            amount: balance.amount.to_string(),
+            amount_formatted: balance.amount_formatted.as_deref(),
+            value: balance.value.as_ref().map(BigDecimal::to_string),
+            tx_hash: "",
+        }
+    }
+}
+
+impl Statement {
+    /// One `distribution` row per line, framed by per-token `opening` and
+    /// `closing` rows.
+    pub fn to_csv(&self) -> Result<Vec<u8>, csv::Error> {
+        let mut writer = csv::Writer::from_writer(Vec::new());
+
+        for balance in &self.opening {
+            writer.serialize(CsvRow::balance("opening", self.period_start, balance))?;
+        }
+        for line in &self.lines {
+            writer.serialize(CsvRow {
+                kind: "distribution",
+                at: line.sent_at.clone(),
+                group_address: &line.group_address,
+                group_name: &line.group_name,
+                token_address: &line.token_address,
+                token_symbol: line.token_symbol,
+                amount: line.amount.to_string(),
+                amount_formatted: line.amount_formatted.as_deref(),
+                value: line.value.as_ref().map(BigDecimal::to_string),
+                tx_hash: &line.tx_hash,
+            })?;
+        }
+        for balance in &self.closing {
+            writer.serialize(CsvRow::balance("closing", self.period_end, balance))?;
+        }
+
+        writer.into_inner().map_err(|e| e.into_error().into())
+    }
+
+    /// A standalone page meant for printing.
+    pub fn to_html(&self) -> String {
+        let mut html = String::new();
+        let _ = write!(
+            html,
+            "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+             <title>PayMesh statement {start} to {end}</title>\n\
+             <style>\n\
+             body {{ font-family: sans-serif; margin: 2rem; color: #111; }}\n\
+             table {{ border-collapse: collapse; width: 100%; margin-bottom: 1.5rem; font-size: 0.85rem; }}\n\
+             th, td {{ border-bottom: 1px solid #ccc; padding: 0.3rem 0.5rem; text-align: left; }}\n\
+             td.num {{ text-align: right; font-variant-numeric: tabular-nums; }}\n\
+             code {{ font-size: 0.75rem; word-break: break-all; }}\n\
+             @media print {{ body {{ margin: 0; }} }}\n\
+             </style>\n</head>\n<body>\n\
+             <h1>Payout statement</h1>\n\
+             <p>Member <code>{member}</code><br>Period {start} to {end} (UTC, end exclusive)</p>\n",
+            member = escape_html(&self.member_address),
+            start = format_time(self.period_start),
+            end = format_time(self.period_end),
+        );
+
+        let currency = self.currency.as_ref();
+        balance_table(&mut html, "Opening balance", &self.opening, currency);
+
+        html.push_str("<h2>Distributions</h2>\n");
+        let columns = table_head(
+            &mut html,
+            ["Time", "Group", "Token", "Amount"]
+                .into_iter()
+                .map(str::to_owned)
+                .chain(value_header(currency))
+                .chain(["Transaction".to_owned()]),
+        );
+        if self.lines.is_empty() {
+            let _ = writeln!(
+                html,
+                "<tr><td colspan=\"{columns}\">No distributions in this period.</td></tr>"
+            );
+        }
+        for line in &self.lines {
+            let _ = writeln!(
+                html,
+                "<tr><td>{}</td><td>{}<br><code>{}</code></td><td>{}</td>\
+                 <td class=\"num\">{}</td>{}<td><code>{}</code></td></tr>",
+                escape_html(&line.sent_at),
+                escape_html(&line.group_name),
+                escape_html(&line.group_address),
+                token_label(line.token_symbol, &line.token_address),
+                escape_html(
+                    line.amount_formatted
+                        .as_deref()
+                        .unwrap_or(&line.amount.to_string())
+                ),
+                value_cell(currency, line.value.as_ref()),
+                escape_html(&line.tx_hash),
+            );
+        }
+        html.push_str("</tbody>\n</table>\n");
+
+        balance_table(&mut html, "Received this period", &self.received, currency);
+        balance_table(&mut html, "Closing balance", &self.closing, currency);
+
+        html.push_str("</body>\n</html>\n");
+        html
+    }
+}
+
+fn balance_table(
+    html: &mut String,
+    title: &str,
+    balances: &[TokenBalance],
+    currency: Option<&Currency>,
+) {
+    let _ = writeln!(html, "<h2>{title}</h2>");
+    let columns = table_head(
+        html,
+        ["Token", "Amount", "Base units"]
+            .into_iter()
+            .map(str::to_owned)
+            .chain(value_header(currency)),
+    );
+    if balances.is_empty() {
+        let _ = writeln!(html, "<tr><td colspan=\"{columns}\">None</td></tr>");
+    }
+    for balance in balances {
+        let _ = writeln!(
+            html,
+            "<tr><td>{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td>{}</tr>",
+            token_label(balance.token_symbol, &balance.token_address),
+            escape_html(balance.amount_formatted.as_deref().unwrap_or("")),
+            balance.amount,
+            value_cell(currency, balance.value.as_ref()),
+        );
+    }
+    html.push_str("</tbody>\n</table>\n");
+}
+
+/// Opens a table with `columns` as its header row; returns how many there
+/// are, for cells that span the whole row.
+fn table_head(html: &mut String, columns: impl IntoIterator<Item = String>) -> usize {
+    html.push_str("<table>\n<thead><tr>");
+    let mut count = 0;
+    for column in columns {
+        let _ = write!(html, "<th>{column}</th>");
+        count += 1;
+    }
+    html.push_str("</tr></thead>\n<tbody>\n");
+    count
+}
+
+/// Only shown when a `currency` is requested.
+fn value_header(currency: Option<&Currency>) -> Option<String> {
+    currency.map(|currency| format!("Value ({})", escape_html(currency.as_str())))
+}
+
+fn value_cell(currency: Option<&Currency>, value: Option<&BigDecimal>) -> String {
+    match (currency, value) {
+        (None, _) => String::new(),
+        (Some(_), Some(value)) => format!("<td class=\"num\">{value}</td>"),
+        (Some(_), None) => "<td class=\"num\">unpriced</td>".to_owned(),
+    }
+}
+
+fn token_label(symbol: Option<&str>, address: &str) -> String {
+    match symbol {
+        Some(symbol) => escape_html(symbol),
+        None => format!("<code>{}</code>", escape_html(address)),
+    }
+}
+
+fn format_time(at: OffsetDateTime) -> String {
+    at.format(&time::format_description::well_known::Rfc3339)
+        .unwrap_or_else(|_| at.to_string())
+}
+
+fn escape_html(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
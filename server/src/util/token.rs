@@ -0,0 +1,57 @@
+use bigdecimal::BigDecimal;
+
+/// A token the contract accepts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Token {
+    pub address: &'static str,
+    pub symbol: &'static str,
+    pub decimals: u32,
+}
+
+/// Supported tokens on Starknet mainnet, addresses in stored (normalized) form.
+pub const TOKENS: [Token; 4] = [
+    Token {
+        address: "0x053c91253bc9682c04929ca02ed00b3e423f6710d2ee7e0d5ebb06f3ecf368a8",
+        symbol: "USDC",
+        decimals: 6,
+    },
+    Token {
+        address: "0x068f5c6a61780768455de69077e07e89787839bf8166decfbf92b645209c0fb8",
+        symbol: "USDT",
+        decimals: 6,
+    },
+    Token {
+        address: "0x049d36570d4e46f48e99674bd3fcc84644ddd6b96f7c741b1562b82f9e004dc7",
+        symbol: "ETH",
+        decimals: 18,
+    },
+    Token {
+        address: "0x04718f5a0fc34cc1af16a1cdee98ffb20c31f5cd61d6ab07201858f4287c938d",
+        symbol: "STRK",
+        decimals: 18,
+    },
+];
+
+pub fn lookup(address: &str) -> Option<Token> {
+    TOKENS.into_iter().find(|token| token.address == address)
+}
+
+impl Token {
+    /// Base units as a plain decimal in whole tokens, e.g. `1500000` USDC as
+    /// `1.5`.
+    pub fn format_amount(&self, amount: &BigDecimal) -> String {
+        let (units, scale) = amount.as_bigint_and_exponent();
+        let whole = BigDecimal::new(units, scale + i64::from(self.decimals));
+        let plain = whole.to_plain_string();
+        if plain.contains('.') {
+            plain.trim_end_matches('0').trim_end_matches('.').to_owned()
+        } else {
+            plain
+        }
+    }
+}
+
+/// Base units of `amount` in whole tokens when the token is known.
+pub fn format_amount(token_address: &str, amount: &BigDecimal) -> Option<String> {
+    lookup(token_address).map(|token| token.format_amount(amount))
+}
@@ -65,14 +65,14 @@ export default function (runtimeConfig: ApibaraRuntimeConfig) {
             typeof v === "bigint" ? v.toString() : v
         );
         
-        const {group_address, _, creator, name, usage_count, members} = JSON.parse(safeArgs);
+        const {group_address, group_id, creator, name, usage_count, members} = JSON.parse(safeArgs);
 
         if (!groupCache.includes(group_address)) {
           groupCache.push(group_address);
           console.log(`✅ Added group ${group_address} to cache`);
         }
         
-          create_group(group_address, creator, name, usage_count, members);
+          create_group(group_address, group_id, creator, name, usage_count, members);
         } 
         else if (eventKey === TRANSFER_SELECTOR) {
 
@@ -119,8 +119,9 @@ export default function (runtimeConfig: ApibaraRuntimeConfig) {
 
           const {group_address, usage_count} = JSON.parse(safeArgs);
 
+          let tx_hash = event.transactionHash;
 
-          subsciption_topped(group_address, Number(usage_count));
+          subsciption_topped(group_address, Number(usage_count), tx_hash);
         }
       }
     },
@@ -143,7 +144,7 @@ const store_distribution_history = (group_address: string, token_address: string
           ...members_decoupled
         ]
     });
-  fetch(`${process.env.API_URL}/store_payment_distribution_history`, {
+  fetch(`${process.env.API_URL}/v1/payments`, {
     method: "POST",
     headers: { "Content-Type": "application/json" },
     body: body
@@ -159,7 +160,7 @@ const pay = (address: string, from_address: string, tx_hash: string, amount: str
     "token_address": token_address
   });
 
-  fetch(`${process.env.API_URL}/pay_group`, {
+  fetch(`${process.env.API_URL}/v1/transfers`, {
     method: "POST",
     headers: { "Content-Type": "application/json" },
     body: body
@@ -168,14 +169,15 @@ const pay = (address: string, from_address: string, tx_hash: string, amount: str
     console.error(`Payment error for ${address}:`, err);
   });
 };
-const subsciption_topped = (group_address: string, usage_count: number) => {
+const subsciption_topped = (group_address: string, usage_count: number, tx_hash: string) => {
   let body = JSON.stringify({
       "group_address": group_address,
       "usage_count": usage_count,
+      "tx_hash": tx_hash,
     });
   console.log(`subscription topped data ${body}`)
 
-  fetch(`${process.env.API_URL}/subscription_topped`, {
+  fetch(`${process.env.API_URL}/v1/top-ups`, {
     method: "POST",
     headers: { "Content-Type": "application/json" },
     body: body
@@ -184,13 +186,14 @@ const subsciption_topped = (group_address: string, usage_count: number) => {
   });
 };
 
-const create_group = (address: string, creatorAddress: string, groupName: string, usageCount: number, members: Array<{ addr: string; percentage: number; }>) => {
+const create_group = (address: string, groupId: string, creatorAddress: string, groupName: string, usageCount: number, members: Array<{ addr: string; percentage: number; }>) => {
   let members_decoupled = members.map(member => ({
     addr: member.addr,
     percentage: Number(member.percentage)
   }));
   let body = JSON.stringify({
     "group_address": address,
+    "group_id": Number(groupId),
     "group_name": groupName,
     "created_by": creatorAddress,
     "usage_remaining": Number(usageCount),
@@ -198,7 +201,7 @@ const create_group = (address: string, creatorAddress: string, groupName: string
         ...members_decoupled
       ]
     })
-  fetch(`${process.env.API_URL}/group`, {
+  fetch(`${process.env.API_URL}/v1/groups`, {
     method: "POST",
     headers: { "Content-Type": "application/json" },
     body: body, 